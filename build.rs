@@ -0,0 +1,25 @@
+// With the `ffi` feature, writes the C header of src/ffi.rs to include/freecell.h
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        // Only src/ffi.rs is parsed, which spares a `cargo metadata` run
+        let generated = cbindgen::Config::from_file(format!("{}/cbindgen.toml", dir))
+            .map_err(|e| e.to_string())
+            .and_then(|config| {
+                cbindgen::Builder::new()
+                    .with_config(config)
+                    .with_src(format!("{}/src/ffi.rs", dir))
+                    .generate()
+                    .map_err(|e| e.to_string())
+            });
+        match generated {
+            Ok(bindings) => {
+                bindings.write_to_file(format!("{}/include/freecell.h", dir));
+            }
+            Err(e) => println!("cargo:warning=freecell.h not generated: {}", e),
+        }
+    }
+}
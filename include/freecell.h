@@ -0,0 +1,103 @@
+#ifndef FREECELL_H
+#define FREECELL_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum FreecellMoveKind {
+  FREECELL_MOVE_KIND_COL_TO_FOUNDATION,
+  FREECELL_MOVE_KIND_FREECELL_TO_FOUNDATION,
+  FREECELL_MOVE_KIND_COL_TO_FREECELL,
+  FREECELL_MOVE_KIND_FREECELL_TO_COL,
+  FREECELL_MOVE_KIND_COL_TO_COL,
+} FreecellMoveKind;
+
+typedef struct FreecellGame FreecellGame;
+
+typedef struct FreecellSolution FreecellSolution;
+
+typedef struct FreecellMove {
+  enum FreecellMoveKind kind;
+  uint32_t source;
+  uint32_t dest;
+  uint32_t pile_size;
+  char notation[8];
+} FreecellMove;
+
+/*
+ Message of the last error on this thread, NULL if none. Valid until the next
+ failing call.
+ */
+const char *freecell_last_error(void);
+
+/*
+ Microsoft FreeCell deal number, 1 to 2147483647. NULL for other numbers.
+ */
+struct FreecellGame *freecell_game_from_deal(uint32_t number);
+
+/*
+ The 52 card codes in dealing order, "AS 7D KC ...". NULL if the deck is invalid.
+
+ # Safety
+ `cards` must be NULL or a NUL terminated string.
+ */
+struct FreecellGame *freecell_game_from_cards(const char *cards);
+
+/*
+ # Safety
+ `game` must be NULL or a handle not freed yet.
+ */
+void freecell_game_free(struct FreecellGame *game);
+
+/*
+ Solves the game. `strategy` is NULL for the default one, or "astar", "dfs",
+ "weighted:N". A `time_limit_ms` of 0 means no time limit. Returns NULL on an
+ invalid argument, an unsolved game gives a solution with no move.
+
+ # Safety
+ `game` must be NULL or a live handle, `strategy` NULL or a NUL terminated string.
+ */
+struct FreecellSolution *freecell_solve(const struct FreecellGame *game,
+                                        const char *strategy,
+                                        uint32_t max_nodes,
+                                        uint32_t time_limit_ms);
+
+/*
+ # Safety
+ `solution` must be a live handle.
+ */
+bool freecell_solution_solved(const struct FreecellSolution *solution);
+
+/*
+ # Safety
+ `solution` must be a live handle.
+ */
+uint64_t freecell_solution_nodes(const struct FreecellSolution *solution);
+
+/*
+ # Safety
+ `solution` must be a live handle.
+ */
+size_t freecell_solution_len(const struct FreecellSolution *solution);
+
+/*
+ Move `index` of the solution, NULL past the end. Owned by the solution.
+
+ # Safety
+ `solution` must be a live handle.
+ */
+const struct FreecellMove *freecell_solution_move(const struct FreecellSolution *solution,
+                                                  size_t index);
+
+/*
+ # Safety
+ `solution` must be NULL or a handle not freed yet.
+ */
+void freecell_solution_free(struct FreecellSolution *solution);
+
+#endif  /* FREECELL_H */
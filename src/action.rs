@@ -1,7 +1,10 @@
-use crate::card::{Card, Suit};
 use std::fmt::Debug;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ActionType {
     ColToFoundation,
     FreecellToFoundation,
@@ -10,10 +13,78 @@ pub enum ActionType {
     ColToCol,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+impl ActionType {
+    // In declaration order, `ActionType::ALL[t as usize] == t`
+    pub const ALL: [ActionType; 5] = [
+        ActionType::ColToFoundation,
+        ActionType::FreecellToFoundation,
+        ActionType::ColToFreecell,
+        ActionType::FreecellToCol,
+        ActionType::ColToCol,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Action {
     pub action_type: ActionType,
     pub source: usize,
     pub dest: usize,
     pub pile_size: usize,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Location {
+    Column(usize),
+    Freecell(usize),
+    Foundation(usize),
+}
+
+impl Action {
+    pub fn source_location(&self) -> Location {
+        match self.action_type {
+            ActionType::ColToFoundation | ActionType::ColToFreecell | ActionType::ColToCol => {
+                Location::Column(self.source)
+            }
+            ActionType::FreecellToFoundation | ActionType::FreecellToCol => {
+                Location::Freecell(self.source)
+            }
+        }
+    }
+
+    pub fn dest_location(&self) -> Location {
+        match self.action_type {
+            ActionType::ColToFoundation | ActionType::FreecellToFoundation => {
+                Location::Foundation(self.dest)
+            }
+            ActionType::ColToFreecell => Location::Freecell(self.dest),
+            ActionType::FreecellToCol | ActionType::ColToCol => Location::Column(self.dest),
+        }
+    }
+
+    // Build the action moving `pile_size` cards between two locations, if such a move exists
+    pub fn between(source: Location, dest: Location, pile_size: usize) -> Option<Action> {
+        let (action_type, source, dest) = match (source, dest) {
+            (Location::Column(s), Location::Foundation(d)) => (ActionType::ColToFoundation, s, d),
+            (Location::Freecell(s), Location::Foundation(d)) => {
+                (ActionType::FreecellToFoundation, s, d)
+            }
+            (Location::Column(s), Location::Freecell(d)) => (ActionType::ColToFreecell, s, d),
+            (Location::Freecell(s), Location::Column(d)) => (ActionType::FreecellToCol, s, d),
+            (Location::Column(s), Location::Column(d)) => (ActionType::ColToCol, s, d),
+            _ => return None,
+        };
+
+        if action_type != ActionType::ColToCol && pile_size != 1 {
+            return None;
+        }
+
+        Some(Action {
+            action_type,
+            source,
+            dest,
+            pile_size,
+        })
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+use rayon::prelude::*;
+use serde_json::{Value, json};
+
+use crate::batch::{BatchDeal, BatchOptions, BatchResult, BatchSummary, solve_deal};
+
+// "0..4" and "0..=4" both include 4, a single number analyzes one count
+pub fn parse_cell_range(text: &str) -> Result<RangeInclusive<usize>, String> {
+    let parse = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid freecell count: {}", s))
+    };
+    let (start, end) = match text.split_once("..") {
+        Some((start, end)) => (parse(start)?, parse(end.trim_start_matches('='))?),
+        None => (parse(text)?, parse(text)?),
+    };
+    if start > end || end > 4 {
+        return Err(format!("Freecell counts must be within 0..4, got {}", text));
+    }
+    Ok(start..=end)
+}
+
+// Results of one deal, one per freecell count
+pub struct CellAnalysis {
+    pub id: String,
+    pub runs: Vec<(usize, BatchResult)>,
+}
+
+impl CellAnalysis {
+    // Fewest freecells the solver needed, a deal solved with n cells is also solvable with more
+    pub fn min_cells(&self) -> Option<usize> {
+        self.runs
+            .iter()
+            .find(|(_, result)| result.solved())
+            .map(|(cells, _)| *cells)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "deal": self.id,
+            "min_cells": self.min_cells(),
+            "runs": self
+                .runs
+                .iter()
+                .map(|(cells, result)| {
+                    let mut run = result.to_json();
+                    run["cells"] = json!(cells);
+                    run
+                })
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    // "617  min 1  | 0: limit 100000n | 1: 112m 23456n | ..."
+    pub fn to_text(&self) -> String {
+        let mut line = format!(
+            "{:<10} min {}",
+            self.id,
+            self.min_cells().map_or("-".to_string(), |c| c.to_string())
+        );
+        for (cells, result) in &self.runs {
+            match result.moves {
+                Some(moves) => {
+                    let _ = write!(line, "  | {}: {}m {}n", cells, moves, result.nodes);
+                }
+                None => {
+                    let _ = write!(line, "  | {}: {} {}n", cells, result.status(), result.nodes);
+                }
+            }
+        }
+        line
+    }
+}
+
+// Solves every deal once per freecell count of `cells`, all runs in parallel
+pub fn analyze_cells(
+    deals: &[BatchDeal],
+    cells: RangeInclusive<usize>,
+    options: BatchOptions,
+    jobs: usize,
+) -> Result<Vec<CellAnalysis>, String> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let runs: Vec<(usize, usize)> = (0..deals.len())
+        .flat_map(|deal| cells.clone().map(move |count| (deal, count)))
+        .collect();
+    let results: Vec<BatchResult> = pool.install(|| {
+        runs.par_iter()
+            .map(|&(index, count)| {
+                let deal = &deals[index];
+                let variant = BatchDeal {
+                    id: deal.id.clone(),
+                    game: deal.game.clone().map(|game| game.with_cells(count)),
+                };
+                solve_deal(&variant, options)
+            })
+            .collect()
+    });
+
+    let mut analyses: Vec<CellAnalysis> = deals
+        .iter()
+        .map(|deal| CellAnalysis {
+            id: deal.id.clone(),
+            runs: Vec::new(),
+        })
+        .collect();
+    for ((index, count), result) in runs.into_iter().zip(results) {
+        analyses[index].runs.push((count, result));
+    }
+    Ok(analyses)
+}
+
+// One summary per freecell count, to see how the effort grows as cells are removed
+pub fn summaries(analyses: &[CellAnalysis]) -> Vec<(usize, BatchSummary)> {
+    let Some(first) = analyses.first() else {
+        return Vec::new();
+    };
+    first
+        .runs
+        .iter()
+        .enumerate()
+        .map(|(i, (cells, _))| {
+            let results: Vec<BatchResult> = analyses.iter().map(|a| a.runs[i].1.clone()).collect();
+            (*cells, BatchSummary::new(&results))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::batch::parse_batch;
+    use crate::solver::{HeuristicWeights, Strategy};
+
+    #[test]
+    fn test_parse_cell_range() {
+        assert_eq!(parse_cell_range("0..4"), Ok(0..=4));
+        assert_eq!(parse_cell_range("1..=3"), Ok(1..=3));
+        assert_eq!(parse_cell_range("2"), Ok(2..=2));
+        assert!(parse_cell_range("3..1").is_err());
+        assert!(parse_cell_range("0..5").is_err());
+    }
+
+    #[test]
+    fn test_min_cells() {
+        let options = BatchOptions {
+            strategy: Strategy::Dfs,
+            max_nodes: 100_000,
+            time_limit: None,
+            weights: HeuristicWeights::default(),
+            optimize: false,
+            rate: false,
+        };
+        let analyses = analyze_cells(&parse_batch("1"), 3..=4, options, 0).unwrap();
+
+        assert_eq!(analyses[0].runs.len(), 2);
+        assert!(analyses[0].runs[1].1.solved());
+        assert!(analyses[0].min_cells().is_some());
+        assert_eq!(summaries(&analyses).len(), 2);
+    }
+}
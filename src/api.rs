@@ -0,0 +1,111 @@
+//! The part of the crate other crates are meant to build on, kept stable across minor
+//! versions: a [`SolverConfig`] with its [`Limits`], and the [`Solution`] of a search
+//! with its [`SearchStats`]. The structs are `#[non_exhaustive]`: start from
+//! `default()` and set the fields, so that new settings are not breaking changes.
+//!
+//! ```
+//! use freecell::{Game, SolverConfig, ms_deal};
+//!
+//! let mut config = SolverConfig::default();
+//! config.limits.max_nodes = 100_000;
+//! let solution = freecell::solve(&Game::new(&ms_deal(1)), &config);
+//! assert!(solution.is_solved());
+//! ```
+
+use std::time::Duration;
+
+use crate::action::Action;
+use crate::game::Game;
+use crate::solver::{HeuristicWeights, Solver, Strategy};
+use crate::stats::SearchStats;
+
+/// When a search gives up. The first limit reached stops it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Limits {
+    /// Positions explored
+    pub max_nodes: u32,
+    /// Wall-clock time
+    pub time: Option<Duration>,
+    /// Estimated bytes of the positions kept by the search
+    pub memory: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_nodes: 1_000_000,
+            time: None,
+            memory: None,
+        }
+    }
+}
+
+/// How to search: the strategy, the weights of its heuristic and the limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct SolverConfig {
+    pub strategy: Strategy,
+    pub weights: HeuristicWeights,
+    pub limits: Limits,
+}
+
+/// Result of [`solve`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Solution {
+    /// Moves from the deal to the won game, `None` if the search found none
+    pub moves: Option<Vec<Action>>,
+    /// Whether the search stopped on a limit, rather than proving there was no solution
+    pub limit_reached: bool,
+    pub stats: SearchStats,
+}
+
+impl Solution {
+    pub fn is_solved(&self) -> bool {
+        self.moves.is_some()
+    }
+}
+
+/// Searches a solution of `game`, without printing anything
+pub fn solve(game: &Game, config: &SolverConfig) -> Solution {
+    let mut solver = Solver::new(game.clone());
+    solver.verbose = false;
+    solver.strategy = config.strategy;
+    solver.weights = config.weights;
+    solver.time_limit = config.limits.time;
+    solver.memory_limit = config.limits.memory;
+    let moves = solver.solve(config.limits.max_nodes);
+    Solution {
+        moves,
+        limit_reached: solver.limit_reached,
+        stats: solver.stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+
+    #[test]
+    fn test_solve_limits() {
+        let game = Game::new(&ms_deal(1));
+        let solution = solve(&game, &SolverConfig::default());
+        assert!(solution.is_solved() && !solution.limit_reached);
+
+        let mut config = SolverConfig::default();
+        config.limits.max_nodes = 10;
+        let solution = solve(&game, &config);
+        assert!(!solution.is_solved() && solution.limit_reached);
+        assert_eq!(solution.stats.nodes_explored, 10);
+
+        // Checked before the first node, when the open list holds the deal alone
+        let mut config = SolverConfig::default();
+        config.limits.memory = Some(1);
+        let solution = solve(&game, &config);
+        assert!(solution.limit_reached);
+        assert_eq!(solution.stats.nodes_explored, 0);
+    }
+}
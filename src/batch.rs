@@ -0,0 +1,700 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use rayon::prelude::*;
+use serde_json::{Value, json};
+
+use crate::board::parse_board;
+use crate::card::Card;
+use crate::deal::{ms_deal, parse_deck};
+use crate::exit::Outcome;
+use crate::game::Game;
+use crate::i18n::Msg;
+use crate::postprocess::{SolutionStep, merge_supermoves, remove_wasted_moves};
+use crate::rate::rate_game;
+use crate::solver::{HeuristicWeights, Solver, Strategy};
+
+pub struct BatchDeal {
+    // Deal number, or the line where the deal starts for card lists and boards
+    pub id: String,
+    pub game: Result<Game, String>,
+}
+
+// Longest column there can be: 7 dealt cards, then a sequence from a queen down
+const MAX_COLUMN: usize = 19;
+
+// The first line of an fc-solve / PySolFC board: a foundations or freecells header, or
+// a column, with or without its ':' prefix
+fn starts_board(line: &str) -> bool {
+    let headers = ["Founds:", "Foundations:", "FC:", "Freecells:", ":"];
+    if headers.iter().any(|header| line.starts_with(header)) {
+        return true;
+    }
+    let cards: Vec<&str> = line.split_whitespace().collect();
+    cards.len() <= MAX_COLUMN && cards.iter().all(|card| card.parse::<Card>().is_ok())
+}
+
+// One deal per line: a Microsoft deal number or the 52 cards in dealing order, both
+// may be followed by a '#' comment. A line of board syntax starts an fc-solve /
+// PySolFC board, which runs until the next blank line. Any other line is an invalid
+// deal of its own.
+pub fn parse_batch(text: &str) -> Vec<BatchDeal> {
+    let mut deals = Vec::new();
+    let mut board: Option<(usize, String)> = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+
+        if let Some((start, block)) = &mut board {
+            if trimmed.is_empty() {
+                deals.push(BatchDeal {
+                    id: format!("line {}", start),
+                    game: parse_board(block).map_err(|e| e.to_string()),
+                });
+                board = None;
+            } else {
+                block.push_str(line);
+                block.push('\n');
+            }
+            continue;
+        }
+
+        let content = trimmed.split('#').next().unwrap_or_default().trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        if let Ok(number) = content.parse::<u32>() {
+            let game = match number {
+                1..=0x7fff_ffff => Ok(Game::new(&ms_deal(number))),
+                _ => Err(format!("Invalid deal number: {}", number)),
+            };
+            deals.push(BatchDeal {
+                id: number.to_string(),
+                game,
+            });
+        } else {
+            match parse_deck(content) {
+                Ok(deck) => deals.push(BatchDeal {
+                    id: format!("line {}", line_number),
+                    game: Ok(Game::new(&deck)),
+                }),
+                Err(_) if starts_board(content) => {
+                    board = Some((line_number, format!("{}\n", line)));
+                }
+                Err(e) => deals.push(BatchDeal {
+                    id: format!("line {}", line_number),
+                    game: Err(e.to_string()),
+                }),
+            }
+        }
+    }
+
+    if let Some((start, block)) = board {
+        deals.push(BatchDeal {
+            id: format!("line {}", start),
+            game: parse_board(&block).map_err(|e| e.to_string()),
+        });
+    }
+    deals
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BatchOptions {
+    pub strategy: Strategy,
+    pub max_nodes: u32,
+    pub time_limit: Option<Duration>,
+    pub weights: HeuristicWeights,
+    pub optimize: bool,
+    // Also grade each deal's difficulty, see `rate_game`
+    pub rate: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ReportFormat {
+    // Aligned columns for the terminal
+    #[default]
+    Text,
+    // Header line, one row per deal, summary as trailing '#' comment lines
+    Csv,
+    // One JSON object per deal, the summary as a last {"summary": ...} object
+    Jsonl,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    pub id: String,
+    pub strategy: Strategy,
+    pub outcome: Outcome,
+    pub moves: Option<usize>,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    // Why the deal could not be read, for `Outcome::InputError`
+    pub error: Option<String>,
+    // Difficulty from 1 to 5 when the batch is rated
+    pub grade: Option<u8>,
+}
+
+const CSV_HEADER: &str = "deal,solved,status,moves,nodes,time_ms,strategy,grade";
+
+const STATUSES: [(Outcome, &str); 4] = [
+    (Outcome::Solved, "solved"),
+    (Outcome::Unsolvable, "unsolvable"),
+    (Outcome::LimitReached, "limit"),
+    (Outcome::InputError, "invalid"),
+];
+
+pub(crate) fn status(outcome: Outcome) -> &'static str {
+    STATUSES
+        .iter()
+        .find(|(o, _)| *o == outcome)
+        .map_or("?", |(_, name)| name)
+}
+
+// Quotes a CSV field when needed
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+impl BatchResult {
+    pub fn solved(&self) -> bool {
+        self.outcome == Outcome::Solved
+    }
+
+    pub fn status(&self) -> &'static str {
+        status(self.outcome)
+    }
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{:.3},{},{}",
+            csv_field(&self.id),
+            self.solved(),
+            status(self.outcome),
+            self.moves.map_or(String::new(), |m| m.to_string()),
+            self.nodes,
+            self.elapsed.as_secs_f64() * 1000.0,
+            self.strategy,
+            self.grade.map_or(String::new(), |g| g.to_string())
+        )
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "deal": self.id,
+            "solved": self.solved(),
+            "status": status(self.outcome),
+            "moves": self.moves,
+            "nodes": self.nodes,
+            "time_ms": self.elapsed.as_secs_f64() * 1000.0,
+            "strategy": self.strategy.to_string(),
+            "error": self.error,
+            "grade": self.grade,
+        })
+    }
+
+    // Reads back a line of the jsonl report
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let field = |name: &str| value.get(name).ok_or(format!("Missing field: {}", name));
+        let text = |name: &str| {
+            field(name)?
+                .as_str()
+                .ok_or(format!("Field {} is not a string", name))
+        };
+
+        let status = text("status")?;
+        let outcome = STATUSES
+            .iter()
+            .find(|(_, name)| *name == status)
+            .map(|(o, _)| *o)
+            .ok_or(format!("Unknown status: {}", status))?;
+
+        Ok(BatchResult {
+            id: text("deal")?.to_string(),
+            strategy: text("strategy")?.parse()?,
+            outcome,
+            moves: field("moves")?.as_u64().map(|m| m as usize),
+            nodes: field("nodes")?.as_u64().unwrap_or(0),
+            elapsed: Duration::from_secs_f64(field("time_ms")?.as_f64().unwrap_or(0.0) / 1000.0),
+            error: value.get("error").and_then(Value::as_str).map(String::from),
+            grade: value.get("grade").and_then(Value::as_u64).map(|g| g as u8),
+        })
+    }
+
+    pub fn format(&self, report: ReportFormat) -> String {
+        match report {
+            ReportFormat::Text => self.to_string(),
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Jsonl => self.to_json().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for BatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = status(self.outcome);
+        if let Some(error) = &self.error {
+            return write!(f, "{:<10} {:<10} {}", self.id, status, error);
+        }
+        write!(
+            f,
+            "{:<10} {:<10} {:>5} moves {:>9} nodes {:>10.2?}",
+            self.id,
+            status,
+            self.moves.map_or("-".to_string(), |m| m.to_string()),
+            self.nodes,
+            self.elapsed
+        )?;
+        match self.grade {
+            Some(grade) => write!(f, "  grade {}", grade),
+            None => Ok(()),
+        }
+    }
+}
+
+// Nearest-rank percentile of sorted values
+fn percentile<T: Copy + Default>(sorted: &[T], p: usize) -> T {
+    if sorted.is_empty() {
+        return T::default();
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// Node counts are bucketed by power of ten, the last bucket holding 10^7 and more
+const HISTOGRAM_BUCKETS: usize = 8;
+const SLOWEST_DEALS: usize = 5;
+
+// One character per value, scaled to the largest, a blank for zero
+pub fn sparkline(values: &[usize]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&v| match v {
+            0 => ' ',
+            _ => SPARK_LEVELS[(v * SPARK_LEVELS.len()).div_ceil(max) - 1],
+        })
+        .collect()
+}
+
+fn node_bucket(nodes: u64) -> usize {
+    (nodes.max(1).ilog10() as usize).min(HISTOGRAM_BUCKETS - 1)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchSummary {
+    pub deals: usize,
+    pub solved: usize,
+    pub invalid: usize,
+    // p50, p90, p99 and max over the deals that could be read
+    pub time_ms: [f64; 4],
+    pub nodes: [u64; 4],
+    // Same over the solved deals
+    pub moves: [usize; 4],
+    pub mean_moves: f64,
+    // Deals per power of ten of explored nodes: 1-9, 10-99, ...
+    pub node_histogram: [usize; HISTOGRAM_BUCKETS],
+    // Deal ids and times in ms, the slowest first
+    pub slowest: Vec<(String, f64)>,
+}
+
+impl BatchSummary {
+    pub fn new(results: &[BatchResult]) -> Self {
+        let attempted: Vec<&BatchResult> = results
+            .iter()
+            .filter(|r| r.outcome != Outcome::InputError)
+            .collect();
+
+        let mut times: Vec<f64> = attempted
+            .iter()
+            .map(|r| r.elapsed.as_secs_f64() * 1000.0)
+            .collect();
+        times.sort_by(f64::total_cmp);
+        let mut nodes: Vec<u64> = attempted.iter().map(|r| r.nodes).collect();
+        nodes.sort_unstable();
+        let mut moves: Vec<usize> = results.iter().filter_map(|r| r.moves).collect();
+        moves.sort_unstable();
+
+        let mut node_histogram = [0; HISTOGRAM_BUCKETS];
+        for &n in &nodes {
+            node_histogram[node_bucket(n)] += 1;
+        }
+        let mut slowest: Vec<(String, f64)> = attempted
+            .iter()
+            .map(|r| (r.id.clone(), r.elapsed.as_secs_f64() * 1000.0))
+            .collect();
+        slowest.sort_by(|a, b| b.1.total_cmp(&a.1));
+        slowest.truncate(SLOWEST_DEALS);
+
+        BatchSummary {
+            deals: results.len(),
+            solved: moves.len(),
+            invalid: results.len() - attempted.len(),
+            time_ms: [50, 90, 99, 100].map(|p| percentile(&times, p)),
+            nodes: [50, 90, 99, 100].map(|p| percentile(&nodes, p)),
+            moves: [50, 90, 99, 100].map(|p| percentile(&moves, p)),
+            mean_moves: match moves.len() {
+                0 => 0.0,
+                n => moves.iter().sum::<usize>() as f64 / n as f64,
+            },
+            node_histogram,
+            slowest,
+        }
+    }
+
+    // Solved deals among those that could be read
+    pub fn solve_rate(&self) -> f64 {
+        let attempted = self.deals - self.invalid;
+        if attempted == 0 {
+            return 0.0;
+        }
+        self.solved as f64 / attempted as f64
+    }
+
+    pub fn to_json(&self) -> Value {
+        let quantiles = |values: [f64; 4]| json!({ "p50": values[0], "p90": values[1], "p99": values[2], "max": values[3] });
+        json!({
+            "deals": self.deals,
+            "solved": self.solved,
+            "invalid": self.invalid,
+            "solve_rate": self.solve_rate(),
+            "time_ms": quantiles(self.time_ms),
+            "nodes": quantiles(self.nodes.map(|n| n as f64)),
+            "moves": quantiles(self.moves.map(|m| m as f64)),
+            "mean_moves": self.mean_moves,
+            "node_histogram": self.node_histogram,
+            "slowest": self
+                .slowest
+                .iter()
+                .map(|(deal, time_ms)| json!({ "deal": deal, "time_ms": time_ms }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn format(&self, report: ReportFormat) -> String {
+        match report {
+            ReportFormat::Text => self.to_string(),
+            ReportFormat::Csv => self
+                .to_string()
+                .lines()
+                .map(|line| format!("# {}", line))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ReportFormat::Jsonl => json!({ "summary": self.to_json() }).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "solved {}/{} ({:.1}%), {} invalid",
+            self.solved,
+            self.deals - self.invalid,
+            self.solve_rate() * 100.0,
+            self.invalid
+        )?;
+        writeln!(
+            f,
+            "{:<8} {:>10} {:>10} {:>10} {:>10}",
+            "", "p50", "p90", "p99", "max"
+        )?;
+        let [p50, p90, p99, max] = self.time_ms;
+        writeln!(
+            f,
+            "{:<8} {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+            "time ms", p50, p90, p99, max
+        )?;
+        let [p50, p90, p99, max] = self.nodes;
+        writeln!(
+            f,
+            "{:<8} {:>10} {:>10} {:>10} {:>10}",
+            "nodes", p50, p90, p99, max
+        )?;
+        let [p50, p90, p99, max] = self.moves;
+        writeln!(
+            f,
+            "{:<8} {:>10} {:>10} {:>10} {:>10}   mean {:.1}",
+            "moves", p50, p90, p99, max, self.mean_moves
+        )?;
+        writeln!(
+            f,
+            "{:<8} 1 |{}| 10M+ nodes   {:?}",
+            "histo",
+            sparkline(&self.node_histogram),
+            self.node_histogram
+        )?;
+        let slowest: Vec<String> = self
+            .slowest
+            .iter()
+            .map(|(deal, time_ms)| format!("{} ({:.1} ms)", deal, time_ms))
+            .collect();
+        write!(f, "{:<8} {}", "slowest", slowest.join(", "))
+    }
+}
+
+pub fn solve_deal(deal: &BatchDeal, options: BatchOptions) -> BatchResult {
+    let game = match &deal.game {
+        Ok(game) => game,
+        Err(e) => {
+            return BatchResult {
+                id: deal.id.clone(),
+                outcome: Outcome::InputError,
+                moves: None,
+                nodes: 0,
+                elapsed: Duration::ZERO,
+                strategy: options.strategy,
+                error: Some(e.clone()),
+                grade: None,
+            };
+        }
+    };
+
+    let mut result = solve_game(&deal.id, game, options).0;
+    if options.rate {
+        result.grade = Some(rate_game(game, options).grade);
+    }
+    result
+}
+
+// Same as `solve_deal`, the solution is also returned
+pub fn solve_game(
+    id: &str,
+    game: &Game,
+    options: BatchOptions,
+) -> (BatchResult, Option<Vec<SolutionStep>>) {
+    let now = Instant::now();
+    let mut solver = Solver::new(game.clone());
+    solver.strategy = options.strategy;
+    solver.time_limit = options.time_limit;
+    solver.weights = options.weights;
+    solver.verbose = false;
+    let solution = solver.solve(options.max_nodes).map(|mut solution| {
+        if options.optimize {
+            solution = remove_wasted_moves(game, &solution);
+        }
+        merge_supermoves(game, &solution)
+    });
+
+    let result = BatchResult {
+        id: id.to_string(),
+        strategy: options.strategy,
+        outcome: match &solution {
+            Some(_) => Outcome::Solved,
+            None if solver.limit_reached => Outcome::LimitReached,
+            None => Outcome::Unsolvable,
+        },
+        moves: solution.as_ref().map(Vec::len),
+        nodes: solver.stats.nodes_explored,
+        elapsed: now.elapsed(),
+        error: None,
+        grade: None,
+    };
+    (result, solution)
+}
+
+// Finished deals are appended to the checkpoint file as jsonl report lines, `every`
+// deals at a time, so that an interrupted run restarted with the same file skips them
+pub struct Checkpoint {
+    path: String,
+    every: usize,
+    pending: Vec<String>,
+}
+
+impl Checkpoint {
+    pub fn new(path: &str, every: usize) -> Self {
+        Checkpoint {
+            path: path.to_string(),
+            every: every.max(1),
+            pending: Vec::new(),
+        }
+    }
+
+    // Results saved by previous runs. Lines that can't be read, like one cut short
+    // when the run was killed, are dropped and their deal will be solved again.
+    pub fn load(&self) -> Result<Vec<BatchResult>, String> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("{}: {}", self.path, e)),
+        };
+        Ok(text
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter_map(|value| BatchResult::from_json(&value).ok())
+            .collect())
+    }
+
+    fn record(&mut self, result: &BatchResult) -> Result<(), String> {
+        self.pending.push(result.to_json().to_string());
+        if self.pending.len() >= self.every {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("{}: {}", self.path, e))?;
+        for line in self.pending.drain(..) {
+            writeln!(file, "{}", line).map_err(|e| format!("{}: {}", self.path, e))?;
+        }
+        Ok(())
+    }
+}
+
+// Spreads the deals over `jobs` worker threads (0 for one per core). A report line is
+// printed as soon as a deal is done (unless `report` is None), so in completion order,
+// while the returned results keep the input order, after the ones restored from the
+// checkpoint.
+pub fn run_batch(
+    deals: &[BatchDeal],
+    options: BatchOptions,
+    jobs: usize,
+    report: Option<ReportFormat>,
+    checkpoint: Option<Checkpoint>,
+) -> Result<Vec<BatchResult>, String> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut results = match &checkpoint {
+        Some(checkpoint) => checkpoint.load()?,
+        None => Vec::new(),
+    };
+    let done: HashSet<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    let remaining: Vec<&BatchDeal> = deals
+        .iter()
+        .filter(|deal| !done.contains(deal.id.as_str()))
+        .collect();
+    if !results.is_empty() && report.is_some() {
+        eprintln!("{}", Msg::BatchResuming(results.len(), remaining.len()));
+    }
+
+    if report == Some(ReportFormat::Csv) {
+        println!("{}", CSV_HEADER);
+    }
+    let checkpoint = checkpoint.map(Mutex::new);
+    let solved: Vec<BatchResult> = pool.install(|| {
+        remaining
+            .par_iter()
+            .map(|deal| {
+                let result = solve_deal(deal, options);
+                if let Some(report) = report {
+                    println!("{}", result.format(report));
+                }
+                if let Some(checkpoint) = &checkpoint
+                    && let Err(e) = checkpoint.lock().unwrap().record(&result)
+                {
+                    eprintln!("{}", Msg::CheckpointNotRecorded(&e));
+                }
+                result
+            })
+            .collect()
+    });
+
+    if let Some(checkpoint) = checkpoint {
+        checkpoint.into_inner().unwrap().flush()?;
+    }
+    results.extend(solved);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::board::to_pysol;
+
+    #[test]
+    fn test_parse_batch() {
+        let cards: Vec<String> = ms_deal(5).iter().map(|c| c.to_string()).collect();
+        let text = format!(
+            "# deals\n617 # the classic\n\n{}\n{}\n{}",
+            cards.join(" "),
+            to_pysol(&Game::new(&ms_deal(1))),
+            "0\n"
+        );
+
+        let deals = parse_batch(&text);
+        let ids: Vec<&str> = deals.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, ["617", "line 4", "line 5", "0"]);
+        assert_eq!(
+            deals[2].game.as_ref().unwrap().layout_key(),
+            Game::new(&ms_deal(1)).layout_key()
+        );
+        assert!(deals[3].game.is_err());
+
+        // One bad card, the deals after it still count
+        let text = format!("1\n{} XX\n2\n3\n", cards[..51].join(" "));
+        let deals = parse_batch(&text);
+        let ids: Vec<&str> = deals.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, ["1", "line 2", "2", "3"]);
+        assert!(deals[1].game.is_err());
+        assert!(deals[2].game.is_ok() && deals[3].game.is_ok());
+    }
+
+    fn result(id: &str, outcome: Outcome, moves: Option<usize>, ms: u64) -> BatchResult {
+        BatchResult {
+            id: id.to_string(),
+            strategy: Strategy::AStar,
+            outcome,
+            moves,
+            nodes: ms * 10,
+            elapsed: Duration::from_millis(ms),
+            error: None,
+            grade: None,
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut results: Vec<BatchResult> = (1..=9)
+            .map(|i| result(&i.to_string(), Outcome::Solved, Some(80 + i as usize), i))
+            .collect();
+        results.push(result("10", Outcome::LimitReached, None, 100));
+        results.push(result("11", Outcome::InputError, None, 0));
+
+        let summary = BatchSummary::new(&results);
+        assert_eq!((summary.deals, summary.solved, summary.invalid), (11, 9, 1));
+        assert_eq!(summary.solve_rate(), 0.9);
+        assert_eq!(summary.nodes, [50, 90, 1000, 1000]);
+        assert_eq!(summary.moves, [85, 89, 89, 89]);
+        assert_eq!(summary.mean_moves, 85.0);
+        assert_eq!(summary.node_histogram, [0, 9, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(summary.slowest[0], ("10".to_string(), 100.0));
+        assert_eq!(summary.slowest.len(), 5);
+        assert_eq!(sparkline(&summary.node_histogram), " █ ▁    ");
+
+        assert_eq!(results[9].to_csv(), "10,false,limit,,1000,100.000,astar,");
+    }
+
+    #[test]
+    fn test_result_json_round_trip() {
+        let original = BatchResult {
+            grade: Some(4),
+            ..result("617", Outcome::Solved, Some(104), 250)
+        };
+        let restored = BatchResult::from_json(&original.to_json()).unwrap();
+        assert_eq!(restored.to_csv(), original.to_csv());
+    }
+}
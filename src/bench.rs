@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use crate::batch::{BatchDeal, BatchOptions, BatchResult, solve_deal};
+use crate::deal::ms_deal;
+use crate::game::Game;
+use crate::i18n::Msg;
+use crate::output::OutputMode;
+use crate::solver::{HeuristicWeights, Strategy};
+
+// Microsoft deals picked from how long the solvers take on them, keep them fixed
+// so that numbers from different commits can be compared
+const BENCH_DEALS: [(u32, &str); 9] = [
+    (1, "easy"),
+    (164, "easy"),
+    (9998, "easy"),
+    (5, "medium"),
+    (285, "medium"),
+    (1941, "medium"),
+    (10, "hard"),
+    (178, "hard"),
+    (617, "hard"),
+];
+
+// Solves every bench deal with every strategy, one after the other so that timings
+// are not skewed by other work, and prints a comparison table (or a JSON array of
+// the results with --format json)
+pub fn run_bench(
+    max_nodes: u32,
+    time_limit: Duration,
+    weights: HeuristicWeights,
+    mode: OutputMode,
+) -> Vec<BatchResult> {
+    let table = mode == OutputMode::Text;
+    if table {
+        println!(
+            "{:<6} {:<7} {:<6} {:<11} {:>9} {:>10} {:>6}",
+            "deal", "level", "algo", "status", "nodes", "time", "moves"
+        );
+    }
+
+    let mut results = Vec::new();
+    for (number, level) in BENCH_DEALS {
+        let deal = BatchDeal {
+            id: number.to_string(),
+            game: Ok(Game::new(&ms_deal(number))),
+        };
+        for strategy in Strategy::ALL {
+            let options = BatchOptions {
+                strategy,
+                max_nodes,
+                time_limit: Some(time_limit),
+                weights,
+                optimize: true,
+                rate: false,
+            };
+            let result = solve_deal(&deal, options);
+            if !table {
+                results.push(result);
+                continue;
+            }
+            println!(
+                "{:<6} {:<7} {:<6} {:<11} {:>9} {:>10.2?} {:>6}",
+                number,
+                level,
+                strategy,
+                result.status(),
+                result.nodes,
+                result.elapsed,
+                result.moves.map_or("-".to_string(), |m| m.to_string())
+            );
+            results.push(result);
+        }
+    }
+
+    if mode == OutputMode::Json {
+        let json: Vec<_> = results.iter().map(BatchResult::to_json).collect();
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    }
+    if !table {
+        return results;
+    }
+
+    println!();
+    for strategy in Strategy::ALL {
+        let runs: Vec<&BatchResult> = results.iter().filter(|r| r.strategy == strategy).collect();
+        let solved: Vec<&&BatchResult> = runs.iter().filter(|r| r.solved()).collect();
+        println!(
+            "{}",
+            Msg::BenchTotal(
+                &strategy,
+                solved.len(),
+                runs.len(),
+                runs.iter().map(|r| r.nodes).sum::<u64>(),
+                runs.iter().map(|r| r.elapsed).sum::<Duration>(),
+                solved.iter().filter_map(|r| r.moves).sum::<usize>()
+            )
+        );
+    }
+    results
+}
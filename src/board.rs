@@ -0,0 +1,210 @@
+use std::fmt::Write;
+
+use crate::card::{Card, Suit};
+use crate::deal::validate_deck;
+use crate::error::{CardError, SolverError};
+use crate::game::Game;
+
+// Text layout used by PySolFC's "Export current game" and its solver interface:
+//
+//   Founds: D-0 C-A S-0 H-2
+//   FC: - 9S - -
+//   : 4C 2C 9C 8C QS 4S 2H
+//   : ...
+//
+// Columns are listed bottom card first, one per line.
+pub fn to_pysol(game: &Game) -> String {
+    let mut out = String::from("Founds:");
+    for (suit, &count) in Suit::ALL.iter().zip(game.foundations.iter()) {
+        let rank = match count {
+            0 => '0',
+            _ => Card {
+                rank: count,
+                suit: *suit,
+            }
+            .rank_char(),
+        };
+        let _ = write!(out, " {}-{}", suit.to_char(), rank);
+    }
+
+    out.push_str("\nFC:");
+    for cell in &game.freecells {
+        match cell {
+            Some(card) => {
+                let _ = write!(out, " {}", card);
+            }
+            None => out.push_str(" -"),
+        }
+    }
+    out.push('\n');
+
+    for column in &game.columns {
+        out.push(':');
+        for card in column {
+            let _ = write!(out, " {}", card);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn parse_suit(c: char) -> Result<Suit, SolverError> {
+    Suit::ALL
+        .into_iter()
+        .find(|suit| suit.to_char() == c.to_ascii_uppercase())
+        .ok_or(SolverError::Card(CardError::InvalidSuit(c)))
+}
+
+fn parse_foundations(text: &str, game: &mut Game) -> Result<(), SolverError> {
+    for token in text.split_whitespace() {
+        let (suit, rank) = token
+            .split_once('-')
+            .ok_or_else(|| SolverError::Board(format!("Invalid foundation: {}", token)))?;
+        let mut suit_chars = suit.chars();
+        let (Some(suit_char), None) = (suit_chars.next(), suit_chars.next()) else {
+            return Err(SolverError::Board(format!("Invalid foundation: {}", token)));
+        };
+        let suit = parse_suit(suit_char)?;
+        game.foundations[suit as usize] = match rank {
+            "0" => 0,
+            _ => format!("{}{}", rank, suit_char).parse::<Card>()?.rank,
+        };
+    }
+    Ok(())
+}
+
+fn parse_freecells(text: &str, game: &mut Game) -> Result<(), SolverError> {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    if tokens.len() > game.freecells.len() {
+        return Err(SolverError::Board(format!(
+            "Too many freecells: {}",
+            tokens.len()
+        )));
+    }
+    for (cell, token) in game.freecells.iter_mut().zip(tokens) {
+        *cell = match token {
+            "-" => None,
+            _ => Some(token.parse()?),
+        };
+    }
+    Ok(())
+}
+
+// Every card must be somewhere exactly once, cards below a foundation's top included
+pub(crate) fn check_cards(game: &Game) -> Result<(), SolverError> {
+    let founded = Suit::ALL
+        .iter()
+        .zip(game.foundations.iter())
+        .flat_map(|(&suit, &count)| (1..=count).map(move |rank| Card { rank, suit }));
+    let placed = game
+        .columns
+        .iter()
+        .flatten()
+        .chain(game.freecells.iter().flatten())
+        .copied();
+    validate_deck(&founded.chain(placed).collect::<Vec<_>>())
+}
+
+// Reads a position written by `to_pysol` (or saved from PySolFC) as well as the
+// fc-solve input format, which spells the headers "Foundations:" and "Freecells:".
+// Both headers are optional, empty lines and lines starting with '#' are ignored.
+pub fn parse_board(text: &str) -> Result<Game, SolverError> {
+    let mut game = Game::new(&[]);
+    let mut column = 0;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line
+            .strip_prefix("Founds:")
+            .or_else(|| line.strip_prefix("Foundations:"))
+        {
+            parse_foundations(rest, &mut game)?;
+        } else if let Some(rest) = line
+            .strip_prefix("FC:")
+            .or_else(|| line.strip_prefix("Freecells:"))
+        {
+            parse_freecells(rest, &mut game)?;
+        } else {
+            if column >= game.columns.len() {
+                return Err(SolverError::Board(format!(
+                    "Too many columns, unexpected line: {}",
+                    line
+                )));
+            }
+            let cards = line.strip_prefix(':').unwrap_or(line);
+            game.columns[column] = cards
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?;
+            column += 1;
+        }
+    }
+
+    check_cards(&game)?;
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::action::{Action, Location};
+
+    fn sample_game() -> Game {
+        let deck: Vec<Card> = (0..52)
+            .map(|i| Card {
+                rank: (i % 13) + 1,
+                suit: Suit::ALL[(i / 13) as usize],
+            })
+            .collect();
+        Game::new(&deck)
+    }
+
+    #[test]
+    fn test_pysol_round_trip() {
+        let mut game = sample_game();
+        // Not a fresh deal anymore: one card sits in a freecell
+        game.apply(&Action::between(Location::Column(0), Location::Freecell(1), 1).unwrap())
+            .unwrap();
+
+        let text = to_pysol(&game);
+        assert!(text.starts_with("Founds: D-0 C-0 S-0 H-0\nFC: - "));
+        assert_eq!(parse_board(&text).unwrap().layout_key(), game.layout_key());
+    }
+
+    #[test]
+    fn test_parse_board_rejects_missing_cards() {
+        let text = to_pysol(&sample_game());
+        let truncated: String = text.lines().take(5).collect::<Vec<_>>().join("\n");
+        assert!(matches!(
+            parse_board(&truncated),
+            Err(SolverError::IncompleteDeck { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_fc_solve_board() {
+        let text = "\
+Foundations: H-0 C-0 D-A S-0
+Freecells:  8H  -
+4C 2C 9C 8C QS 4S 2H
+5H QH 3C AC 3H 4H QD
+QC 9S 6H 9H 3S KS 3D
+5D 2S JC 5C JH 6D AS
+2D KD 10H 10C KC 7S
+7H JD 7D 6S 10S 8S
+KH 6C 4D 10D 8D
+JS 9D AH 5S 7C
+";
+        let game = parse_board(text).unwrap();
+        assert_eq!(game.foundations[Suit::Diamond as usize], 1);
+        assert_eq!(game.freecells[0], Some(Card::try_from("8H").unwrap()));
+        assert_eq!(game.freecells[1], None);
+        assert_eq!(game.columns[4][2], Card::try_from("TH").unwrap());
+        assert_eq!(game.columns[7].len(), 5);
+    }
+}
@@ -1,4 +1,14 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::error::CardError;
+
+const RANK_CHARS: [char; 13] = [
+    'A', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K',
+];
 
 #[non_exhaustive]
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -10,20 +20,37 @@ pub enum Suit {
     Heart = 3,
 }
 
-#[derive(Clone, PartialEq, Eq, Copy)]
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Diamond, Suit::Club, Suit::Spade, Suit::Heart];
+
+    pub fn to_char(self) -> char {
+        match self {
+            Suit::Diamond => 'D',
+            Suit::Club => 'C',
+            Suit::Spade => 'S',
+            Suit::Heart => 'H',
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Copy, Hash)]
 pub struct Card {
     pub rank: u8,
     pub suit: Suit,
 }
 
 impl Card {
+    pub fn rank_char(&self) -> char {
+        RANK_CHARS[(self.rank - 1) as usize]
+    }
+
     pub fn is_black(&self) -> bool {
-        self.suit == Suit::Diamond || self.suit == Suit::Heart
+        self.suit == Suit::Club || self.suit == Suit::Spade
     }
 
     #[allow(dead_code)]
     pub fn encode(&self) -> u8 {
-        ((self.suit as u8) << 4) as u8 + self.rank
+        ((self.suit as u8) << 4) + self.rank
     }
 
     #[allow(dead_code)]
@@ -40,6 +67,13 @@ impl Card {
     }
 }
 
+// Two-character code used by fc-solve and most FreeCell tools: "AS", "TD", "KH"
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.rank_char(), self.suit.to_char())
+    }
+}
+
 impl Debug for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -62,19 +96,63 @@ impl Debug for Card {
     }
 }
 
-impl From<&str> for Card {
-    fn from(txt: &str) -> Self {
-        let (r, s) = txt.split_at(txt.len() - 1);
-        let rank = r.parse::<u8>().unwrap();
-        let suit = match s.chars().next() {
-            Some('D') => Suit::Diamond,
-            Some('C') => Suit::Club,
-            Some('S') => Suit::Spade,
-            Some('H') => Suit::Heart,
-            _ => panic!("Invalid suit character: {}", s),
+// Accepts the two-character codes ("AS", "TD", "kh") as well as numeric ranks ("1S", "10D")
+impl FromStr for Card {
+    type Err = CardError;
+
+    fn from_str(txt: &str) -> Result<Self, Self::Err> {
+        let txt = txt.trim();
+        let Some(s) = txt.chars().last() else {
+            return Err(CardError::Empty);
         };
+        let r = &txt[..txt.len() - s.len_utf8()];
 
-        Card { rank, suit }
+        let rank = match r.to_ascii_uppercase().as_str() {
+            "A" => 1,
+            "T" => 10,
+            "J" => 11,
+            "Q" => 12,
+            "K" => 13,
+            n => n
+                .parse::<u8>()
+                .ok()
+                .filter(|rank| (1..=13).contains(rank))
+                .ok_or_else(|| CardError::InvalidRank(txt.to_string()))?,
+        };
+        let suit = match s.to_ascii_uppercase() {
+            'D' => Suit::Diamond,
+            'C' => Suit::Club,
+            'S' => Suit::Spade,
+            'H' => Suit::Heart,
+            _ => return Err(CardError::InvalidSuit(s)),
+        };
+
+        Ok(Card { rank, suit })
+    }
+}
+
+// Serialized as the two-character code, "AS" rather than a rank/suit object
+#[cfg(feature = "serde")]
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl TryFrom<&str> for Card {
+    type Error = CardError;
+
+    fn try_from(txt: &str) -> Result<Self, Self::Error> {
+        txt.parse()
     }
 }
 
@@ -83,6 +161,46 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_card_display() {
+        assert_eq!(Card::try_from("1S").unwrap().to_string(), "AS");
+        assert_eq!(Card::try_from("10D").unwrap().to_string(), "TD");
+        assert_eq!(Card::try_from("13H").unwrap().to_string(), "KH");
+        assert_eq!(Card::try_from("7C").unwrap().to_string(), "7C");
+    }
+
+    #[test]
+    fn test_card_color() {
+        let black = |code: &str| code.parse::<Card>().unwrap().is_black();
+        assert!(black("AC"));
+        assert!(black("KS"));
+        assert!(!black("AD"));
+        assert!(!black("KH"));
+    }
+
+    #[test]
+    fn test_card_parse() {
+        assert_eq!("AS".parse::<Card>(), Ok(Card::try_from("1S").unwrap()));
+        assert_eq!("td".parse::<Card>(), Ok(Card::try_from("10D").unwrap()));
+        assert_eq!("KH".parse::<Card>(), Ok(Card::try_from("13H").unwrap()));
+        assert!("14H".parse::<Card>().is_err());
+        assert_eq!("5X".parse::<Card>(), Err(CardError::InvalidSuit('X')));
+        assert!("".parse::<Card>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_card_serde() {
+        let cards = vec![
+            Card::try_from("AS").unwrap(),
+            Card::try_from("10D").unwrap(),
+        ];
+        let json = serde_json::to_string(&cards).unwrap();
+        assert_eq!(json, r#"["AS","TD"]"#);
+        assert_eq!(serde_json::from_str::<Vec<Card>>(&json).unwrap(), cards);
+        assert!(serde_json::from_str::<Card>(r#""1X""#).is_err());
+    }
+
     // #[test]
     // fn test_card_can_stack() {
     //     let card1 = Card {
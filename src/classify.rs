@@ -0,0 +1,105 @@
+// Size every rank glyph is scaled down to before being compared
+pub const GLYPH_WIDTH: usize = 12;
+pub const GLYPH_HEIGHT: usize = 16;
+
+// A grayscale glyph scaled down to GLYPH_WIDTH x GLYPH_HEIGHT by averaging, ink
+// towards 1 whatever the brightness of the picture
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphFeatures(Vec<f64>);
+
+impl GlyphFeatures {
+    // `pixels` holds `width * height` gray levels, row by row
+    pub fn new(pixels: &[u8], width: usize, height: usize) -> Self {
+        let mut sums = vec![0.0; GLYPH_WIDTH * GLYPH_HEIGHT];
+        let mut counts = vec![0usize; GLYPH_WIDTH * GLYPH_HEIGHT];
+        for y in 0..height {
+            for x in 0..width {
+                let cell = (y * GLYPH_HEIGHT / height) * GLYPH_WIDTH + x * GLYPH_WIDTH / width;
+                sums[cell] += 1.0 - pixels[y * width + x] as f64 / 255.0;
+                counts[cell] += 1;
+            }
+        }
+        GlyphFeatures(
+            sums.iter()
+                .zip(counts)
+                .map(|(sum, count)| if count == 0 { 0.0 } else { sum / count as f64 })
+                .collect(),
+        )
+    }
+
+    // Normalized cross-correlation, from -1 to 1, like OpenCV's TM_CCOEFF_NORMED
+    pub fn correlation(&self, other: &GlyphFeatures) -> f64 {
+        let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+        let (mean_a, mean_b) = (mean(&self.0), mean(&other.0));
+        let (mut cross, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+        for (a, b) in self.0.iter().zip(&other.0) {
+            cross += (a - mean_a) * (b - mean_b);
+            var_a += (a - mean_a) * (a - mean_a);
+            var_b += (b - mean_b) * (b - mean_b);
+        }
+        if var_a == 0.0 || var_b == 0.0 {
+            return 0.0;
+        }
+        cross / (var_a * var_b).sqrt()
+    }
+}
+
+// Nearest-neighbour rank reader, learned from the rank corners of the card templates:
+// a glyph gets the rank of the reference it correlates best with
+#[derive(Debug, Clone, Default)]
+pub struct RankClassifier {
+    references: Vec<(u8, GlyphFeatures)>,
+}
+
+impl RankClassifier {
+    pub fn learn(&mut self, rank: u8, glyph: GlyphFeatures) {
+        self.references.push((rank, glyph));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+
+    // The rank and its correlation, clamped to 0..1. None without references.
+    pub fn classify(&self, glyph: &GlyphFeatures) -> Option<(u8, f64)> {
+        self.references
+            .iter()
+            .map(|(rank, reference)| (*rank, glyph.correlation(reference).max(0.0)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // A 24x32 white picture with a dark bar, vertical or horizontal
+    fn bar(vertical: bool, shift: usize) -> GlyphFeatures {
+        let (width, height) = (24, 32);
+        let pixels: Vec<u8> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let ink = match vertical {
+                    true => (10 + shift..14 + shift).contains(&x),
+                    false => (14 + shift..18 + shift).contains(&y),
+                };
+                if ink { 20 } else { 240 }
+            })
+            .collect();
+        GlyphFeatures::new(&pixels, width, height)
+    }
+
+    #[test]
+    fn test_rank_classifier() {
+        let mut classifier = RankClassifier::default();
+        assert_eq!(classifier.classify(&bar(true, 0)), None);
+        classifier.learn(1, bar(true, 0));
+        classifier.learn(7, bar(false, 0));
+
+        let (rank, confidence) = classifier.classify(&bar(true, 1)).unwrap();
+        assert_eq!(rank, 1);
+        assert!(confidence > 0.5);
+        assert_eq!(classifier.classify(&bar(false, 1)).unwrap().0, 7);
+    }
+}
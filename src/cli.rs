@@ -0,0 +1,537 @@
+use std::ops::RangeInclusive;
+
+use clap::{Args, Parser, Subcommand};
+
+use freecell::analyze;
+use freecell::batch::ReportFormat;
+use freecell::i18n::Lang;
+use freecell::output::{OutputMode, SolutionFormat};
+use freecell::solver::Strategy;
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "FreeCell solver",
+    after_help = "Exit codes: 0 solved, 1 proven unsolvable, 2 invalid input, 3 node limit reached"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Settings file, freecell-solver.toml of the current directory if it exists
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// text for people, json for scripts, quiet to only set the exit code
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub format: OutputMode,
+
+    /// Print boards without colors, also the case when NO_COLOR is set
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Language of the messages, English when LANG starts with "en", French otherwise
+    #[arg(long, value_enum, global = true)]
+    pub lang: Option<Lang>,
+
+    /// Card theme of the templates, a folder of `templates/`. Guessed from the capture
+    /// when the config sets none
+    #[arg(long, global = true)]
+    pub theme: Option<String>,
+
+    /// Screen to capture, numbered from 0 as the system lists them. The screen clicked
+    /// on when selecting the region, the primary one otherwise
+    #[cfg(feature = "automation")]
+    #[arg(long, global = true)]
+    pub display: Option<usize>,
+
+    /// Captures the client area of the window with this in its title, wherever it is
+    /// (X11, with xdotool)
+    #[cfg(feature = "automation")]
+    #[arg(long, global = true)]
+    pub window: Option<String>,
+
+    /// Asks for the two corners of the game area even when the board could be found on
+    /// the screen
+    #[cfg(feature = "automation")]
+    #[arg(long, global = true)]
+    pub click_region: bool,
+
+    /// Forgets the region saved in the config and asks for its two corners again
+    #[cfg(feature = "automation")]
+    #[arg(long, global = true)]
+    pub reselect: bool,
+
+    /// Game area to capture, x1,y1,x2,y2 in pixels of the screen, rather than the one
+    /// of the config or a selection
+    #[cfg(feature = "automation")]
+    #[arg(long, global = true, value_parser = freecell::config::parse_region)]
+    pub rect: Option<[i32; 4]>,
+
+    /// Writes each screen capture to capture.png, to see what the OCR was given
+    #[cfg(feature = "automation")]
+    #[arg(long, global = true)]
+    pub save_captures: bool,
+
+    /// Keeps every capture of watch and hotkey, with what was read on it, in a
+    /// subfolder of this folder: misreadings can be turned into OCR fixtures
+    #[cfg(all(feature = "vision", feature = "automation"))]
+    #[arg(long, global = true)]
+    pub archive: Option<String>,
+
+    // Without a subcommand the options of `solve` apply
+    #[command(flatten)]
+    pub solve: SolveArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SolveArgs {
+    /// Microsoft FreeCell deal number (1 to 2147483647) instead of a random game
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=0x7fff_ffff), conflicts_with_all = ["board", "cards"])]
+    pub deal: Option<u32>,
+
+    /// Seed of the random deal, printed on each run so that a deal can be dealt again
+    #[arg(long, conflicts_with_all = ["deal", "cards", "board"])]
+    pub seed: Option<u64>,
+
+    /// The 52 cards of the deal in dealing order, e.g. "AS 7D KC ..."
+    #[arg(long, conflicts_with = "board")]
+    pub cards: Option<String>,
+
+    /// Search strategy (astar, dfs, weighted:N). Defaults to the config, then the STRATEGY variable of .env
+    #[arg(long)]
+    pub strategy: Option<Strategy>,
+
+    /// Print search counters once the solver is done
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Time move generation, heuristic, hashing and queue operations, printed with
+    /// the search counters
+    #[arg(long)]
+    pub profile: bool,
+
+    /// Estimate the search tree size by random probing before solving
+    #[arg(long)]
+    pub estimate: bool,
+
+    /// Let the estimate pick the strategy and the node limit
+    #[arg(long)]
+    pub auto: bool,
+
+    /// Number of random rollouts used by the estimate
+    #[arg(long, default_value_t = 300)]
+    pub probes: usize,
+
+    /// Explore every reachable position without node limit nor heuristic, to prove
+    /// that the deal has no solution
+    #[arg(long, conflicts_with_all = ["strategy", "estimate", "auto"])]
+    pub prove: bool,
+
+    /// Keep the solution exactly as found, without removing wasted moves
+    #[arg(long)]
+    pub no_optimize: bool,
+
+    /// Print supermoves card by card instead of as a single move
+    #[arg(long)]
+    pub expanded: bool,
+
+    /// How the solution is printed
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: SolutionFormat,
+
+    /// With the verbose format, print the board every N moves
+    #[arg(long, default_value_t = 1)]
+    pub snapshot_every: usize,
+
+    /// Save a picture of the deal to this PNG file
+    #[arg(long)]
+    pub png: Option<String>,
+
+    /// Load the position from a PySolFC or fc-solve board file ("-" for stdin)
+    /// instead of dealing a random game
+    #[arg(long)]
+    pub board: Option<String>,
+
+    /// Save the position in PySolFC's board format, to hand-play it there
+    #[arg(long)]
+    pub pysol: Option<String>,
+
+    /// Save the solution (or the deal if unsolved) as an SVG diagram
+    #[arg(long)]
+    pub svg: Option<String>,
+
+    /// Save the A* search to this file every --checkpoint-every nodes and when it hits
+    /// a limit, so that it can be resumed
+    #[arg(long, conflicts_with = "prove")]
+    pub checkpoint: Option<String>,
+
+    /// Nodes explored between two checkpoints
+    #[arg(long, default_value_t = 1_000_000)]
+    pub checkpoint_every: u64,
+
+    /// Go on with a search saved by --checkpoint, which is saved again to the same file
+    /// unless --checkpoint is given
+    #[arg(long, conflicts_with_all = ["deal", "seed", "cards", "board", "strategy", "prove", "estimate", "auto"])]
+    pub resume: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct BatchArgs {
+    /// File with one deal per line (deal number, card list or board block), stdin if omitted or "-"
+    pub input: Option<String>,
+
+    /// Search strategy (astar, dfs, weighted:N). Defaults to the config, then the STRATEGY variable of .env
+    #[arg(long)]
+    pub strategy: Option<Strategy>,
+
+    /// Nodes explored per deal before giving up, defaults to the config
+    #[arg(long)]
+    pub max_nodes: Option<u32>,
+
+    /// Seconds spent on a deal before giving up, defaults to the config
+    #[arg(long, value_parser = freecell::config::parse_seconds)]
+    pub time_limit: Option<f64>,
+
+    /// Number of deals solved in parallel, 0 for one per CPU core
+    #[arg(long, short, default_value_t = 0)]
+    pub jobs: usize,
+
+    /// Count the moves of the solutions as found, without removing wasted moves
+    #[arg(long)]
+    pub no_optimize: bool,
+
+    /// How the result lines and the summary are printed
+    #[arg(long, value_enum, default_value_t)]
+    pub report: ReportFormat,
+
+    /// Grade each deal's difficulty from 1 to 5, as a report column
+    #[arg(long)]
+    pub rate: bool,
+
+    /// Also write the aggregate statistics to this JSON file
+    #[arg(long)]
+    pub summary: Option<String>,
+
+    /// SQLite file the results are added to, deals it already has solved are skipped
+    #[arg(long)]
+    pub db: Option<String>,
+
+    /// Record finished deals in this file and skip the ones it already holds
+    #[arg(long)]
+    pub checkpoint: Option<String>,
+
+    /// Write the checkpoint every N finished deals
+    #[arg(long, default_value_t = 100, requires = "checkpoint")]
+    pub checkpoint_every: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct GenerateArgs {
+    /// Number of deals to write
+    #[arg(long, default_value_t = 10)]
+    pub count: usize,
+
+    /// Only keep the deals a quick solver pass manages to solve
+    #[arg(long)]
+    pub solvable: bool,
+
+    /// Write the solution found by the solver pass after each deal
+    #[arg(long)]
+    pub solutions: bool,
+
+    /// Only keep the solvable deals needing more than this many nodes (or --min-moves)
+    #[arg(long)]
+    pub min_nodes: Option<u64>,
+
+    /// Only keep the solvable deals needing more than this many moves (or --min-nodes)
+    #[arg(long)]
+    pub min_moves: Option<usize>,
+
+    /// Skip without solving the deals whose estimated tree size is below 10^N nodes
+    #[arg(long)]
+    pub min_estimate: Option<f64>,
+
+    /// Seed of the first deal, the next ones use the following seeds. Random if omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// File to write the deals to, stdout if omitted
+    #[arg(long, short)]
+    pub output: Option<String>,
+
+    /// Nodes the quick solver pass explores before dropping a deal
+    #[arg(long, default_value_t = 100_000)]
+    pub max_nodes: u32,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct AnalyzeArgs {
+    /// File with one deal per line, as for batch, stdin if omitted or "-"
+    pub input: Option<String>,
+
+    /// Microsoft FreeCell deal number to analyze instead of a file
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=0x7fff_ffff), conflicts_with = "input")]
+    pub deal: Option<u32>,
+
+    /// Freecell counts to solve each deal with, e.g. "0..4" or "2"
+    #[arg(long, default_value = "0..4", value_parser = analyze::parse_cell_range)]
+    pub cells: RangeInclusive<usize>,
+
+    /// Search strategy (astar, dfs, weighted:N). Defaults to the config, then the STRATEGY variable of .env
+    #[arg(long)]
+    pub strategy: Option<Strategy>,
+
+    /// Nodes explored per run before giving up, defaults to the config
+    #[arg(long)]
+    pub max_nodes: Option<u32>,
+
+    /// Seconds spent on a run before giving up, defaults to the config
+    #[arg(long, value_parser = freecell::config::parse_seconds)]
+    pub time_limit: Option<f64>,
+
+    /// Number of runs solved in parallel, 0 for one per CPU core
+    #[arg(long, short, default_value_t = 0)]
+    pub jobs: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RateArgs {
+    /// Microsoft FreeCell deal number
+    #[arg(value_parser = clap::value_parser!(u32).range(1..=0x7fff_ffff))]
+    pub deal: u32,
+
+    /// Nodes explored per solver run before giving up, defaults to the config
+    #[arg(long)]
+    pub max_nodes: Option<u32>,
+
+    /// Seconds spent on a solver run before giving up, defaults to the config
+    #[arg(long, value_parser = freecell::config::parse_seconds)]
+    pub time_limit: Option<f64>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CompareArgs {
+    /// File with one deal per line, as for batch, stdin if neither it nor --deal is given
+    pub input: Option<String>,
+
+    /// Microsoft FreeCell deal numbers, comma separated or repeated
+    #[arg(long, value_delimiter = ',', conflicts_with = "input")]
+    pub deal: Vec<u32>,
+
+    /// Strategies to race, comma separated
+    #[arg(long, value_delimiter = ',', default_value = "astar,dfs")]
+    pub strategies: Vec<Strategy>,
+
+    /// Nodes explored per run before giving up, defaults to the config
+    #[arg(long)]
+    pub max_nodes: Option<u32>,
+
+    /// Seconds spent on a run before giving up, defaults to the config
+    #[arg(long, value_parser = freecell::config::parse_seconds)]
+    pub time_limit: Option<f64>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CrosscheckArgs {
+    /// File with one deal per line, as for batch, stdin if neither it nor --deal is given
+    pub input: Option<String>,
+
+    /// Microsoft FreeCell deal numbers, comma separated or repeated
+    #[arg(long, value_delimiter = ',', conflicts_with = "input")]
+    pub deal: Vec<u32>,
+
+    /// fc-solve binary to compare with, skipped if it cannot be found
+    #[arg(long, default_value = "fc-solve")]
+    pub fc_solve: String,
+
+    /// Nodes explored per deal before giving up, also fc-solve's iteration limit
+    #[arg(long)]
+    pub max_nodes: Option<u32>,
+
+    /// Seconds spent on a deal before giving up, defaults to the config
+    #[arg(long, value_parser = freecell::config::parse_seconds)]
+    pub time_limit: Option<f64>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ServeArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// Address to listen on, 0.0.0.0 to accept other machines
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+
+    /// Number of requests solved at once, 0 for one per CPU core
+    #[arg(long, short, default_value_t = 0)]
+    pub jobs: usize,
+
+    /// Most nodes a request may explore, defaults to the config
+    #[arg(long)]
+    pub max_nodes: Option<u32>,
+
+    /// Most seconds a request may take, defaults to the config
+    #[arg(long, value_parser = freecell::config::parse_seconds)]
+    pub time_limit: Option<f64>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Solve a deal, this is also what runs without a subcommand
+    Solve(SolveArgs),
+    /// Solve many deals, one result line per deal
+    Batch(BatchArgs),
+    /// Solve deals with fewer freecells to find how many they need
+    Analyze(AnalyzeArgs),
+    /// Run several strategies on the same deals and compare them side by side
+    Compare(CompareArgs),
+    /// Solve deals here and with fc-solve, then check both verdicts and solutions
+    Crosscheck(CrosscheckArgs),
+    /// Grade a deal's difficulty from 1 to 5
+    Rate(RateArgs),
+    /// Write random deals, one per line in the batch input format
+    Generate(GenerateArgs),
+    /// Solve a fixed set of deals with every strategy and compare them
+    Bench {
+        /// Nodes explored per deal before giving up
+        #[arg(long, default_value_t = 500_000)]
+        max_nodes: u32,
+
+        /// Seconds spent on a deal before giving up
+        #[arg(long, default_value_t = 30.0, value_parser = freecell::config::parse_seconds)]
+        time_limit: f64,
+    },
+    /// Answer solve requests over HTTP: POST /solve and GET /deal/{n}
+    Serve(ServeArgs),
+    /// Long-running mode for GUIs: JSON commands on stdin, JSON events on stdout
+    Engine {
+        /// Nodes a search explores when `go` gives no limit, defaults to the config
+        #[arg(long)]
+        max_nodes: Option<u32>,
+    },
+    /// Show how each solver version did on the results stored by `batch --db`
+    Results {
+        /// SQLite file written by `batch --db`
+        db: String,
+    },
+    /// Play a deal in the terminal, with undo, hints and a solver takeover
+    Play {
+        /// Microsoft FreeCell deal number instead of a random game
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=0x7fff_ffff))]
+        deal: Option<u32>,
+
+        /// Seed of the random deal
+        #[arg(long, conflicts_with = "deal")]
+        seed: Option<u64>,
+
+        /// Save the hand history of the game to this JSON file, see `replay`
+        #[arg(long)]
+        history: Option<String>,
+    },
+    /// Capture the game area into a PNG without reading it, for `ocr --image`
+    #[cfg(feature = "automation")]
+    Capture {
+        /// Where to write the capture
+        #[arg(long, default_value = "capture.png")]
+        output: String,
+    },
+    /// Step through a solution saved with --output-format json
+    Replay {
+        /// JSON file holding the deal and its moves: a solution or a hand history
+        file: String,
+    },
+    /// Read the deal on the capture with the card templates and check the deck
+    #[cfg(feature = "vision")]
+    Ocr {
+        /// Prompt for corrections until the 52 cards are there, even out of a terminal
+        #[arg(long)]
+        fix: bool,
+
+        /// Save the deal as a PySolFC board, for `solve --board`
+        #[arg(long)]
+        output: Option<String>,
+
+        /// Read this screenshot rather than the capture of the config
+        #[arg(long)]
+        image: Option<String>,
+
+        /// Solve the deal once read, with the options of `solve`
+        #[arg(long)]
+        solve: bool,
+
+        /// Write a copy of the screenshot showing the matches and the columns found
+        #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "ocr-debug.png")]
+        debug_ocr: Option<String>,
+    },
+    /// Read every OCR fixture of a folder and show where the boards read differ
+    #[cfg(feature = "vision")]
+    OcrCheck {
+        /// One folder per fixture, holding `capture.png` and `expected.board`
+        #[arg(default_value = "fixtures/ocr")]
+        folder: String,
+    },
+    /// Capture the game mid-way, read the position on screen and solve from there
+    #[cfg(all(feature = "vision", feature = "automation"))]
+    Rescan {
+        /// Prompt for corrections until the 52 cards are there
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Keep capturing the game and suggest the next move each time the position changes
+    #[cfg(all(feature = "vision", feature = "automation"))]
+    Watch {
+        /// Seconds between two captures
+        #[arg(long, default_value_t = 2.0, value_parser = freecell::config::parse_seconds)]
+        interval: f64,
+        /// Print the whole solution of each new deal, rather than the next move of
+        /// every position
+        #[arg(long)]
+        deals: bool,
+    },
+    /// Solve the game on screen and play it there with the mouse, reading the board
+    /// again after each move
+    #[cfg(all(feature = "vision", feature = "automation"))]
+    Autoplay {
+        /// Stop after this many moves
+        #[arg(long, default_value_t = 500)]
+        max_moves: usize,
+    },
+    /// Stay in the background and read then solve the game each time a key combination
+    /// is pressed
+    #[cfg(all(feature = "vision", feature = "automation"))]
+    Hotkey {
+        /// Modifiers and key, joined by +: ctrl, shift, alt, then a letter, a digit,
+        /// f1 to f12, space...
+        #[arg(long, default_value = "ctrl+shift+s")]
+        keys: String,
+    },
+    /// Build the card templates the OCR reads deals with
+    Templates {
+        #[command(subcommand)]
+        command: TemplatesCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplatesCommand {
+    /// Cut one template per card out of a screenshot of a fresh deal
+    Extract {
+        /// Screenshot of the deal, before any card was moved
+        capture: String,
+
+        /// Microsoft FreeCell deal number shown on the screenshot
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=0x7fff_ffff))]
+        deal: u32,
+
+        /// Folder the templates are written to, named like `10D.png`. Defaults to the
+        /// templates folder of the config, in the folder of the theme if there is one
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Show the cards of the capture the templates read badly and save each one as a
+    /// new template of the card typed in
+    #[cfg(feature = "vision")]
+    Label,
+}
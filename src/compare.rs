@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::batch::{BatchDeal, BatchOptions, BatchResult, BatchSummary, solve_deal};
+use crate::solver::Strategy;
+
+const CELL_WIDTH: usize = 28;
+
+// Results of every strategy on one deal, in the order the strategies were given
+pub struct Race {
+    pub id: String,
+    pub results: Vec<BatchResult>,
+}
+
+impl Race {
+    // Solved with the fewest nodes, ties going to the first strategy
+    pub fn winner(&self) -> Option<&BatchResult> {
+        self.results
+            .iter()
+            .filter(|r| r.solved())
+            .min_by_key(|r| r.nodes)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "deal": self.id,
+            "winner": self.winner().map(|r| r.strategy.to_string()),
+            "results": self.results.iter().map(BatchResult::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+// "104m 12345n 35.2ms" or "limit 100000n 1.2s"
+fn cell(result: &BatchResult) -> String {
+    let effort = format!("{}n {:.1?}", result.nodes, result.elapsed);
+    match result.moves {
+        Some(moves) => format!("{}m {}", moves, effort),
+        None => format!("{} {}", result.status(), effort),
+    }
+}
+
+// Runs every strategy on every deal with the same limits. Deals are solved one after
+// the other, like in bench, so that the timings can be compared.
+pub fn race(deals: &[BatchDeal], strategies: &[Strategy], options: BatchOptions) -> Vec<Race> {
+    deals
+        .iter()
+        .map(|deal| Race {
+            id: deal.id.clone(),
+            results: strategies
+                .iter()
+                .map(|&strategy| {
+                    solve_deal(
+                        deal,
+                        BatchOptions {
+                            strategy,
+                            ..options
+                        },
+                    )
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+pub fn format_table(races: &[Race], strategies: &[Strategy]) -> String {
+    let mut lines = Vec::new();
+    let header: Vec<String> = strategies
+        .iter()
+        .map(|s| format!("{:<width$}", s.to_string(), width = CELL_WIDTH))
+        .collect();
+    lines.push(format!("{:<10} {} winner", "deal", header.join(" ")));
+
+    for race in races {
+        let cells: Vec<String> = race
+            .results
+            .iter()
+            .map(|r| format!("{:<width$}", cell(r), width = CELL_WIDTH))
+            .collect();
+        lines.push(format!(
+            "{:<10} {} {}",
+            race.id,
+            cells.join(" "),
+            race.winner()
+                .map_or("-".to_string(), |r| r.strategy.to_string())
+        ));
+    }
+
+    // One column per strategy: solved deals, total nodes and total time
+    let totals: Vec<String> = (0..strategies.len())
+        .map(|i| {
+            let results: Vec<BatchResult> = races.iter().map(|r| r.results[i].clone()).collect();
+            let summary = BatchSummary::new(&results);
+            let nodes: u64 = results.iter().map(|r| r.nodes).sum();
+            let time: Duration = results.iter().map(|r| r.elapsed).sum();
+            let total = format!(
+                "{}/{} {}n {:.1?}",
+                summary.solved, summary.deals, nodes, time
+            );
+            format!("{:<width$}", total, width = CELL_WIDTH)
+        })
+        .collect();
+    lines.push(format!("{:<10} {}", "total", totals.join(" ")));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::batch::parse_batch;
+    use crate::solver::HeuristicWeights;
+
+    #[test]
+    fn test_race() {
+        let options = BatchOptions {
+            strategy: Strategy::AStar,
+            max_nodes: 20_000,
+            time_limit: None,
+            weights: HeuristicWeights::default(),
+            optimize: true,
+            rate: false,
+        };
+        let strategies = [Strategy::AStar, Strategy::Weighted(3)];
+        let races = race(&parse_batch("164"), &strategies, options);
+
+        assert_eq!(races[0].results.len(), 2);
+        assert_eq!(races[0].results[1].strategy, Strategy::Weighted(3));
+        assert!(races[0].winner().is_some());
+        let table = format_table(&races, &strategies);
+        assert!(table.starts_with("deal       astar"));
+        assert!(table.lines().last().unwrap().starts_with("total      1/1"));
+    }
+}
@@ -0,0 +1,540 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use toml::{Table, Value};
+
+use crate::display::CardStyle;
+use crate::layout::LayoutPreset;
+use crate::solver::{HeuristicWeights, Strategy};
+
+pub const DEFAULT_CONFIG_PATH: &str = "freecell-solver.toml";
+// FREECELL_SOLVER__MAX_NODES=500000 overrides `max_nodes` of the [solver] section
+const ENV_PREFIX: &str = "FREECELL_";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SolverSettings {
+    pub strategy: Option<Strategy>,
+    pub max_nodes: u32,
+    // Seconds, no limit if absent
+    pub time_limit: Option<f64>,
+}
+
+impl Default for SolverSettings {
+    fn default() -> Self {
+        SolverSettings {
+            strategy: None,
+            max_nodes: 1_000_000,
+            time_limit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OcrSettings {
+    // Template matches below this score are dropped, unless the fallback keeps them
+    pub min_confidence: f64,
+    // Weaker matches, down to this score, are kept when their colors match the
+    // template's by at least `histogram_confidence`. Set it to `min_confidence` or
+    // more to turn the fallback off.
+    pub fallback_confidence: f64,
+    pub histogram_confidence: f64,
+    // Kept matches below this one are reported as doubtful before solving
+    pub warn_confidence: f64,
+    // Two matches overlapping more than this (intersection over union) are one card
+    pub max_overlap: f64,
+    pub templates: String,
+    // Template set of a card theme, in `{templates}/{theme}`. When absent and
+    // `templates` holds theme folders, the one reading the capture best is used.
+    pub theme: Option<String>,
+    // Folder of corner templates, `ranks/` and `suits/`: when set, cards are read from
+    // their corner glyphs rather than matched whole
+    pub corner_templates: Option<String>,
+    // Reads the rank glyph of each match again, with a classifier learned from the
+    // card templates, and merges both opinions
+    pub read_ranks: bool,
+    // Matches gray levels after a local contrast equalization rather than colors, for
+    // dark themes and night-light filters. Templates are inverted when they are light
+    // and the capture dark, or the reverse. Turns the color fallback off.
+    pub normalize: bool,
+    // Templates are first matched on the capture this many times smaller, then at full
+    // size only around the spots found. 1 matches at full size everywhere.
+    pub coarse_scale: u32,
+    // Fraction of the templates' height also matched alone, from their top: the part
+    // of a buried card left visible by the next one. Whole templates still read the
+    // bottom cards. Off when absent, about 0.25 for tall columns.
+    pub strip_height: Option<f64>,
+    // Runs the template matching on the GPU through OpenCL, when OpenCV was built with
+    // it and finds a device. The CPU takes over otherwise.
+    pub opencl: bool,
+    // Measures the size of the cards on the capture and scales the templates to it,
+    // for captures of a window larger or smaller than the templates' one
+    pub calibrate: bool,
+    pub capture: String,
+    // Client the capture comes from: its columns are then found where it draws them,
+    // rather than guessed from the gaps between the cards
+    pub layout: Option<LayoutPreset>,
+    // Copy of the capture annotated with the matches and the columns, see `--debug-ocr`
+    pub debug_image: Option<String>,
+}
+
+impl Default for OcrSettings {
+    fn default() -> Self {
+        OcrSettings {
+            min_confidence: 0.8,
+            fallback_confidence: 0.65,
+            histogram_confidence: 0.9,
+            warn_confidence: 0.9,
+            max_overlap: 0.3,
+            templates: "templates".to_string(),
+            theme: None,
+            corner_templates: None,
+            read_ranks: true,
+            normalize: false,
+            coarse_scale: 4,
+            strip_height: None,
+            opencl: false,
+            calibrate: false,
+            capture: "capture.png".to_string(),
+            layout: None,
+            debug_image: None,
+        }
+    }
+}
+
+impl OcrSettings {
+    // The theme folders of `templates`, those holding templates, by name
+    pub fn themes(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.templates) else {
+            return Vec::new();
+        };
+        let mut themes: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                std::fs::read_dir(path).is_ok_and(|mut files| {
+                    files.any(|file| {
+                        file.is_ok_and(|file| file.path().extension().is_some_and(|e| e == "png"))
+                    })
+                })
+            })
+            .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .collect();
+        themes.sort();
+        themes
+    }
+
+    // The same settings, reading cards with the templates of `theme`
+    pub fn with_theme(&self, theme: &str) -> OcrSettings {
+        OcrSettings {
+            templates: format!("{}/{}", self.templates, theme),
+            theme: Some(theme.to_string()),
+            ..self.clone()
+        }
+    }
+}
+
+// Library grabbing the screen, see `screen::CaptureBackend` in the binary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureKind {
+    // The screenshots crate on Wayland sessions, scrap elsewhere
+    #[default]
+    Auto,
+    // X11, Windows and macOS, not Wayland
+    Scrap,
+    // The screenshots crate: X11 through xcb, Windows, macOS, and Wayland through
+    // xdg-desktop-portal, where a frame takes a while
+    #[serde(alias = "portal")]
+    Screenshots,
+}
+
+impl CaptureKind {
+    // The backend actually used, `wayland` telling the session type for `Auto`
+    pub fn resolve(self, wayland: bool) -> CaptureKind {
+        match self {
+            CaptureKind::Auto if wayland => CaptureKind::Screenshots,
+            CaptureKind::Auto => CaptureKind::Scrap,
+            kind => kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScreenSettings {
+    // Corners of the game area [x1, y1, x2, y2], in pixels of the screen, selected
+    // with the mouse if absent
+    pub region: Option<[i32; 4]>,
+    pub backend: CaptureKind,
+    // Screen to capture, as numbered by the backend. The primary screen if absent
+    pub display: Option<usize>,
+    // Part of the title of the game window: its client area is captured wherever it
+    // is, before `region`. Found with xdotool, so on X11 only.
+    pub window: Option<String>,
+    // Without a region, looks for the felt and the card columns on the screen before
+    // asking for a selection. false always asks, like --click-region.
+    pub detect: bool,
+    // Pixels of the captures per unit of the mouse coordinates, as read from the
+    // system when absent. Set it when the clicked area comes out shifted or off size
+    // on a scaled screen.
+    pub scale: Option<f64>,
+    // Writes every capture to capture.png, like --save-captures. They go straight to
+    // the OCR in memory otherwise.
+    pub save_captures: bool,
+    // Folder where `watch` and `hotkey` keep each capture with what was read on it,
+    // one subfolder per capture and at most `archive_max` of them, like --archive
+    pub archive: Option<String>,
+    pub archive_max: usize,
+}
+
+impl Default for ScreenSettings {
+    fn default() -> Self {
+        ScreenSettings {
+            region: None,
+            backend: CaptureKind::default(),
+            display: None,
+            window: None,
+            detect: true,
+            scale: None,
+            save_captures: false,
+            archive: None,
+            archive_max: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutomationSettings {
+    // First wait between two attempts at grabbing a frame, doubled after each
+    pub frame_retry_ms: u64,
+    // A screen giving no frame for that long is reported, asleep or locked
+    pub frame_timeout_ms: u64,
+    // Wait between two checks for the region clicks
+    pub click_poll_ms: u64,
+    // A capture is kept once it matches the next one, taken `stable_ms` later, but for
+    // a `stable_tolerance` fraction of its pixels. After `stable_attempts` frames that
+    // kept changing, it is given up
+    pub stable_ms: u64,
+    pub stable_tolerance: f64,
+    pub stable_attempts: u32,
+    // Wait after each move played by `autoplay`, for the game to draw it
+    pub move_delay_ms: u64,
+}
+
+impl Default for AutomationSettings {
+    fn default() -> Self {
+        AutomationSettings {
+            frame_retry_ms: 10,
+            frame_timeout_ms: 5000,
+            click_poll_ms: 100,
+            stable_ms: 150,
+            stable_tolerance: 0.001,
+            stable_attempts: 20,
+            move_delay_ms: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplaySettings {
+    pub cards: CardStyle,
+    // false turns colors off like --no-color
+    pub color: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            cards: CardStyle::default(),
+            color: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub solver: SolverSettings,
+    pub heuristic: HeuristicWeights,
+    pub ocr: OcrSettings,
+    pub screen: ScreenSettings,
+    pub automation: AutomationSettings,
+    pub display: DisplaySettings,
+}
+
+// Values are read as TOML when they parse as such ("500000", "[0, 0, 800, 600]"),
+// as plain strings otherwise ("dfs")
+fn parse_env_value(raw: &str) -> Value {
+    format!("value = {}", raw)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+fn apply_env_overrides(
+    table: &mut Table,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<(), String> {
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let Some((section, key)) = path.split_once("__") else {
+            continue;
+        };
+
+        let section = table
+            .entry(section.to_ascii_lowercase())
+            .or_insert_with(|| Value::Table(Table::new()));
+        let Value::Table(section) = section else {
+            return Err(format!("{} does not point into a section", name));
+        };
+        section.insert(key.to_ascii_lowercase(), parse_env_value(&raw));
+    }
+    Ok(())
+}
+
+fn parse_config(
+    text: &str,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<Config, String> {
+    let mut table: Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+    apply_env_overrides(&mut table, vars)?;
+    let config: Config = table
+        .try_into()
+        .map_err(|e: toml::de::Error| e.to_string())?;
+    if let Some(seconds) = config.solver.time_limit {
+        positive_seconds(seconds).map_err(|e| format!("[solver] time_limit: {}", e))?;
+    }
+    Ok(config)
+}
+
+// Positive and short enough for a Duration, which holds about 584 billion years
+fn positive_seconds(seconds: f64) -> Result<f64, String> {
+    match seconds > 0.0 && Duration::try_from_secs_f64(seconds).is_ok() {
+        true => Ok(seconds),
+        false => Err(format!("{} is not a positive number of seconds", seconds)),
+    }
+}
+
+// Seconds given to --time-limit or --interval, a positive number that fits a Duration
+pub fn parse_seconds(text: &str) -> Result<f64, String> {
+    let seconds = text
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("{}: {}", text, e))?;
+    positive_seconds(seconds)
+}
+
+// Reads the given file, or freecell-solver.toml when it exists, then applies the
+// FREECELL_<SECTION>__<KEY> environment variables on top
+pub fn load_config(path: Option<&str>) -> Result<Config, String> {
+    let text = match path {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?,
+        None => std::fs::read_to_string(DEFAULT_CONFIG_PATH).unwrap_or_default(),
+    };
+    parse_config(&text, std::env::vars())
+        .map_err(|e| format!("{}: {}", path.unwrap_or(DEFAULT_CONFIG_PATH), e))
+}
+
+// A region written "x1,y1,x2,y2", as given to --rect
+pub fn parse_region(text: &str) -> Result<[i32; 4], String> {
+    let values = text
+        .split(',')
+        .map(|value| value.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("{}: {}", text, e))?;
+    let [x1, y1, x2, y2] = values[..] else {
+        return Err(format!("{}: expected x1,y1,x2,y2", text));
+    };
+    if x1 == x2 || y1 == y2 {
+        return Err(format!("{}: the region is empty", text));
+    }
+    Ok([x1, y1, x2, y2])
+}
+
+// `text` with `region` and `display` replaced in its [screen] section, the rest of
+// the file and its comments as they were
+fn set_screen_region(text: &str, region: [i32; 4], display: Option<usize>) -> String {
+    let [x1, y1, x2, y2] = region;
+    let mut entries = vec![format!("region = [{}, {}, {}, {}]", x1, y1, x2, y2)];
+    entries.extend(display.map(|index| format!("display = {}", index)));
+
+    let mut lines = Vec::new();
+    let mut in_screen = false;
+    let mut written = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed.strip_prefix('[') {
+            in_screen = header.split(']').next().map(str::trim) == Some("screen");
+            lines.push(line.to_string());
+            if in_screen && !written {
+                lines.extend(entries.iter().cloned());
+                written = true;
+            }
+            continue;
+        }
+        let key = trimmed.split('=').next().unwrap_or_default().trim();
+        if !(in_screen && (key == "region" || key == "display")) {
+            lines.push(line.to_string());
+        }
+    }
+    if !written {
+        if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push("[screen]".to_string());
+        lines.extend(entries);
+    }
+    lines.join("\n") + "\n"
+}
+
+// Writes the capture region into the config file read by `load_config`, so that it is
+// not asked again. Returns the path of the file.
+pub fn save_screen_region(
+    path: Option<&str>,
+    region: [i32; 4],
+    display: Option<usize>,
+) -> Result<String, String> {
+    let path = path.unwrap_or(DEFAULT_CONFIG_PATH);
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("{}: {}", path, e)),
+    };
+    let text = set_screen_region(&text, region, display);
+    // Never leave a file that the next run could not read
+    parse_config(&text, std::iter::empty()).map_err(|e| format!("{}: {}", path, e))?;
+    std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_themes() {
+        let folder = std::env::temp_dir().join("freecell-test-themes");
+        let _ = std::fs::remove_dir_all(&folder);
+        for theme in ["windows", "aisleriot", "empty"] {
+            std::fs::create_dir_all(folder.join(theme)).unwrap();
+        }
+        std::fs::write(folder.join("windows/10D.png"), b"").unwrap();
+        std::fs::write(folder.join("aisleriot/KH.png"), b"").unwrap();
+        std::fs::write(folder.join("AS.png"), b"").unwrap();
+
+        let settings = OcrSettings {
+            templates: folder.to_string_lossy().into_owned(),
+            ..OcrSettings::default()
+        };
+        assert_eq!(settings.themes(), ["aisleriot", "windows"]);
+        let themed = settings.with_theme("windows");
+        assert!(themed.templates.ends_with("freecell-test-themes/windows"));
+        assert_eq!(themed.theme.as_deref(), Some("windows"));
+        assert!(themed.themes().is_empty());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let text = "[solver]\nmax_nodes = 5000\n\n[heuristic]\nblocked_card = 8\n";
+        let config = parse_config(
+            text,
+            vars(&[
+                ("FREECELL_SOLVER__STRATEGY", "dfs"),
+                ("FREECELL_SCREEN__REGION", "[0, 0, 800, 600]"),
+                ("FREECELL_SCREEN__DISPLAY", "1"),
+                ("FREECELL_SCREEN__WINDOW", "Solitaire"),
+                ("FREECELL_SCREEN__BACKEND", "portal"),
+                ("FREECELL_DISPLAY__CARDS", "glyphs"),
+                ("PATH", "/usr/bin"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.solver.max_nodes, 5000);
+        assert_eq!(config.solver.strategy, Some(Strategy::Dfs));
+        assert_eq!(config.heuristic.blocked_card, 8);
+        assert_eq!(config.heuristic.remaining_card, 10);
+        assert_eq!(config.screen.region, Some([0, 0, 800, 600]));
+        assert_eq!(config.screen.display, Some(1));
+        assert_eq!(config.screen.window.as_deref(), Some("Solitaire"));
+        assert_eq!(config.screen.backend, CaptureKind::Screenshots);
+        assert_eq!(CaptureKind::default(), CaptureKind::Auto);
+        assert_eq!(CaptureKind::Auto.resolve(true), CaptureKind::Screenshots);
+        assert_eq!(CaptureKind::Auto.resolve(false), CaptureKind::Scrap);
+        assert_eq!(CaptureKind::Scrap.resolve(true), CaptureKind::Scrap);
+        assert!(config.screen.detect);
+        assert_eq!(config.automation.click_poll_ms, 100);
+        assert_eq!(config.display.cards, CardStyle::Glyphs);
+        assert!(config.display.color);
+
+        assert!(parse_config("[solver]\nmax_node = 1\n", vars(&[])).is_err());
+    }
+
+    #[test]
+    fn test_parse_seconds() {
+        assert_eq!(parse_seconds("2.5"), Ok(2.5));
+        assert!(parse_seconds("0").is_err());
+        assert!(parse_seconds("-1").is_err());
+        assert!(parse_seconds("nan").is_err());
+        assert!(parse_seconds("inf").is_err());
+        assert!(parse_seconds("1e30").is_err());
+        assert!(parse_seconds("soon").is_err());
+
+        assert!(parse_config("[solver]\ntime_limit = -1.0\n", vars(&[])).is_err());
+        assert!(parse_config("[solver]\ntime_limit = nan\n", vars(&[])).is_err());
+        let env = vars(&[("FREECELL_SOLVER__TIME_LIMIT", "2.5")]);
+        assert_eq!(parse_config("", env).unwrap().solver.time_limit, Some(2.5));
+        let env = vars(&[("FREECELL_SOLVER__TIME_LIMIT", "1e30")]);
+        assert!(parse_config("", env).is_err());
+        let config = parse_config("[solver]\ntime_limit = 1.5\n", vars(&[])).unwrap();
+        assert_eq!(config.solver.time_limit, Some(1.5));
+    }
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!(parse_region("100,200, 1500,900"), Ok([100, 200, 1500, 900]));
+        assert!(parse_region("100,200,1500").is_err());
+        assert!(parse_region("100,200,100,900").is_err());
+        assert!(parse_region("a,b,c,d").is_err());
+    }
+
+    #[test]
+    fn test_set_screen_region() {
+        let text =
+            "# Mine\n[screen]\nregion = [0, 0, 1, 1]\ndetect = false\n\n[display]\ncolor = false\n";
+        let text = set_screen_region(text, [10, 20, 810, 620], Some(1));
+        assert!(text.starts_with("# Mine\n[screen]\nregion = [10, 20, 810, 620]\n"));
+        let config = parse_config(&text, vars(&[])).unwrap();
+        assert_eq!(config.screen.region, Some([10, 20, 810, 620]));
+        assert_eq!(config.screen.display, Some(1));
+        assert!(!config.screen.detect);
+        assert!(!config.display.color);
+
+        let text = set_screen_region(&text, [1, 2, 3, 4], None);
+        let config = parse_config(&text, vars(&[])).unwrap();
+        assert_eq!(config.screen.region, Some([1, 2, 3, 4]));
+        assert_eq!(config.screen.display, None);
+
+        let text = set_screen_region("[solver]\nmax_nodes = 10\n", [1, 2, 3, 4], None);
+        assert_eq!(
+            text,
+            "[solver]\nmax_nodes = 10\n\n[screen]\nregion = [1, 2, 3, 4]\n"
+        );
+    }
+}
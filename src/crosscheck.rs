@@ -0,0 +1,224 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+use crate::action::Action;
+use crate::batch::{BatchDeal, BatchOptions, solve_game, status};
+use crate::board::to_pysol;
+use crate::exit::Outcome;
+use crate::game::Game;
+use crate::notation::parse_move;
+use crate::verify::verify_solution;
+
+// What fc-solve said about a deal, its solution replayed into our moves
+pub struct External {
+    pub outcome: Outcome,
+    pub moves: Option<Vec<Action>>,
+    pub elapsed: Duration,
+}
+
+// fc-solve prints the moves one per block with -m -snx ("3a", "72v3", "8h"), then
+// "This game is solveable." or "I could not solve this game.", anything else is taken
+// as its iteration limit. Moves are read against the position they are played from.
+pub fn parse_fc_solve_output(game: &Game, output: &str) -> Result<External, String> {
+    let mut state = game.clone();
+    let mut moves = Vec::new();
+    let mut outcome = Outcome::LimitReached;
+
+    for line in output.lines().map(str::trim) {
+        if line.starts_with("This game is solveable") {
+            outcome = Outcome::Solved;
+        } else if line.starts_with("I could not solve") {
+            outcome = Outcome::Unsolvable;
+        } else if line.len() >= 2 && line.chars().all(|c| c.is_ascii_alphanumeric()) {
+            let action = parse_move(&state, line)
+                .ok_or_else(|| format!("move #{} ({}) is illegal", moves.len() + 1, line))?;
+            state.apply_unchecked(&action);
+            moves.push(action);
+        }
+    }
+
+    Ok(External {
+        outcome,
+        moves: (outcome == Outcome::Solved).then_some(moves),
+        elapsed: Duration::ZERO,
+    })
+}
+
+pub fn fc_solve_available(binary: &str) -> bool {
+    Command::new(binary)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+// Runs the fc-solve binary on the board written to its stdin
+pub fn run_fc_solve(binary: &str, game: &Game, max_nodes: u32) -> Result<External, String> {
+    let now = Instant::now();
+    let mut child = Command::new(binary)
+        .args(["-m", "-snx", "-mi", &max_nodes.to_string()])
+        .args(["--freecells-num", &game.cells.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", binary, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(to_pysol(game).as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+
+    let mut external = parse_fc_solve_output(game, &String::from_utf8_lossy(&output.stdout))?;
+    external.elapsed = now.elapsed();
+    Ok(external)
+}
+
+// Both verdicts on one deal. `problems` lists the disagreements and the solutions that
+// do not replay to a won game, empty when the two solvers agree.
+pub struct Crosscheck {
+    pub id: String,
+    pub ours: Outcome,
+    pub our_moves: Option<usize>,
+    // None when fc-solve could not be run
+    pub theirs: Option<Outcome>,
+    pub their_moves: Option<usize>,
+    pub problems: Vec<String>,
+}
+
+impl Crosscheck {
+    pub fn agrees(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "deal": self.id,
+            "ours": status(self.ours),
+            "our_moves": self.our_moves,
+            "fc_solve": self.theirs.map(status),
+            "fc_solve_moves": self.their_moves,
+            "problems": self.problems,
+        })
+    }
+}
+
+fn check_solution(who: &str, game: &Game, moves: &[Action]) -> Option<String> {
+    match verify_solution(game, moves) {
+        Ok(end) if end.is_won() => None,
+        Ok(_) => Some(format!("{} solution does not win the game", who)),
+        Err(e) => Some(format!("{} solution: {}", who, e)),
+    }
+}
+
+// `fc_solve` is the binary to compare with, None to only check our own solutions
+pub fn crosscheck(deal: &BatchDeal, options: BatchOptions, fc_solve: Option<&str>) -> Crosscheck {
+    let mut check = Crosscheck {
+        id: deal.id.clone(),
+        ours: Outcome::InputError,
+        our_moves: None,
+        theirs: None,
+        their_moves: None,
+        problems: Vec::new(),
+    };
+    let game = match &deal.game {
+        Ok(game) => game,
+        Err(e) => {
+            check.problems.push(e.clone());
+            return check;
+        }
+    };
+
+    let (result, solution) = solve_game(&deal.id, game, options);
+    check.ours = result.outcome;
+    if let Some(steps) = solution {
+        let moves: Vec<Action> = steps.into_iter().flat_map(|step| step.expanded).collect();
+        check.our_moves = Some(moves.len());
+        check.problems.extend(check_solution("our", game, &moves));
+    }
+
+    let Some(binary) = fc_solve else {
+        return check;
+    };
+    match run_fc_solve(binary, game, options.max_nodes) {
+        Ok(external) => {
+            check.theirs = Some(external.outcome);
+            if let Some(moves) = &external.moves {
+                check.their_moves = Some(moves.len());
+                check
+                    .problems
+                    .extend(check_solution("fc-solve", game, moves));
+            }
+            // A limit proves nothing, only opposite verdicts are a disagreement
+            let decisive = [Outcome::Solved, Outcome::Unsolvable];
+            if decisive.contains(&check.ours)
+                && decisive.contains(&external.outcome)
+                && check.ours != external.outcome
+            {
+                check.problems.push(format!(
+                    "verdicts differ: {} here, {} for fc-solve",
+                    status(check.ours),
+                    status(external.outcome)
+                ));
+            }
+        }
+        Err(e) => check.problems.push(format!("fc-solve failed: {}", e)),
+    }
+    check
+}
+
+pub fn format_line(check: &Crosscheck) -> String {
+    let side = |outcome: Option<Outcome>, moves: Option<usize>| match (outcome, moves) {
+        (Some(outcome), Some(moves)) => format!("{} ({}m)", status(outcome), moves),
+        (Some(outcome), None) => status(outcome).to_string(),
+        (None, _) => "-".to_string(),
+    };
+    let verdict = match check.agrees() {
+        true => "ok".to_string(),
+        false => check.problems.join("; "),
+    };
+    format!(
+        "{:<10} {:<18} {:<18} {}",
+        check.id,
+        side(Some(check.ours), check.our_moves),
+        side(check.theirs, check.their_moves),
+        verdict
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+    use crate::notation::to_freecell_pro;
+    use crate::solver::Solver;
+
+    #[test]
+    fn test_parse_fc_solve_output() {
+        let game = Game::new(&ms_deal(1));
+        let solution = Solver::new(game.clone()).solve(100_000).unwrap();
+        let mut output = String::from("-=-=-=-=-=-=-=-=-=-=-=-\n\n");
+        for action in &solution {
+            output.push_str(&format!(
+                "{}\n\n====================\n\n",
+                to_freecell_pro(action)
+            ));
+        }
+        output.push_str("This game is solveable.\nTotal number of states checked is 1234.\n");
+
+        let external = parse_fc_solve_output(&game, &output).unwrap();
+        assert_eq!(external.outcome, Outcome::Solved);
+        let moves = external.moves.unwrap();
+        assert!(verify_solution(&game, &moves).unwrap().is_won());
+
+        let external = parse_fc_solve_output(&game, "I could not solve this game.\n").unwrap();
+        assert_eq!(external.outcome, Outcome::Unsolvable);
+        assert!(parse_fc_solve_output(&game, "1h\n").is_err());
+    }
+}
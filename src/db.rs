@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, params};
+
+use crate::batch::BatchResult;
+use crate::game::Game;
+
+pub const SOLVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS results (
+    id INTEGER PRIMARY KEY,
+    deal TEXT NOT NULL,
+    deal_hash TEXT NOT NULL,
+    ruleset TEXT NOT NULL,
+    strategy TEXT NOT NULL,
+    solved INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    moves INTEGER,
+    nodes INTEGER NOT NULL,
+    time_ms REAL NOT NULL,
+    grade INTEGER,
+    solver_version TEXT NOT NULL,
+    recorded_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_deal ON results (deal_hash, ruleset);
+";
+
+// FNV-1a over the layout, unlike Game::hash_key it stays the same across Rust versions
+// so that a database can be shared between builds
+pub fn deal_hash(game: &Game) -> String {
+    let bytes = game
+        .columns
+        .iter()
+        .flat_map(|column| column.iter().map(|c| c.encode()).chain([0xFF]))
+        .chain(
+            game.freecells
+                .iter()
+                .map(|cell| cell.map_or(0, |c| c.encode())),
+        )
+        .chain(game.foundations);
+
+    let hash = bytes.fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+// Rules the deal was played under, deals are only compared within the same ruleset
+pub fn ruleset(game: &Game) -> String {
+    format!("freecell-{}cells", game.cells)
+}
+
+// Runs of one solver version
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionTrend {
+    pub version: String,
+    pub runs: u64,
+    pub solved: u64,
+    pub avg_nodes: f64,
+    pub avg_time_ms: f64,
+    pub avg_moves: Option<f64>,
+}
+
+pub struct ResultsDb {
+    conn: Connection,
+}
+
+impl ResultsDb {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| format!("{}: {}", path, e))?;
+        conn.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+        Ok(ResultsDb { conn })
+    }
+
+    #[cfg(test)]
+    fn in_memory() -> Self {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        ResultsDb { conn }
+    }
+
+    // Records the results in a single transaction, along with the game each came from
+    pub fn record<'a>(
+        &mut self,
+        results: impl IntoIterator<Item = (&'a BatchResult, &'a Game)>,
+    ) -> Result<usize, String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        let mut count = 0;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO results (deal, deal_hash, ruleset, strategy, solved, status,
+                     moves, nodes, time_ms, grade, solver_version, recorded_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                )
+                .map_err(|e| e.to_string())?;
+            for (result, game) in results {
+                insert
+                    .execute(params![
+                        result.id,
+                        deal_hash(game),
+                        ruleset(game),
+                        result.strategy.to_string(),
+                        result.solved(),
+                        result.status(),
+                        result.moves.map(|m| m as i64),
+                        result.nodes as i64,
+                        result.elapsed.as_secs_f64() * 1000.0,
+                        result.grade,
+                        SOLVER_VERSION,
+                        now,
+                    ])
+                    .map_err(|e| e.to_string())?;
+                count += 1;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(count)
+    }
+
+    // (deal_hash, ruleset) of the deals some earlier run solved
+    pub fn solved_deals(&self) -> Result<HashSet<(String, String)>, String> {
+        let mut query = self
+            .conn
+            .prepare("SELECT DISTINCT deal_hash, ruleset FROM results WHERE solved")
+            .map_err(|e| e.to_string())?;
+        query
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .and_then(Iterator::collect)
+            .map_err(|e| e.to_string())
+    }
+
+    // Aggregates per solver version, oldest recording first
+    pub fn version_trends(&self) -> Result<Vec<VersionTrend>, String> {
+        let mut query = self
+            .conn
+            .prepare(
+                "SELECT solver_version, COUNT(*), SUM(solved), AVG(nodes), AVG(time_ms),
+                 AVG(CASE WHEN solved THEN moves END)
+                 FROM results GROUP BY solver_version ORDER BY MIN(recorded_at), solver_version",
+            )
+            .map_err(|e| e.to_string())?;
+        query
+            .query_map([], |row| {
+                Ok(VersionTrend {
+                    version: row.get(0)?,
+                    runs: row.get(1)?,
+                    solved: row.get(2)?,
+                    avg_nodes: row.get(3)?,
+                    avg_time_ms: row.get(4)?,
+                    avg_moves: row.get(5)?,
+                })
+            })
+            .and_then(Iterator::collect)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+    use crate::exit::Outcome;
+    use crate::solver::Strategy;
+    use std::time::Duration;
+
+    #[test]
+    fn test_record_and_query() {
+        let mut db = ResultsDb::in_memory();
+        let solved = Game::new(&ms_deal(1));
+        let unsolved = Game::new(&ms_deal(2));
+        let result = |id: &str, outcome, moves| BatchResult {
+            id: id.to_string(),
+            strategy: Strategy::AStar,
+            outcome,
+            moves,
+            nodes: 500,
+            elapsed: Duration::from_millis(20),
+            error: None,
+            grade: None,
+        };
+        let first = result("1", Outcome::Solved, Some(100));
+        let second = result("2", Outcome::LimitReached, None);
+
+        assert_eq!(db.record([(&first, &solved), (&second, &unsolved)]), Ok(2));
+        assert_ne!(ruleset(&solved.clone().with_cells(3)), ruleset(&solved));
+        assert_eq!(
+            db.solved_deals().unwrap(),
+            HashSet::from([(deal_hash(&solved), "freecell-4cells".to_string())])
+        );
+
+        let trends = db.version_trends().unwrap();
+        assert_eq!(trends.len(), 1);
+        assert_eq!((trends[0].runs, trends[0].solved), (2, 1));
+        assert_eq!(trends[0].avg_moves, Some(100.0));
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+
+#[cfg(feature = "rand")]
+use rand::{SeedableRng, seq::SliceRandom};
+#[cfg(feature = "rand")]
+use rand_chacha::ChaCha8Rng;
+
+use crate::card::{Card, Suit};
+use crate::error::SolverError;
+
+// Card order of the Microsoft shuffle: rank major, then clubs, diamonds, hearts, spades
+const MS_SUITS: [Suit; 4] = [Suit::Club, Suit::Diamond, Suit::Heart, Suit::Spade];
+
+// Linear congruential generator of the Microsoft C runtime, seeded with the deal number
+struct MsRand {
+    state: u32,
+}
+
+impl MsRand {
+    fn next(&mut self) -> u32 {
+        self.state = self.state.wrapping_mul(214013).wrapping_add(2531011) & 0x7fff_ffff;
+        self.state >> 16
+    }
+}
+
+// Cards of Microsoft FreeCell deal `number` in dealing order, ready for `Game::new`:
+// the deck is drawn by swapping the picked card with the last remaining one.
+pub fn ms_deal(number: u32) -> Vec<Card> {
+    let mut rng = MsRand { state: number };
+    let mut deck: Vec<u8> = (0..52).collect();
+    let mut dealt = Vec::with_capacity(52);
+
+    while !deck.is_empty() {
+        let j = rng.next() as usize % deck.len();
+        dealt.push(deck.swap_remove(j));
+    }
+
+    dealt
+        .into_iter()
+        .map(|i| Card {
+            rank: i / 4 + 1,
+            suit: MS_SUITS[(i % 4) as usize],
+        })
+        .collect()
+}
+
+// Shuffled deck, the same for a given seed on every platform and rand version
+#[cfg(feature = "rand")]
+pub fn random_deal(seed: u64) -> Vec<Card> {
+    let mut deck: Vec<Card> = Suit::ALL
+        .iter()
+        .flat_map(|&suit| (1..=13).map(move |rank| Card { rank, suit }))
+        .collect();
+
+    deck.shuffle(&mut ChaCha8Rng::seed_from_u64(seed));
+    deck
+}
+
+// A deal must hold each of the 52 cards exactly once
+pub fn validate_deck(cards: &[Card]) -> Result<(), SolverError> {
+    let mut seen = HashSet::new();
+    for card in cards {
+        if !seen.insert(*card) {
+            return Err(SolverError::DuplicateCard(*card));
+        }
+    }
+
+    if cards.len() != 52 {
+        let missing: Vec<String> = Suit::ALL
+            .iter()
+            .flat_map(|&suit| (1..=13).map(move |rank| Card { rank, suit }))
+            .filter(|card| !seen.contains(card))
+            .map(|card| card.to_string())
+            .collect();
+        return Err(SolverError::IncompleteDeck {
+            found: cards.len(),
+            missing: missing.join(" "),
+        });
+    }
+    Ok(())
+}
+
+// Cards separated by spaces or commas, in dealing order: "AS 7D KC ..."
+pub fn parse_deck(text: &str) -> Result<Vec<Card>, SolverError> {
+    let cards = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<Card>, _>>()?;
+    validate_deck(&cards)?;
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn first_row(number: u32) -> String {
+        ms_deal(number)[..8]
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_ms_deals() {
+        assert_eq!(first_row(1), "JD 2D 9H JC 5D 7H 7C 5H");
+        assert_eq!(first_row(617), "7D AD 5C 3S 5S 8C 2D AH");
+        assert_eq!(ms_deal(11982).len(), 52);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_random_deal_is_reproducible() {
+        assert_eq!(random_deal(42), random_deal(42));
+        assert_ne!(random_deal(42), random_deal(43));
+        assert!(validate_deck(&random_deal(42)).is_ok());
+    }
+
+    #[test]
+    fn test_parse_deck() {
+        let text: Vec<String> = ms_deal(617).iter().map(|c| c.to_string()).collect();
+        assert_eq!(parse_deck(&text.join(" ")).unwrap(), ms_deal(617));
+
+        let short = text[1..].join(", ");
+        assert!(
+            parse_deck(&short)
+                .unwrap_err()
+                .to_string()
+                .contains("missing: 7D")
+        );
+
+        let twice = format!("{} AD", text.join(" "));
+        assert!(
+            parse_deck(&twice)
+                .unwrap_err()
+                .to_string()
+                .contains("AD appears twice")
+        );
+        assert!(parse_deck("AS XX").is_err());
+    }
+}
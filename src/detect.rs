@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use image::{Rgba, RgbaImage};
+
+use crate::layout::Geometry;
+
+// Card faces are light and grey, the felt and the ink are not
+pub(crate) fn is_face(img: &RgbaImage, x: u32, y: u32) -> bool {
+    let [r, g, b, _] = img.get_pixel(x, y).0;
+    r.min(g).min(b) >= 200 && r.max(g).max(b) - r.min(g).min(b) <= 30
+}
+
+// Runs of x within `area` (x, y, width, height) where card faces fill at least half as
+// many rows as in the fullest one. Ink running down a whole column can split it, so
+// runs are merged across the narrowest gaps down to `expected`: columns are the
+// widest gaps apart.
+pub(crate) fn face_columns(
+    img: &RgbaImage,
+    [left, top, width, height]: [u32; 4],
+    expected: usize,
+) -> Result<Vec<(u32, u32)>, String> {
+    let counts: Vec<u32> = (left..left + width)
+        .map(|x| (top..top + height).filter(|&y| is_face(img, x, y)).count() as u32)
+        .collect();
+    let most = counts.iter().copied().max().unwrap_or(0);
+    if most == 0 {
+        return Err("No card found on the screenshot".to_string());
+    }
+
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for (x, &count) in (left..).zip(&counts) {
+        if 2 * count < most {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == x => *end = x,
+            _ => runs.push((x, x)),
+        }
+    }
+    while runs.len() > expected {
+        let narrowest = (1..runs.len())
+            .min_by_key(|&i| runs[i].0 - runs[i - 1].1)
+            .unwrap();
+        runs[narrowest - 1].1 = runs.remove(narrowest).1;
+    }
+    if runs.len() != expected {
+        return Err(format!(
+            "Found {} columns on the screenshot, expected {}",
+            runs.len(),
+            expected
+        ));
+    }
+    Ok(runs)
+}
+
+// The most common of `pixels`, in steps of 8 per channel
+fn common_color(pixels: impl Iterator<Item = Rgba<u8>>) -> Option<Rgba<u8>> {
+    let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+    for pixel in pixels {
+        let [r, g, b, _] = pixel.0;
+        *counts.entry([r >> 3, g >> 3, b >> 3]).or_default() += 1;
+    }
+    let ([r, g, b], _) = counts.into_iter().max_by_key(|&(_, count)| count)?;
+    Some(Rgba([r << 3 | 4, g << 3 | 4, b << 3 | 4, 255]))
+}
+
+fn is_felt(felt: Rgba<u8>, pixel: &Rgba<u8>) -> bool {
+    felt.0[..3]
+        .iter()
+        .zip(&pixel.0[..3])
+        .all(|(a, b)| a.abs_diff(*b) <= 24)
+}
+
+// First and last index whose count reaches a quarter of the highest one
+fn span(counts: &[usize]) -> Option<(u32, u32)> {
+    let most = *counts.iter().max()?;
+    let kept = |count: &usize| most > 0 && 4 * count >= most;
+    let first = counts.iter().position(kept)?;
+    let last = counts.iter().rposition(kept)?;
+    Some((first as u32, last as u32))
+}
+
+// Fraction of the pixels of two frames that differ beyond compression noise, all of
+// them when the frames do not have the same size
+pub fn frame_difference(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    if a.dimensions() != b.dimensions() || a.is_empty() {
+        return 1.0;
+    }
+    let changed = a
+        .pixels()
+        .zip(b.pixels())
+        .filter(|(p, q)| p.0.iter().zip(&q.0).any(|(x, y)| x.abs_diff(*y) > 8))
+        .count();
+    changed as f64 / (a.width() * a.height()) as f64
+}
+
+// The game area of a screenshot and where the board sits in it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectedBoard {
+    // x1, y1, x2, y2 as in `[screen] region`
+    pub region: [i32; 4],
+    // In fractions of the whole screenshot, as `preset_board` expects them
+    pub geometry: Geometry,
+}
+
+// Finds the board on a screenshot of a deal, without knowing the client: the 8 columns
+// are where card faces are, see `face_columns`, the game area is where the felt is.
+// Card tops are the lines across a column where the face is interrupted, so the last
+// card of the longest column gives the card height. The freecells and foundations are
+// taken in line with the columns, from the first line above them that is not felt.
+pub fn detect_board(img: &RgbaImage) -> Result<DetectedBoard, String> {
+    let (width, height) = img.dimensions();
+    let runs = face_columns(img, [0, 0, width, height], 8)?;
+    let mut widths: Vec<u32> = runs.iter().map(|(start, end)| end + 1 - start).collect();
+    widths.sort();
+    let card_width = widths[widths.len() / 2];
+    let column_step = (runs[7].0 - runs[0].0) as f64 / 7.0;
+
+    // Rows of each column showing a card face, up from its last card: cards overlap,
+    // the few rows of an outline aside there is no gap until the freecells
+    let extents: Vec<(u32, u32)> = runs
+        .iter()
+        .map(|&(start, end)| {
+            let rows: Vec<u32> = (0..height)
+                .filter(|&y| (start..=end).any(|x| is_face(img, x, y)))
+                .collect();
+            let first = (1..rows.len())
+                .rev()
+                .find(|&i| rows[i] - rows[i - 1] > 3)
+                .unwrap_or(0);
+            (rows[first], rows[rows.len() - 1])
+        })
+        .collect();
+
+    // The felt shows between the columns, the game area is where it is
+    let gaps = runs
+        .windows(2)
+        .zip(&extents)
+        .flat_map(|(pair, &(top, bottom))| {
+            (pair[0].1 + 1..pair[1].0)
+                .flat_map(move |x| (top..=bottom).map(move |y| *img.get_pixel(x, y)))
+        });
+    let felt = common_color(gaps).ok_or("No game area found on the screenshot")?;
+    let mut felt_columns = vec![0; width as usize];
+    let mut felt_rows = vec![0; height as usize];
+    for (x, y, pixel) in img.enumerate_pixels() {
+        if is_felt(felt, pixel) {
+            felt_columns[x as usize] += 1;
+            felt_rows[y as usize] += 1;
+        }
+    }
+    let ((x1, x2), (y1, y2)) = span(&felt_columns)
+        .zip(span(&felt_rows))
+        .ok_or("No game area found on the screenshot")?;
+
+    let columns_top = extents.iter().map(|&(top, _)| top).min().unwrap_or(y1);
+    let (longest, &(top, bottom)) = extents
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, (_, bottom))| *bottom)
+        .ok_or("No card found on the screenshot")?;
+    let (start, end) = runs[longest];
+    let last_top = (top..=bottom)
+        .rev()
+        .find(|&y| 2 * (start..=end).filter(|&x| is_face(img, x, y)).count() < card_width as usize)
+        .map_or(top, |edge| edge + 1);
+    let card_height = bottom + 1 - last_top;
+
+    let top_row = (y1..columns_top.saturating_sub(card_width / 4))
+        .find(|&y| (runs[0].0..=runs[7].1).any(|x| !is_felt(felt, img.get_pixel(x, y))))
+        .unwrap_or(y1);
+
+    let (w, h) = (width as f64, height as f64);
+    let first_column = runs[0].0 as f64 / w;
+    Ok(DetectedBoard {
+        region: [x1 as i32, y1 as i32, x2 as i32 + 1, y2 as i32 + 1],
+        geometry: Geometry {
+            card_width: card_width as f64 / w,
+            card_height: card_height as f64 / h,
+            top_row: top_row as f64 / h,
+            columns_top: columns_top as f64 / h,
+            first_column,
+            column_step: column_step / w,
+            first_freecell: first_column,
+            first_foundation: first_column + 4.0 * column_step / w,
+            slot_step: column_step / w,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+    use crate::game::Game;
+    use crate::render::{CARD_HEIGHT, CARD_WIDTH, COLUMNS_Y, MARGIN, render_game, slot_x};
+
+    #[test]
+    fn test_detect_board() {
+        // The board in a window on a grey desktop
+        let board = render_game(&Game::new(&ms_deal(617)));
+        let mut screen = RgbaImage::from_pixel(800, 600, Rgba([90, 90, 100, 255]));
+        image::imageops::replace(&mut screen, &board, 100, 50);
+
+        let found = detect_board(&screen).unwrap();
+        let [x1, y1, x2, y2] = found.region;
+        assert_eq!((x1, y1), (100, 50));
+        assert_eq!(
+            (x2 - x1, y2 - y1),
+            (board.width() as i32, board.height() as i32)
+        );
+
+        let geometry = found.geometry;
+        for i in 0..8 {
+            let expected = 100 + slot_x(i as u32) as i32 + 1;
+            assert!((geometry.column_left(i, 800) - expected).abs() <= 1);
+        }
+        let pixels = |fraction: f64, size: u32| (fraction * size as f64).round() as u32;
+        assert_eq!(pixels(geometry.card_width, 800), CARD_WIDTH - 2);
+        assert_eq!(pixels(geometry.card_height, 600), CARD_HEIGHT - 2);
+        assert_eq!(pixels(geometry.columns_top, 600), 50 + COLUMNS_Y + 1);
+        assert_eq!(pixels(geometry.top_row, 600), 50 + MARGIN);
+
+        assert!(detect_board(&RgbaImage::from_pixel(80, 60, Rgba([0, 90, 0, 255]))).is_err());
+    }
+
+    #[test]
+    fn test_frame_difference() {
+        let frame = render_game(&Game::new(&ms_deal(617)));
+        let mut noisy = frame.clone();
+        noisy
+            .pixels_mut()
+            .for_each(|p| p.0[0] = p.0[0].saturating_add(3));
+        assert_eq!(frame_difference(&frame, &noisy), 0.0);
+
+        // A card halfway through its move
+        let mut moving = frame.clone();
+        image::imageops::replace(
+            &mut moving,
+            &RgbaImage::from_pixel(40, 50, Rgba([250; 4])),
+            5,
+            5,
+        );
+        let changed = frame_difference(&frame, &moving);
+        assert!(changed > 0.0 && changed < 0.1);
+        assert_eq!(frame_difference(&frame, &RgbaImage::new(10, 10)), 1.0);
+    }
+}
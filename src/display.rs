@@ -0,0 +1,156 @@
+use std::fmt::Write;
+use std::io::IsTerminal;
+
+use serde::Deserialize;
+
+use crate::card::{Card, Suit};
+use crate::config::DisplaySettings;
+use crate::game::Game;
+
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardStyle {
+    // Rank and suit symbol, like the Debug output: " 10♥"
+    #[default]
+    Symbols,
+    // Two-character codes: "TH"
+    Codes,
+    // Playing card characters of the U+1F0A1 block: "🂺"
+    Glyphs,
+}
+
+// Unicode has a knight between the jack and the queen
+fn glyph(card: &Card) -> char {
+    let base = match card.suit {
+        Suit::Spade => 0x1F0A0,
+        Suit::Heart => 0x1F0B0,
+        Suit::Diamond => 0x1F0C0,
+        Suit::Club => 0x1F0D0,
+    };
+    let offset = if card.rank >= 12 {
+        card.rank + 1
+    } else {
+        card.rank
+    };
+    char::from_u32(base + offset as u32).unwrap_or('?')
+}
+
+// How boards are printed on the terminal
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BoardStyle {
+    // Hearts and diamonds in red
+    pub color: bool,
+    pub cards: CardStyle,
+}
+
+impl BoardStyle {
+    // Colors unless turned off with --no-color, the config or NO_COLOR, or stdout is not a terminal
+    pub fn detect(no_color: bool, settings: &DisplaySettings) -> Self {
+        BoardStyle {
+            color: !no_color
+                && settings.color
+                && std::env::var_os("NO_COLOR").is_none()
+                && std::io::stdout().is_terminal(),
+            cards: settings.cards,
+        }
+    }
+
+    // Terminal columns taken by a card
+    pub fn width(&self) -> usize {
+        match self.cards {
+            CardStyle::Symbols => 4,
+            CardStyle::Codes => 3,
+            CardStyle::Glyphs => 2,
+        }
+    }
+
+    pub fn card(&self, card: &Card) -> String {
+        let text = match self.cards {
+            CardStyle::Symbols => format!("{:?}", card),
+            CardStyle::Codes => format!(" {}", card),
+            CardStyle::Glyphs => format!("{} ", glyph(card)),
+        };
+        if self.color && !card.is_black() {
+            format!("{}{}{}", RED, text, RESET)
+        } else {
+            text
+        }
+    }
+
+    pub fn empty(&self, text: &str) -> String {
+        format!("{:^width$}", text, width = self.width())
+    }
+
+    // Same layout as the Debug output of Game. The compact styles show the top card
+    // of each foundation instead of the number of cards.
+    pub fn board(&self, game: &Game) -> String {
+        let mut out = String::new();
+
+        for cell in &game.freecells {
+            match cell {
+                Some(card) => out.push_str(&self.card(card)),
+                None if self.width() > 2 => out.push_str(&self.empty("--")),
+                None => out.push_str(&self.empty("-")),
+            }
+        }
+        for (&count, &suit) in game.foundations.iter().zip(Suit::ALL.iter()) {
+            match (self.cards, count) {
+                (CardStyle::Symbols, _) => {
+                    let _ = write!(out, "{:>4}", count);
+                }
+                (_, 0) => out.push_str(&self.empty(".")),
+                _ => out.push_str(&self.card(&Card { rank: count, suit })),
+            }
+        }
+        out.push_str("\n\n");
+
+        let max_rows = game.columns.iter().map(Vec::len).max().unwrap_or(0);
+        for row in 0..max_rows {
+            for column in &game.columns {
+                match column.get(row) {
+                    Some(card) => out.push_str(&self.card(card)),
+                    None => out.push_str(&self.empty("")),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_board_matches_debug_without_color() {
+        let deck: Vec<Card> = ["AS", "KH", "7D", "TC", "QS", "2H", "9C", "JD", "5S"]
+            .iter()
+            .map(|&c| Card::try_from(c).unwrap())
+            .collect();
+        let game = Game::new(&deck);
+
+        let plain = BoardStyle::default();
+        assert_eq!(plain.board(&game), format!("{:?}", game));
+        let colored = BoardStyle {
+            color: true,
+            ..plain
+        }
+        .board(&game);
+        assert!(colored.contains("\x1b[31m  K♥\x1b[0m"));
+        assert!(!colored.contains("\x1b[31m  A♠"));
+    }
+
+    #[test]
+    fn test_card_glyphs() {
+        let glyphs: String = ["AS", "TH", "QD", "KC"]
+            .iter()
+            .map(|&c| glyph(&Card::try_from(c).unwrap()))
+            .collect();
+        assert_eq!(glyphs, "🂡🂺🃍🃞");
+    }
+}
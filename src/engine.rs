@@ -0,0 +1,295 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use freecell::action::Action;
+use freecell::batch::BatchOptions;
+use freecell::board::parse_board;
+use freecell::deal::{ms_deal, parse_deck, random_deal};
+use freecell::game::Game;
+use freecell::output::{game_json, solution_json};
+use freecell::postprocess::{merge_supermoves, remove_wasted_moves};
+use freecell::solver::{SearchControl, Solver, Strategy};
+
+const PROGRESS_EVERY: Duration = Duration::from_millis(250);
+
+// One JSON command per line on stdin, e.g. {"cmd": "go", "max_nodes": 500000}
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase", deny_unknown_fields)]
+enum Command {
+    // Start a deal: one of deal, seed, cards or board
+    Newgame {
+        deal: Option<u32>,
+        seed: Option<u64>,
+        cards: Option<String>,
+        board: Option<String>,
+    },
+    // Moves played since the start of the deal, as the JSON of `Action`
+    Position {
+        moves: Vec<Action>,
+    },
+    Go {
+        strategy: Option<Strategy>,
+        max_nodes: Option<u32>,
+        // Seconds
+        time_limit: Option<f64>,
+    },
+    Stop,
+    Quit,
+}
+
+// Events are written one JSON object per line, from the search thread as well
+type Emit = Arc<dyn Fn(Value) + Send + Sync>;
+
+struct Search {
+    control: SearchControl,
+    handle: JoinHandle<()>,
+}
+
+pub struct Engine {
+    start: Game,
+    game: Game,
+    options: BatchOptions,
+    search: Option<Search>,
+    emit: Emit,
+}
+
+fn new_game(
+    deal: Option<u32>,
+    seed: Option<u64>,
+    cards: Option<String>,
+    board: Option<String>,
+) -> Result<Game, String> {
+    match (deal, seed, cards, board) {
+        (Some(number @ 1..=0x7fff_ffff), None, None, None) => Ok(Game::new(&ms_deal(number))),
+        (Some(number), None, None, None) => Err(format!("Invalid deal number: {}", number)),
+        (None, Some(seed), None, None) => Ok(Game::new(&random_deal(seed))),
+        (None, None, Some(cards), None) => parse_deck(&cards)
+            .map(|deck| Game::new(&deck))
+            .map_err(|e| e.to_string()),
+        (None, None, None, Some(board)) => parse_board(&board).map_err(|e| e.to_string()),
+        _ => Err("Expected exactly one of deal, seed, cards or board".to_string()),
+    }
+}
+
+fn search(game: Game, mut solver: Solver, max_nodes: u32, control: SearchControl, emit: Emit) {
+    let started = Instant::now();
+    let (done, finished) = mpsc::channel::<()>();
+    thread::scope(|scope| {
+        let progress = emit.clone();
+        let nodes = control.clone();
+        // Reports until `done` is dropped at the end of the search
+        scope.spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(PROGRESS_EVERY) {
+                progress(json!({
+                    "event": "progress",
+                    "nodes": nodes.nodes(),
+                    "elapsed_ms": started.elapsed().as_millis() as u64,
+                }));
+            }
+        });
+
+        let solution = solver.solve(max_nodes);
+        drop(done);
+        let steps = solution.map(|solution| {
+            let solution = remove_wasted_moves(&game, &solution);
+            merge_supermoves(&game, &solution)
+        });
+        let mut result = solution_json(&game, steps.as_deref(), &solver.stats, false);
+        result["event"] = json!("result");
+        result["limit_reached"] = json!(solver.limit_reached);
+        result["stopped"] = json!(control.is_stopped());
+        emit(result);
+    });
+}
+
+impl Engine {
+    fn new(game: Game, options: BatchOptions, emit: Emit) -> Self {
+        Engine {
+            start: game.clone(),
+            game,
+            options,
+            search: None,
+            emit,
+        }
+    }
+
+    fn error(&self, message: impl ToString) {
+        (self.emit)(json!({ "event": "error", "message": message.to_string() }));
+    }
+
+    fn position(&self) {
+        (self.emit)(json!({
+            "event": "position",
+            "deal": game_json(&self.game),
+            "won": self.game.is_won(),
+        }));
+    }
+
+    fn searching(&mut self) -> bool {
+        if self
+            .search
+            .as_ref()
+            .is_some_and(|search| search.handle.is_finished())
+        {
+            self.wait();
+        }
+        self.search.is_some()
+    }
+
+    fn wait(&mut self) {
+        if let Some(search) = self.search.take() {
+            let _ = search.handle.join();
+        }
+    }
+
+    fn go(&mut self, strategy: Option<Strategy>, max_nodes: Option<u32>, time_limit: Option<f64>) {
+        let time_limit = match time_limit {
+            Some(seconds) => match Duration::try_from_secs_f64(seconds) {
+                Ok(limit) if seconds > 0.0 => Some(limit),
+                _ => return self.error(format!("Invalid time_limit: {}", seconds)),
+            },
+            None => None,
+        };
+        let mut solver = Solver::new(self.game.clone());
+        solver.verbose = false;
+        solver.strategy = strategy.unwrap_or(self.options.strategy);
+        solver.weights = self.options.weights;
+        solver.time_limit = time_limit.or(self.options.time_limit);
+        let control = SearchControl::default();
+        solver.control = Some(control.clone());
+
+        let game = self.game.clone();
+        let max_nodes = max_nodes.unwrap_or(self.options.max_nodes);
+        let (thread_control, emit) = (control.clone(), self.emit.clone());
+        let handle = thread::spawn(move || search(game, solver, max_nodes, thread_control, emit));
+        self.search = Some(Search { control, handle });
+    }
+
+    // False once the engine should exit
+    pub fn handle(&mut self, line: &str) -> bool {
+        let command = match serde_json::from_str::<Command>(line) {
+            Ok(command) => command,
+            Err(e) => {
+                self.error(format!("Invalid command: {}", e));
+                return true;
+            }
+        };
+        let busy = self.searching();
+        match command {
+            Command::Stop | Command::Quit => {
+                if let Some(search) = &self.search {
+                    search.control.stop();
+                }
+                self.wait();
+                return !matches!(command, Command::Quit);
+            }
+            _ if busy => self.error("A search is running, send stop first"),
+            Command::Newgame {
+                deal,
+                seed,
+                cards,
+                board,
+            } => match new_game(deal, seed, cards, board) {
+                Ok(game) => {
+                    self.start = game.clone();
+                    self.game = game;
+                    self.position();
+                }
+                Err(e) => self.error(e),
+            },
+            Command::Position { moves } => {
+                let mut game = self.start.clone();
+                match moves
+                    .iter()
+                    .try_for_each(|action| game.apply(action).map(|_| ()))
+                {
+                    Ok(()) => {
+                        self.game = game;
+                        self.position();
+                    }
+                    Err(e) => self.error(format!("Illegal move: {}", e)),
+                }
+            }
+            Command::Go {
+                strategy,
+                max_nodes,
+                time_limit,
+            } => self.go(strategy, max_nodes, time_limit),
+        }
+        true
+    }
+}
+
+// Reads commands until quit or the end of stdin
+pub fn run_engine(options: BatchOptions) {
+    // A single lock, so that lines of the two threads never mix
+    let stdout = Arc::new(Mutex::new(()));
+    let emit: Emit = Arc::new(move |event: Value| {
+        let _guard = stdout.lock();
+        println!("{}", event);
+    });
+    emit(json!({ "event": "ready", "version": env!("CARGO_PKG_VERSION") }));
+
+    let mut engine = Engine::new(Game::new(&ms_deal(1)), options, emit);
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if !line.trim().is_empty() && !engine.handle(&line) {
+            return;
+        }
+    }
+    engine.handle(r#"{"cmd": "quit"}"#);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use freecell::solver::HeuristicWeights;
+
+    fn engine() -> (Engine, Arc<Mutex<Vec<Value>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let options = BatchOptions {
+            strategy: Strategy::AStar,
+            max_nodes: 100_000,
+            time_limit: None,
+            weights: HeuristicWeights::default(),
+            optimize: true,
+            rate: false,
+        };
+        let emit: Emit = Arc::new(move |event| sink.lock().unwrap().push(event));
+        (Engine::new(Game::new(&ms_deal(1)), options, emit), events)
+    }
+
+    #[test]
+    fn test_newgame_position_go() {
+        let (mut engine, events) = engine();
+        engine.handle(r#"{"cmd": "newgame", "deal": 1}"#);
+        let first = Solver::new(engine.game.clone()).get_moves(&engine.game)[0];
+        let position = json!({ "cmd": "position", "moves": [first] });
+        engine.handle(&position.to_string());
+        engine.handle(r#"{"cmd": "go", "max_nodes": 500000}"#);
+        engine.wait();
+        engine.handle(r#"{"cmd": "go", "time_limit": 1e30}"#);
+        engine.handle(r#"{"cmd": "bogus"}"#);
+
+        // Progress events depend on the speed of the search
+        let events: Vec<Value> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e["event"] != "progress")
+            .cloned()
+            .collect();
+        let kinds: Vec<&str> = events.iter().filter_map(|e| e["event"].as_str()).collect();
+        assert_eq!(kinds, ["position", "position", "result", "error", "error"]);
+        assert_eq!(events[2]["solved"], true);
+        assert_eq!(events[2]["stopped"], false);
+    }
+}
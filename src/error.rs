@@ -0,0 +1,58 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::card::Card;
+
+// Why a card code such as "TD" could not be read
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CardError {
+    #[error("Empty card")]
+    Empty,
+    #[error("Invalid rank in card: {0}")]
+    InvalidRank(String),
+    #[error("Invalid suit character: {0}")]
+    InvalidSuit(char),
+}
+
+// Reading the cards of a screenshot: the OpenCV and capture errors are kept as text
+// so that the library does not depend on those crates
+#[derive(Debug, Error)]
+pub enum OcrError {
+    #[error("Could not load image {path}: {reason}")]
+    Image { path: String, reason: String },
+    #[error("Image {0} is empty")]
+    EmptyImage(String),
+    #[error("Invalid template pattern {pattern}: {reason}")]
+    Templates { pattern: String, reason: String },
+    #[error("Template matching failed for {path}: {reason}")]
+    Matching { path: String, reason: String },
+    #[error("Template {path} is not named after a card: {source}")]
+    TemplateName { path: String, source: CardError },
+    #[error("Screen capture failed: {0}")]
+    Capture(String),
+    #[error("Ambiguous card layout: {0}")]
+    Layout(String),
+    #[error("Could not show the card: {0}")]
+    Window(String),
+}
+
+// Everything that can go wrong while setting up a game, from a card list, a board
+// file or a screenshot
+#[derive(Debug, Error)]
+pub enum SolverError {
+    #[error(transparent)]
+    Card(#[from] CardError),
+    #[error("Card {0} appears twice")]
+    DuplicateCard(Card),
+    #[error("Expected 52 cards, found {found} (missing: {missing})")]
+    IncompleteDeck { found: usize, missing: String },
+    #[error("{0}")]
+    Board(String),
+    #[error("Invalid search checkpoint: {0}")]
+    Snapshot(String),
+    #[error(transparent)]
+    Ocr(#[from] OcrError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
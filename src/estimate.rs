@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::game::Game;
+use crate::solver::{Solver, Strategy};
+
+// Above this many estimated nodes (log10) A* tends to run out of memory before DFS gets lost
+const ASTAR_MAX_LOG10_NODES: f64 = 60.0;
+const MIN_NODE_LIMIT: u32 = 100_000;
+const MAX_NODE_LIMIT: u32 = 5_000_000;
+
+#[derive(Debug, Clone)]
+pub struct TreeEstimate {
+    pub probes: usize,
+    pub mean_depth: f64,
+    pub branching_factor: f64,
+    // log10 of the estimated tree size, the raw value overflows quickly
+    pub log10_nodes: f64,
+    pub won_probes: usize,
+}
+
+impl TreeEstimate {
+    pub fn estimated_nodes(&self) -> f64 {
+        10f64.powf(self.log10_nodes)
+    }
+
+    pub fn suggested_strategy(&self) -> Strategy {
+        if self.log10_nodes <= ASTAR_MAX_LOG10_NODES {
+            Strategy::AStar
+        } else {
+            Strategy::Dfs
+        }
+    }
+
+    pub fn suggested_node_limit(&self) -> u32 {
+        let nodes = self.estimated_nodes().min(MAX_NODE_LIMIT as f64) as u32;
+        nodes
+            .saturating_mul(2)
+            .clamp(MIN_NODE_LIMIT, MAX_NODE_LIMIT)
+    }
+}
+
+// Knuth's estimator: each random rollout from the root predicts
+// 1 + b1 + b1*b2 + ... nodes where bi is the branching factor seen at depth i.
+// Within a rollout, moves back to an already visited state are not counted so
+// that probes don't loop on freecell shuttles.
+pub fn estimate_tree_size<R: Rng>(
+    game: &Game,
+    probes: usize,
+    max_depth: usize,
+    rng: &mut R,
+) -> TreeEstimate {
+    let solver = Solver::new(game.clone());
+
+    let mut log10_sum = f64::NEG_INFINITY;
+    let mut total_depth = 0;
+    let mut total_branching = 0;
+    let mut won_probes = 0;
+
+    for _ in 0..probes {
+        let mut state = game.clone();
+        let mut seen = HashSet::new();
+        seen.insert(state.hash_key());
+
+        let mut log10_estimate = 0.0;
+        let mut log10_product = 0.0;
+        let mut depth = 0;
+
+        while depth < max_depth && !state.is_won() {
+            let children: Vec<_> = solver
+                .get_moves(&state)
+                .into_iter()
+                .filter(|mov| !seen.contains(&solver.apply_move(&state, mov).hash_key()))
+                .collect();
+
+            let Some(mov) = children.choose(rng) else {
+                break;
+            };
+
+            log10_product += (children.len() as f64).log10();
+            log10_estimate = log10_add(log10_estimate, log10_product);
+            total_branching += children.len();
+
+            state.apply_unchecked(mov);
+            seen.insert(state.hash_key());
+            depth += 1;
+        }
+
+        if state.is_won() {
+            won_probes += 1;
+        }
+        log10_sum = log10_add(log10_sum, log10_estimate);
+        total_depth += depth;
+    }
+
+    let probes_f = probes.max(1) as f64;
+    TreeEstimate {
+        probes,
+        mean_depth: total_depth as f64 / probes_f,
+        branching_factor: total_branching as f64 / total_depth.max(1) as f64,
+        log10_nodes: (log10_sum - probes_f.log10()).max(0.0),
+        won_probes,
+    }
+}
+
+// log10(10^a + 10^b) without leaving log space
+fn log10_add(a: f64, b: f64) -> f64 {
+    let (hi, lo) = if a >= b { (a, b) } else { (b, a) };
+    if lo == f64::NEG_INFINITY {
+        return hi;
+    }
+    hi + (1.0 + 10f64.powf(lo - hi)).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::deal::ms_deal;
+
+    #[test]
+    fn test_estimate_tree_size() {
+        let game = Game::new(&ms_deal(164));
+        let estimate = estimate_tree_size(&game, 20, 200, &mut ChaCha8Rng::seed_from_u64(7));
+        assert_eq!((estimate.probes, estimate.won_probes), (20, 0));
+        assert_eq!(estimate.mean_depth, 46.65);
+        assert!((estimate.branching_factor - 7.7599).abs() < 1e-4);
+        assert!((estimate.log10_nodes - 116.0770).abs() < 1e-4);
+        assert_eq!(estimate.suggested_strategy(), Strategy::Dfs);
+        assert_eq!(estimate.suggested_node_limit(), MAX_NODE_LIMIT);
+
+        // Every probe ends at once on the root, a tree of one node
+        let won = Game {
+            foundations: [13; 4],
+            ..Game::new(&[])
+        };
+        let estimate = estimate_tree_size(&won, 10, 200, &mut ChaCha8Rng::seed_from_u64(7));
+        assert_eq!((estimate.probes, estimate.won_probes), (10, 10));
+        assert_eq!((estimate.mean_depth, estimate.branching_factor), (0.0, 0.0));
+        assert_eq!(estimate.log10_nodes, 0.0);
+        assert_eq!(estimate.estimated_nodes(), 1.0);
+        assert_eq!(estimate.suggested_strategy(), Strategy::AStar);
+        assert_eq!(estimate.suggested_node_limit(), MIN_NODE_LIMIT);
+    }
+}
@@ -0,0 +1,18 @@
+use std::process::ExitCode;
+
+// Process exit codes, so that scripts can branch on the result without parsing the output.
+// 2 is also what clap uses for command line errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Solved = 0,
+    // The whole search tree was explored without finding a solution
+    Unsolvable = 1,
+    InputError = 2,
+    LimitReached = 3,
+}
+
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        ExitCode::from(outcome as u8)
+    }
+}
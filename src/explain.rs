@@ -0,0 +1,85 @@
+use crate::action::{Action, ActionType, Location};
+use crate::card::Card;
+use crate::game::Game;
+use crate::i18n::Msg;
+use crate::notation;
+
+fn name(card: &Card) -> String {
+    format!("{:?}", card).trim_start().to_string()
+}
+
+fn column_name(index: usize) -> String {
+    Msg::Column(index + 1).to_string()
+}
+
+// Short human-readable reason for a move, derived by comparing the position before and after
+pub fn explain(before: &Game, action: &Action) -> String {
+    let moved = before.cards_moved_by(action);
+    let Some(&lead) = moved.first() else {
+        return String::new();
+    };
+    let mut after = before.clone();
+    after.apply_unchecked(action);
+
+    let lead = name(&lead);
+    let mut reasons = Vec::new();
+
+    match action.dest_location() {
+        Location::Foundation(_) => reasons.push(Msg::PlaysToFoundation(&lead).to_string()),
+        Location::Freecell(_) => match after.columns[action.source].last() {
+            Some(uncovered) => {
+                reasons.push(Msg::ParksToUnbury(&lead, &name(uncovered)).to_string())
+            }
+            None => reasons.push(Msg::Parks(&lead).to_string()),
+        },
+        Location::Column(dest) => match before.columns[dest].last() {
+            Some(target) => reasons.push(Msg::BuildsOn(&lead, &name(target)).to_string()),
+            None if moved.len() > 1 => {
+                reasons.push(Msg::PileToEmpty(moved.len(), &column_name(dest)).to_string())
+            }
+            None => reasons.push(Msg::CardToEmpty(&lead, &column_name(dest)).to_string()),
+        },
+    }
+
+    if let Location::Column(source) = action.source_location() {
+        match after.columns[source].last() {
+            Some(uncovered) if after.can_move_to_foundation(uncovered) => {
+                reasons.push(Msg::FreesForFoundation(&name(uncovered)).to_string())
+            }
+            None => reasons.push(Msg::Empties(&column_name(source)).to_string()),
+            _ => {}
+        }
+    }
+
+    if action.action_type == ActionType::FreecellToCol {
+        let freecell = notation::to_standard(action).chars().next().unwrap_or('?');
+        reasons.push(Msg::FreesFreecell(freecell).to_string());
+    }
+
+    reasons.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_explain_park_and_free() {
+        let mut game = Game::new(&[]);
+        game.columns[0] = vec![
+            Card::try_from("1D").unwrap(),
+            Card::try_from("12S").unwrap(),
+        ];
+        game.columns[1] = vec![Card::try_from("11H").unwrap()];
+
+        let park = Action::between(Location::Column(0), Location::Freecell(0), 1).unwrap();
+        assert_eq!(
+            explain(&game, &park),
+            "met Q♠ de côté pour dégager A♦, libère A♦ pour la fondation"
+        );
+
+        let empty = Action::between(Location::Column(1), Location::Column(0), 1).unwrap();
+        assert_eq!(explain(&game, &empty), "pose J♥ sur Q♠, vide la colonne 2");
+    }
+}
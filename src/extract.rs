@@ -0,0 +1,163 @@
+#[cfg(feature = "png")]
+use image::RgbaImage;
+
+use std::path::{Path, PathBuf};
+
+use crate::card::Card;
+#[cfg(feature = "png")]
+use crate::detect::{face_columns, is_face};
+use crate::game::Game;
+
+// Where a card shows on a screenshot: the strip of its face left visible by the card
+// below, the whole face for the last card of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardCrop {
+    pub card: Card,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Template file name, with the rank as a number like the shipped templates: "10D.png"
+pub fn template_name(card: &Card) -> String {
+    format!("{}{}.png", card.rank, card.suit.to_char())
+}
+
+// A free path for one more template of `card`: "10D.png", then "10D-2.png"... Every
+// variant is matched, a card looking different in some spots gets one for each look.
+pub fn new_template_path(folder: &str, card: &Card) -> PathBuf {
+    let name = template_name(card);
+    let stem = name.trim_end_matches(".png");
+    (1..)
+        .map(|n| match n {
+            1 => Path::new(folder).join(&name),
+            n => Path::new(folder).join(format!("{}-{}.png", stem, n)),
+        })
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+// Finds the cards of `game` on a screenshot of it. Columns are found by
+// `face_columns`, there must be one per column of the game. The spacing between two
+// cards of a column comes from the bottoms of the longest and shortest columns, which
+// start at the same height.
+#[cfg(feature = "png")]
+pub fn locate_cards(img: &RgbaImage, game: &Game) -> Result<Vec<CardCrop>, String> {
+    let area = [0, 0, img.width(), img.height()];
+    let runs = face_columns(img, area, game.columns.len())?;
+
+    // First and last row showing a card face, per column
+    let extents: Vec<(u32, u32)> = runs
+        .iter()
+        .map(|&(start, end)| {
+            let rows: Vec<u32> = (0..img.height())
+                .filter(|&y| (start..=end).any(|x| is_face(img, x, y)))
+                .collect();
+            (rows[0], rows[rows.len() - 1])
+        })
+        .collect();
+
+    let by_length = |i: &usize| game.columns[*i].len();
+    let longest = (0..runs.len()).max_by_key(by_length).unwrap_or(0);
+    let shortest = (0..runs.len()).min_by_key(by_length).unwrap_or(0);
+    let rows_apart = (by_length(&longest) - by_length(&shortest)) as u32;
+    if rows_apart == 0 {
+        return Err("All columns have the same length, the card spacing is unknown".to_string());
+    }
+    let spacing = (extents[longest].1 - extents[shortest].1) / rows_apart;
+    if spacing == 0 {
+        return Err("Columns of different lengths end at the same height".to_string());
+    }
+
+    let mut crops = Vec::new();
+    for (i, column) in game.columns.iter().enumerate() {
+        let (start, end) = runs[i];
+        let (top, bottom) = extents[i];
+        for (row, &card) in column.iter().enumerate() {
+            let y = top + row as u32 * spacing;
+            // The next card's edge is right under the visible strip
+            let height = match row + 1 == column.len() {
+                true => bottom + 1 - y,
+                false => spacing - 1,
+            };
+            crops.push(CardCrop {
+                card,
+                x: start,
+                y,
+                width: end + 1 - start,
+                height,
+            });
+        }
+    }
+    Ok(crops)
+}
+
+// Writes one template per card of `game` into `folder`, cut from the screenshot at
+// `capture`, and returns how many were written. Only the visible strip of each card is
+// kept, the part template matching can rely on in any column.
+#[cfg(feature = "png")]
+pub fn extract_templates(capture: &str, game: &Game, folder: &str) -> Result<usize, String> {
+    let img = image::open(capture)
+        .map_err(|e| format!("{}: {}", capture, e))?
+        .to_rgba8();
+    let crops = locate_cards(&img, game)?;
+    // Every template has the size of the smallest strip, so that they compare alike
+    let height = crops.iter().map(|c| c.height).min().unwrap_or(0);
+    let width = crops.iter().map(|c| c.width).min().unwrap_or(0);
+
+    std::fs::create_dir_all(folder).map_err(|e| format!("{}: {}", folder, e))?;
+    for crop in &crops {
+        let path = Path::new(folder).join(template_name(&crop.card));
+        image::imageops::crop_imm(&img, crop.x, crop.y, width, height)
+            .to_image()
+            .save(&path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(crops.len())
+}
+
+#[cfg(not(feature = "png"))]
+pub fn extract_templates(capture: &str, _game: &Game, _folder: &str) -> Result<usize, String> {
+    Err(format!("{}: built without the `png` feature", capture))
+}
+
+#[cfg(all(test, feature = "png"))]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+    use crate::render::{CARD_HEIGHT, CARD_WIDTH, COLUMNS_Y, OVERLAP, render_game, slot_x};
+
+    #[test]
+    fn test_locate_cards() {
+        let game = Game::new(&ms_deal(617));
+        let crops = locate_cards(&render_game(&game), &game).unwrap();
+        assert_eq!(crops.len(), 52);
+
+        // Inside the outline of each card
+        let first = crops[0];
+        assert_eq!(first.card, game.columns[0][0]);
+        assert_eq!((first.x, first.y), (slot_x(0) + 1, COLUMNS_Y + 1));
+        assert_eq!((first.width, first.height), (CARD_WIDTH - 2, OVERLAP - 1));
+        let last = crops[51];
+        assert_eq!(last.card, *game.columns[7].last().unwrap());
+        assert_eq!(last.height, CARD_HEIGHT - 2);
+
+        assert!(locate_cards(&render_game(&Game::new(&[])), &game).is_err());
+    }
+
+    #[test]
+    fn test_new_template_path() {
+        let folder = std::env::temp_dir().join("freecell-new-template-path");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let folder = folder.to_str().unwrap();
+        let card = Card::try_from("TD").unwrap();
+
+        let first = new_template_path(folder, &card);
+        assert!(first.ends_with("10D.png"));
+        std::fs::write(&first, b"").unwrap();
+        assert!(new_template_path(folder, &card).ends_with("10D-2.png"));
+    }
+}
@@ -0,0 +1,260 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+use std::time::Duration;
+
+use crate::action::{Action, ActionType};
+use crate::deal::{ms_deal, parse_deck};
+use crate::game::Game;
+use crate::notation;
+use crate::postprocess::remove_wasted_moves;
+use crate::solver::{Solver, Strategy};
+
+thread_local! {
+    // Message of the last failed call on this thread, see freecell_last_error
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: impl ToString) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::new(message.to_string()).ok());
+}
+
+// Opaque to C, created by freecell_game_from_* and released by freecell_game_free
+pub struct FreecellGame {
+    game: Game,
+}
+
+// Opaque to C, created by freecell_solve and released by freecell_solution_free
+pub struct FreecellSolution {
+    solved: bool,
+    nodes: u64,
+    moves: Vec<FreecellMove>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreecellMoveKind {
+    ColToFoundation,
+    FreecellToFoundation,
+    ColToFreecell,
+    FreecellToCol,
+    ColToCol,
+}
+
+// Indexes start at 0. A column to column move can carry several cards at once.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FreecellMove {
+    pub kind: FreecellMoveKind,
+    pub source: u32,
+    pub dest: u32,
+    pub pile_size: u32,
+    // fc-solve notation ("3a", "72"), NUL terminated
+    pub notation: [c_char; 8],
+}
+
+impl From<&Action> for FreecellMove {
+    fn from(action: &Action) -> Self {
+        let kind = match action.action_type {
+            ActionType::ColToFoundation => FreecellMoveKind::ColToFoundation,
+            ActionType::FreecellToFoundation => FreecellMoveKind::FreecellToFoundation,
+            ActionType::ColToFreecell => FreecellMoveKind::ColToFreecell,
+            ActionType::FreecellToCol => FreecellMoveKind::FreecellToCol,
+            ActionType::ColToCol => FreecellMoveKind::ColToCol,
+        };
+        let mut text = [0; 8];
+        for (c, b) in text
+            .iter_mut()
+            .zip(notation::to_standard(action).bytes().take(7))
+        {
+            *c = b as c_char;
+        }
+        FreecellMove {
+            kind,
+            source: action.source as u32,
+            dest: action.dest as u32,
+            pile_size: action.pile_size as u32,
+            notation: text,
+        }
+    }
+}
+
+fn into_handle(game: Game) -> *mut FreecellGame {
+    Box::into_raw(Box::new(FreecellGame { game }))
+}
+
+/// Message of the last error on this thread, NULL if none. Valid until the next
+/// failing call.
+#[unsafe(no_mangle)]
+pub extern "C" fn freecell_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
+
+/// Microsoft FreeCell deal number, 1 to 2147483647. NULL for other numbers.
+#[unsafe(no_mangle)]
+pub extern "C" fn freecell_game_from_deal(number: u32) -> *mut FreecellGame {
+    if !(1..=0x7fff_ffff).contains(&number) {
+        set_error(format!("Invalid deal number: {}", number));
+        return ptr::null_mut();
+    }
+    into_handle(Game::new(&ms_deal(number)))
+}
+
+/// The 52 card codes in dealing order, "AS 7D KC ...". NULL if the deck is invalid.
+///
+/// # Safety
+/// `cards` must be NULL or a NUL terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freecell_game_from_cards(cards: *const c_char) -> *mut FreecellGame {
+    if cards.is_null() {
+        set_error("cards is NULL");
+        return ptr::null_mut();
+    }
+    let text = unsafe { CStr::from_ptr(cards) }.to_string_lossy();
+    match parse_deck(&text) {
+        Ok(deck) => into_handle(Game::new(&deck)),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+/// `game` must be NULL or a handle not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freecell_game_free(game: *mut FreecellGame) {
+    if !game.is_null() {
+        drop(unsafe { Box::from_raw(game) });
+    }
+}
+
+/// Solves the game. `strategy` is NULL for the default one, or "astar", "dfs",
+/// "weighted:N". A `time_limit_ms` of 0 means no time limit. Returns NULL on an
+/// invalid argument, an unsolved game gives a solution with no move.
+///
+/// # Safety
+/// `game` must be NULL or a live handle, `strategy` NULL or a NUL terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freecell_solve(
+    game: *const FreecellGame,
+    strategy: *const c_char,
+    max_nodes: u32,
+    time_limit_ms: u32,
+) -> *mut FreecellSolution {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        set_error("game is NULL");
+        return ptr::null_mut();
+    };
+    let mut solver = Solver::new(game.game.clone());
+    solver.verbose = false;
+    if !strategy.is_null() {
+        match unsafe { CStr::from_ptr(strategy) }
+            .to_string_lossy()
+            .parse::<Strategy>()
+        {
+            Ok(strategy) => solver.strategy = strategy,
+            Err(e) => {
+                set_error(e);
+                return ptr::null_mut();
+            }
+        }
+    }
+    if time_limit_ms > 0 {
+        solver.time_limit = Some(Duration::from_millis(time_limit_ms as u64));
+    }
+
+    let solution = solver.solve(max_nodes);
+    let moves = solution
+        .as_ref()
+        .map(|s| remove_wasted_moves(&game.game, s))
+        .unwrap_or_default();
+    Box::into_raw(Box::new(FreecellSolution {
+        solved: solution.is_some(),
+        nodes: solver.stats.nodes_explored,
+        moves: moves.iter().map(FreecellMove::from).collect(),
+    }))
+}
+
+/// # Safety
+/// `solution` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freecell_solution_solved(solution: *const FreecellSolution) -> bool {
+    unsafe { solution.as_ref() }.is_some_and(|s| s.solved)
+}
+
+/// # Safety
+/// `solution` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freecell_solution_nodes(solution: *const FreecellSolution) -> u64 {
+    unsafe { solution.as_ref() }.map_or(0, |s| s.nodes)
+}
+
+/// # Safety
+/// `solution` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freecell_solution_len(solution: *const FreecellSolution) -> usize {
+    unsafe { solution.as_ref() }.map_or(0, |s| s.moves.len())
+}
+
+/// Move `index` of the solution, NULL past the end. Owned by the solution.
+///
+/// # Safety
+/// `solution` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freecell_solution_move(
+    solution: *const FreecellSolution,
+    index: usize,
+) -> *const FreecellMove {
+    unsafe { solution.as_ref() }
+        .and_then(|s| s.moves.get(index))
+        .map_or(ptr::null(), |m| m as *const FreecellMove)
+}
+
+/// # Safety
+/// `solution` must be NULL or a handle not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freecell_solution_free(solution: *mut FreecellSolution) {
+    if !solution.is_null() {
+        drop(unsafe { Box::from_raw(solution) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::verify::verify_solution;
+
+    #[test]
+    fn test_solve_through_c_api() {
+        unsafe {
+            assert!(freecell_game_from_cards(c"AS 2S".as_ptr()).is_null());
+            let error = CStr::from_ptr(freecell_last_error()).to_string_lossy();
+            assert!(error.contains("Expected 52 cards"));
+
+            let game = freecell_game_from_deal(1);
+            let solution = freecell_solve(game, c"astar".as_ptr(), 100_000, 0);
+            assert!(freecell_solution_solved(solution));
+
+            // The moves read back from C replay to a win
+            let expected = remove_wasted_moves(
+                &(*game).game,
+                &Solver::new((*game).game.clone()).solve(100_000).unwrap(),
+            );
+            let len = freecell_solution_len(solution);
+            assert_eq!(len, expected.len());
+            for (index, action) in expected.iter().enumerate() {
+                let m = &*freecell_solution_move(solution, index);
+                assert_eq!(m.pile_size as usize, action.pile_size);
+                let text = CStr::from_ptr(m.notation.as_ptr()).to_str().unwrap();
+                assert_eq!(text, notation::to_standard(action));
+            }
+            assert!(verify_solution(&(*game).game, &expected).unwrap().is_won());
+            assert!(freecell_solution_move(solution, len).is_null());
+
+            freecell_solution_free(solution);
+            freecell_game_free(game);
+        }
+    }
+}
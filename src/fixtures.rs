@@ -0,0 +1,275 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::board::parse_board;
+use crate::card::{Card, Suit};
+use crate::config::OcrSettings;
+use crate::game::Game;
+use crate::layout::BoardDetections;
+
+// A screenshot whose position is known, to check what the OCR reads on it. Each
+// fixture is a folder holding `capture.png` and the position in `expected.board`, in
+// the format of `board::parse_board`. An `ocr.toml` there replaces the `[ocr]` settings
+// of the config, its template folders being relative to the fixture.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    pub name: String,
+    pub capture: PathBuf,
+    pub expected: Game,
+    pub settings: Option<OcrSettings>,
+}
+
+impl Fixture {
+    pub fn load(folder: &Path) -> Result<Self, String> {
+        let read = |file: &str| {
+            let path = folder.join(file);
+            std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))
+        };
+        let expected = parse_board(&read("expected.board")?)
+            .map_err(|e| format!("{}: {}", folder.join("expected.board").display(), e))?;
+        let settings = match folder.join("ocr.toml").exists() {
+            true => {
+                let mut settings: OcrSettings = toml::from_str(&read("ocr.toml")?)
+                    .map_err(|e| format!("{}: {}", folder.join("ocr.toml").display(), e))?;
+                let local = |path: &str| folder.join(path).to_string_lossy().into_owned();
+                settings.templates = local(&settings.templates);
+                if let Some(corners) = &settings.corner_templates {
+                    settings.corner_templates = Some(local(corners));
+                }
+                Some(settings)
+            }
+            false => None,
+        };
+        Ok(Fixture {
+            name: folder
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            capture: folder.join("capture.png"),
+            expected,
+            settings,
+        })
+    }
+
+    // The OCR settings to read this fixture with, `config` unless it has its own
+    pub fn settings(&self, config: &OcrSettings) -> OcrSettings {
+        OcrSettings {
+            capture: self.capture.to_string_lossy().into_owned(),
+            debug_image: None,
+            ..self.settings.clone().unwrap_or_else(|| config.clone())
+        }
+    }
+}
+
+// Every fixture of a folder, by name
+pub fn load_fixtures(folder: &str) -> Result<Vec<Fixture>, String> {
+    let entries = std::fs::read_dir(folder).map_err(|e| format!("{}: {}", folder, e))?;
+    let mut fixtures = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("expected.board").exists())
+        .map(|path| Fixture::load(&path))
+        .collect::<Result<Vec<_>, _>>()?;
+    fixtures.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fixtures)
+}
+
+// Padded so that names sort like the times and counts they hold
+fn capture_name(millis: u128, suffix: usize) -> String {
+    match suffix {
+        0 => format!("capture-{:013}", millis),
+        _ => format!("capture-{:013}-{:04}", millis, suffix),
+    }
+}
+
+// A new folder of `root` for one more capture, named after the time so that they
+// sort in order. Past `max` folders, the oldest go. Such a folder becomes a fixture
+// once its `read.board` is corrected and renamed `expected.board`.
+pub fn archive_folder(root: &Path, max: usize) -> Result<PathBuf, String> {
+    let failed = |e: std::io::Error| format!("{}: {}", root.display(), e);
+    std::fs::create_dir_all(root).map_err(failed)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    // Two captures within the same millisecond
+    let mut suffix = 0;
+    let mut folder = root.join(capture_name(now, suffix));
+    while folder.exists() {
+        suffix += 1;
+        folder = root.join(capture_name(now, suffix));
+    }
+    std::fs::create_dir(&folder).map_err(failed)?;
+
+    let mut archived: Vec<PathBuf> = std::fs::read_dir(root)
+        .map_err(failed)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("capture-"))
+        })
+        .collect();
+    archived.sort();
+    let excess = archived.len().saturating_sub(max.max(1));
+    for old in &archived[..excess] {
+        std::fs::remove_dir_all(old).map_err(failed)?;
+    }
+    Ok(folder)
+}
+
+// A cell where the board read differs from the expected one, placed as in
+// `layout::set_card`: 1-based column and row, row 0 being the top row. Foundations are
+// compared by suit, in the order of `Suit::ALL` from the 5th slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellDiff {
+    pub column: usize,
+    pub row: usize,
+    pub expected: Option<Card>,
+    pub found: Option<Card>,
+}
+
+impl fmt::Display for CellDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let card = |card: Option<Card>| card.map_or("--".to_string(), |c| c.to_string());
+        write!(
+            f,
+            "{}/{}: {} -> {}",
+            self.column,
+            self.row,
+            card(self.expected),
+            card(self.found)
+        )
+    }
+}
+
+pub fn diff_board(expected: &Game, found: &BoardDetections) -> Vec<CellDiff> {
+    let mut diffs = Vec::new();
+    let mut compare = |column: usize, row: usize, expected: Option<Card>, found: Option<Card>| {
+        if expected != found {
+            diffs.push(CellDiff {
+                column,
+                row,
+                expected,
+                found,
+            });
+        }
+    };
+
+    for (slot, cell) in expected.freecells.iter().enumerate() {
+        compare(slot + 1, 0, *cell, found.top_row[slot].map(|d| d.card));
+    }
+    for suit in Suit::ALL {
+        let top = |rank: u8| (rank > 0).then_some(Card { rank, suit });
+        let found_rank = found.top_row[4..]
+            .iter()
+            .flatten()
+            .filter(|d| d.card.suit == suit)
+            .map(|d| d.card.rank)
+            .max()
+            .unwrap_or(0);
+        compare(
+            5 + suit as usize,
+            0,
+            top(expected.foundations[suit as usize]),
+            top(found_rank),
+        );
+    }
+
+    for (i, column) in expected.columns.iter().enumerate() {
+        let read = found.columns.get(i).map_or(&[][..], Vec::as_slice);
+        for row in 0..column.len().max(read.len()) {
+            compare(
+                i + 1,
+                row + 1,
+                column.get(row).copied(),
+                read.get(row).map(|d| d.card),
+            );
+        }
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::board::to_pysol;
+    use crate::deal::ms_deal;
+    use crate::layout::{Detection, MatchMethod};
+
+    fn read(game: &Game) -> BoardDetections {
+        let detection = |card: Card| Detection {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            confidence: 1.0,
+            method: MatchMethod::Template,
+            card,
+            runner_up: None,
+        };
+        BoardDetections {
+            columns: game
+                .columns
+                .iter()
+                .map(|column| column.iter().copied().map(detection).collect())
+                .collect(),
+            top_row: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_archive_folder() {
+        let root = std::env::temp_dir().join(format!("freecell-archive-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let folders: Vec<PathBuf> = (0..4).map(|_| archive_folder(&root, 3).unwrap()).collect();
+        assert!(!folders[0].exists());
+        assert!(folders[1..].iter().all(|folder| folder.exists()));
+        assert!(folders.windows(2).all(|pair| pair[0] < pair[1]));
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let names: Vec<String> = [(999, 0), (999, 2), (999, 10), (1000, 0), (1000, 1)]
+            .into_iter()
+            .map(|(millis, suffix)| capture_name(millis, suffix))
+            .collect();
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_fixture_diff() {
+        let root = std::env::temp_dir().join(format!("freecell-fixtures-{}", std::process::id()));
+        let folder = root.join("deal-617");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&folder).unwrap();
+        let game = Game::new(&ms_deal(617));
+        std::fs::write(folder.join("expected.board"), to_pysol(&game)).unwrap();
+        std::fs::write(folder.join("ocr.toml"), "templates = \"cards\"\n").unwrap();
+
+        let fixtures = load_fixtures(root.to_str().unwrap()).unwrap();
+        assert_eq!(fixtures.len(), 1);
+        let fixture = &fixtures[0];
+        assert_eq!(fixture.name, "deal-617");
+        let settings = fixture.settings(&OcrSettings::default());
+        assert_eq!(Path::new(&settings.templates), folder.join("cards"));
+        assert!(settings.capture.ends_with("capture.png"));
+
+        let mut board = read(&fixture.expected);
+        assert!(diff_board(&game, &board).is_empty());
+        board.columns[2][1].card = "AS".parse().unwrap();
+        board.columns[7].pop();
+        let diffs = diff_board(&game, &board);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!((diffs[0].column, diffs[0].row), (3, 2));
+        assert_eq!(diffs[0].found, Some("AS".parse().unwrap()));
+        assert_eq!(
+            (diffs[1].column, diffs[1].row, diffs[1].found),
+            (8, 6, None)
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,12 +1,73 @@
-use crate::card::Card;
-use std::fmt::Debug;
+use crate::action::{Action, ActionType, Location};
+use crate::card::{Card, Suit};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug};
 use std::hash::{DefaultHasher, Hash, Hasher};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IllegalMove {
+    InvalidIndex,
+    InvalidPileSize(usize),
+    EmptySource,
+    OccupiedFreecell(usize),
+    NotPlayableOnFoundation(Card),
+    CannotStack { card: Card, target: Card },
+    NotASequence,
+    PileTooLarge { pile_size: usize, max: usize },
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IllegalMove::InvalidIndex => write!(f, "source or destination out of range"),
+            IllegalMove::InvalidPileSize(size) => write!(f, "invalid pile size {}", size),
+            IllegalMove::EmptySource => write!(f, "source is empty"),
+            IllegalMove::OccupiedFreecell(index) => write!(f, "freecell {} is occupied", index),
+            IllegalMove::NotPlayableOnFoundation(card) => {
+                write!(f, "{:?} cannot go to the foundation", card)
+            }
+            IllegalMove::CannotStack { card, target } => {
+                write!(f, "{:?} cannot be stacked on {:?}", card, target)
+            }
+            IllegalMove::NotASequence => write!(f, "moved cards are not an ordered sequence"),
+            IllegalMove::PileTooLarge { pile_size, max } => {
+                write!(f, "cannot move {} cards at once (max {})", pile_size, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+// Everything needed to revert a move applied in place: every action is reversible
+// from its own source/dest/pile_size, so the token only carries the action.
+#[derive(Debug, Clone, Copy)]
+pub struct UndoToken {
+    action: Action,
+}
+
+impl UndoToken {
+    pub fn action(&self) -> &Action {
+        &self.action
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_cells() -> usize {
+    4
+}
+
+// Serialized as card codes: {"columns": [["JD", "KD", ...], ...], "freecells": [null, "8H", ...], ...}
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Game {
     pub columns: [Vec<Card>; 8],
     pub freecells: [Option<Card>; 4],
     pub foundations: [u8; 4],
+    // Freecells in play, the first ones of `freecells`. Fewer than 4 makes a harder variant
+    #[cfg_attr(feature = "serde", serde(default = "default_cells"))]
+    pub cells: usize,
 }
 
 impl Game {
@@ -15,6 +76,7 @@ impl Game {
             columns: Default::default(),
             freecells: Default::default(),
             foundations: [0; 4],
+            cells: 4,
         };
 
         for (i, card) in cards.iter().enumerate() {
@@ -25,26 +87,51 @@ impl Game {
         game
     }
 
+    pub fn with_cells(mut self, cells: usize) -> Self {
+        self.cells = cells.min(self.freecells.len());
+        self
+    }
+
     pub fn hash_key(&self) -> u64 {
         let mut hasher = DefaultHasher::new();
         self.hash(&mut hasher);
         hasher.finish()
     }
 
+    // Unlike hash_key, two positions differing only by column or freecell order differ here
+    pub fn layout_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.columns.hash(&mut hasher);
+        self.freecells.hash(&mut hasher);
+        self.foundations.hash(&mut hasher);
+        hasher.finish()
+    }
+
     #[allow(dead_code)]
     pub fn is_won(&self) -> bool {
         self.foundations.iter().all(|&f| f == 13)
     }
 
+    // Nothing played yet: empty freecells and foundations, and the 52 cards laid out by
+    // `new`, 7 in the first four columns and 6 in the others
+    pub fn is_new_deal(&self) -> bool {
+        let dealt = |(i, column): (usize, &Vec<Card>)| column.len() == 7 - i / 4;
+        self.foundations == [0; 4]
+            && self.freecells.iter().all(Option::is_none)
+            && self.columns.iter().enumerate().all(dealt)
+    }
+
     pub fn count_free_cells(&self) -> usize {
-        self.freecells.iter().filter(|c| c.is_none()).count()
+        self.freecells[..self.cells]
+            .iter()
+            .filter(|c| c.is_none())
+            .count()
     }
 
     pub fn count_empty_columns(&self) -> usize {
         self.columns.iter().filter(|c| c.is_empty()).count()
     }
 
-    #[allow(dead_code)]
     pub fn max_movable_sequence(&self, remove_one_column: bool) -> u32 {
         // The maximum number of cards that can be moved at once is determined by the number of freecells
         // and the number of empty columns.
@@ -67,7 +154,174 @@ impl Game {
         // Cards can be stacked if they are of different colors and the rank is one less
         // Call top_card.can_stack(bottom_card) to check if the top card can be placed on the bottom card
         let same_color = card_below.is_black() == card_above.is_black();
-        !same_color && card_below.rank + 1 == card_above.rank
+        !same_color && card_above.rank + 1 == card_below.rank
+    }
+
+    pub fn check_move(&self, action: &Action) -> Result<(), IllegalMove> {
+        let in_range = |location: Location| match location {
+            Location::Column(i) => i < self.columns.len(),
+            Location::Freecell(i) => i < self.cells,
+            Location::Foundation(i) => i < self.foundations.len(),
+        };
+        if !in_range(action.source_location()) || !in_range(action.dest_location()) {
+            return Err(IllegalMove::InvalidIndex);
+        }
+        if action.action_type != ActionType::ColToCol && action.pile_size != 1 {
+            return Err(IllegalMove::InvalidPileSize(action.pile_size));
+        }
+
+        match action.action_type {
+            ActionType::ColToFoundation | ActionType::FreecellToFoundation => {
+                let card = if action.action_type == ActionType::ColToFoundation {
+                    self.columns[action.source].last()
+                } else {
+                    self.freecells[action.source].as_ref()
+                }
+                .ok_or(IllegalMove::EmptySource)?;
+                if !self.can_move_to_foundation(card) || card.suit as usize != action.dest {
+                    return Err(IllegalMove::NotPlayableOnFoundation(*card));
+                }
+            }
+            ActionType::ColToFreecell => {
+                if self.columns[action.source].is_empty() {
+                    return Err(IllegalMove::EmptySource);
+                }
+                if self.freecells[action.dest].is_some() {
+                    return Err(IllegalMove::OccupiedFreecell(action.dest));
+                }
+            }
+            ActionType::FreecellToCol => {
+                let card = self.freecells[action.source]
+                    .as_ref()
+                    .ok_or(IllegalMove::EmptySource)?;
+                if let Some(target) = self.columns[action.dest].last()
+                    && !self.can_stack_on(target, card)
+                {
+                    return Err(IllegalMove::CannotStack {
+                        card: *card,
+                        target: *target,
+                    });
+                }
+            }
+            ActionType::ColToCol => {
+                let source = &self.columns[action.source];
+                if action.source == action.dest || action.pile_size == 0 {
+                    return Err(IllegalMove::InvalidPileSize(action.pile_size));
+                }
+                if source.len() < action.pile_size {
+                    return Err(IllegalMove::EmptySource);
+                }
+                let pile = &source[source.len() - action.pile_size..];
+                if !pile.windows(2).all(|w| self.can_stack_on(&w[0], &w[1])) {
+                    return Err(IllegalMove::NotASequence);
+                }
+                let target = self.columns[action.dest].last();
+                let max = self.max_movable_sequence(target.is_none()) as usize;
+                if action.pile_size > max {
+                    return Err(IllegalMove::PileTooLarge {
+                        pile_size: action.pile_size,
+                        max,
+                    });
+                }
+                if let Some(target) = target
+                    && !self.can_stack_on(target, &pile[0])
+                {
+                    return Err(IllegalMove::CannotStack {
+                        card: pile[0],
+                        target: *target,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Cards picked up by the action, the deepest one first
+    pub fn cards_moved_by(&self, action: &Action) -> &[Card] {
+        match action.source_location() {
+            Location::Column(i) => {
+                let col = &self.columns[i];
+                &col[col.len().saturating_sub(action.pile_size)..]
+            }
+            Location::Freecell(i) => self.freecells[i].as_slice(),
+            Location::Foundation(_) => &[],
+        }
+    }
+
+    pub fn apply(&mut self, action: &Action) -> Result<UndoToken, IllegalMove> {
+        self.check_move(action)?;
+        Ok(self.apply_unchecked(action))
+    }
+
+    // Apply a move generated by the solver without checking it, in place.
+    pub fn apply_unchecked(&mut self, action: &Action) -> UndoToken {
+        match action.action_type {
+            ActionType::ColToFoundation => {
+                let card = self.columns[action.source].pop().unwrap();
+                self.foundations[card.suit as usize] += 1;
+            }
+            ActionType::FreecellToFoundation => {
+                let card = self.freecells[action.source].take().unwrap();
+                self.foundations[card.suit as usize] += 1;
+            }
+            ActionType::ColToFreecell => {
+                let card = self.columns[action.source].pop().unwrap();
+                self.freecells[action.dest] = Some(card);
+            }
+            ActionType::FreecellToCol => {
+                let card = self.freecells[action.source].take().unwrap();
+                self.columns[action.dest].push(card);
+            }
+            ActionType::ColToCol => {
+                self.move_pile(action.source, action.dest, action.pile_size);
+            }
+        }
+
+        UndoToken { action: *action }
+    }
+
+    pub fn undo(&mut self, token: UndoToken) {
+        let action = token.action;
+        match action.action_type {
+            ActionType::ColToFoundation => {
+                let card = self.take_from_foundation(action.dest);
+                self.columns[action.source].push(card);
+            }
+            ActionType::FreecellToFoundation => {
+                let card = self.take_from_foundation(action.dest);
+                self.freecells[action.source] = Some(card);
+            }
+            ActionType::ColToFreecell => {
+                let card = self.freecells[action.dest].take().unwrap();
+                self.columns[action.source].push(card);
+            }
+            ActionType::FreecellToCol => {
+                let card = self.columns[action.dest].pop().unwrap();
+                self.freecells[action.source] = Some(card);
+            }
+            ActionType::ColToCol => {
+                self.move_pile(action.dest, action.source, action.pile_size);
+            }
+        }
+    }
+
+    fn take_from_foundation(&mut self, suit: usize) -> Card {
+        let card = Card {
+            rank: self.foundations[suit],
+            suit: Suit::ALL[suit],
+        };
+        self.foundations[suit] -= 1;
+        card
+    }
+
+    fn move_pile(&mut self, source: usize, dest: usize, pile_size: usize) {
+        let start = self.columns[source].len() - pile_size;
+        for i in start..self.columns[source].len() {
+            let card = self.columns[source][i];
+            self.columns[dest].push(card);
+        }
+        self.columns[source].truncate(start);
     }
 }
 
@@ -145,6 +399,77 @@ impl Eq for Game {}
 mod tests {
 
     use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn test_apply_then_undo_restores_game() {
+        let deck: Vec<Card> = (0..52)
+            .map(|i| Card {
+                rank: (i % 13) + 1,
+                suit: Suit::ALL[(i / 13) as usize],
+            })
+            .collect();
+        let mut game = Game::new(&deck);
+        game.apply_unchecked(&Action {
+            action_type: ActionType::ColToFreecell,
+            source: 0,
+            dest: 0,
+            pile_size: 1,
+        });
+        let reference = game.clone();
+
+        for mov in Solver::new(game.clone()).get_moves(&game) {
+            let token = game.apply_unchecked(&mov);
+            game.undo(token);
+            assert_eq!(game.columns, reference.columns, "{:?}", mov);
+            assert_eq!(game.freecells, reference.freecells, "{:?}", mov);
+            assert_eq!(game.foundations, reference.foundations, "{:?}", mov);
+        }
+    }
+
+    #[test]
+    fn test_can_stack_on() {
+        let game = Game::new(&crate::deal::ms_deal(1));
+        let card = |code: &str| Card::try_from(code).unwrap();
+
+        // One rank lower, of the other colour
+        assert!(game.can_stack_on(&card("5D"), &card("4C")));
+        assert!(game.can_stack_on(&card("5D"), &card("4S")));
+        assert!(game.can_stack_on(&card("KS"), &card("QH")));
+        // Same colour, one rank higher or any other rank
+        assert!(!game.can_stack_on(&card("5D"), &card("4H")));
+        assert!(!game.can_stack_on(&card("4C"), &card("5D")));
+        assert!(!game.can_stack_on(&card("5D"), &card("3C")));
+        assert!(!game.can_stack_on(&card("5D"), &card("5C")));
+    }
+
+    #[test]
+    fn test_is_new_deal() {
+        let mut game = Game::new(&crate::deal::ms_deal(1));
+        assert!(game.is_new_deal());
+        game.apply(&Action::between(Location::Column(0), Location::Freecell(0), 1).unwrap())
+            .unwrap();
+        assert!(!game.is_new_deal());
+        assert!(!Game::new(&crate::deal::ms_deal(1)[..51]).is_new_deal());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_game_serde_round_trip() {
+        let deck: Vec<Card> = ["AS", "KH", "7D", "TC", "QS", "2H", "9C", "JD", "5S"]
+            .iter()
+            .map(|&c| Card::try_from(c).unwrap())
+            .collect();
+        let mut game = Game::new(&deck).with_cells(3);
+        game.apply(&Action::between(Location::Column(0), Location::Freecell(0), 1).unwrap())
+            .unwrap();
+
+        let json = serde_json::to_string(&game).unwrap();
+        assert!(json.contains(r#""freecells":["5S",null,null,null]"#));
+        let restored: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.layout_key(), game.layout_key());
+        assert_eq!(restored.cells, 3);
+    }
 
     // #[test]
     // fn test_max_movable_sequence1() {
@@ -0,0 +1,194 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+use crate::batch::{BatchOptions, BatchResult, solve_game};
+use crate::deal::random_deal;
+use crate::estimate::estimate_tree_size;
+use crate::game::Game;
+use crate::notation::solution_to_freecell_pro;
+
+// Give up after this many rejected deals per requested one
+const MAX_ATTEMPTS_PER_DEAL: usize = 10;
+const MAX_ATTEMPTS_PER_HARD_DEAL: usize = 200;
+const ESTIMATE_PROBES: usize = 100;
+const ESTIMATE_MAX_DEPTH: usize = 500;
+
+pub struct GeneratedDeal {
+    pub seed: u64,
+    // Search effort and Freecell Pro notation of the solution when the deal went through the solver
+    pub nodes: Option<u64>,
+    pub moves: Option<usize>,
+    pub solution: Option<String>,
+}
+
+impl GeneratedDeal {
+    fn unsolved(seed: u64) -> Self {
+        GeneratedDeal {
+            seed,
+            nodes: None,
+            moves: None,
+            solution: None,
+        }
+    }
+
+    // Card list followed by a comment, which the batch subcommand reads back as is:
+    // "AS 7D ... # seed 42, 1834 nodes, 97 moves: 3a 72v3 ..."
+    pub fn to_line(&self, with_solution: bool) -> String {
+        let cards: Vec<String> = random_deal(self.seed)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let mut line = format!("{} # seed {}", cards.join(" "), self.seed);
+        if let (Some(nodes), Some(moves)) = (self.nodes, self.moves) {
+            line += &format!(", {} nodes, {} moves", nodes, moves);
+        }
+        if let (Some(solution), true) = (&self.solution, with_solution) {
+            line += &format!(": {}", solution);
+        }
+        line
+    }
+}
+
+// Which deals are kept: solved within the solver limits and, when thresholds are set,
+// needing more than `min_nodes` nodes or more than `min_moves` moves
+#[derive(Debug, Clone, Copy)]
+pub struct DealFilter {
+    pub options: BatchOptions,
+    pub min_nodes: Option<u64>,
+    pub min_moves: Option<usize>,
+    // Deals whose estimated tree size (log10) is below this are dropped without solving
+    pub min_log10_nodes: Option<f64>,
+}
+
+impl DealFilter {
+    pub fn solvable(options: BatchOptions) -> Self {
+        DealFilter {
+            options,
+            min_nodes: None,
+            min_moves: None,
+            min_log10_nodes: None,
+        }
+    }
+
+    fn is_hard_search(&self) -> bool {
+        self.min_nodes.is_some() || self.min_moves.is_some() || self.min_log10_nodes.is_some()
+    }
+
+    fn accepts(&self, result: &BatchResult) -> bool {
+        let Some(moves) = result.moves else {
+            return false;
+        };
+        if self.min_nodes.is_none() && self.min_moves.is_none() {
+            return true;
+        }
+        self.min_nodes.is_some_and(|min| result.nodes > min)
+            || self.min_moves.is_some_and(|min| moves > min)
+    }
+
+    fn check(&self, seed: u64) -> Option<GeneratedDeal> {
+        let game = Game::new(&random_deal(seed));
+        if let Some(min) = self.min_log10_nodes {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let estimate = estimate_tree_size(&game, ESTIMATE_PROBES, ESTIMATE_MAX_DEPTH, &mut rng);
+            if estimate.log10_nodes < min {
+                return None;
+            }
+        }
+
+        let (result, steps) = solve_game(&seed.to_string(), &game, self.options);
+        if !self.accepts(&result) {
+            return None;
+        }
+        let actions: Vec<_> = steps?.iter().map(|s| s.action).collect();
+        Some(GeneratedDeal {
+            seed,
+            nodes: Some(result.nodes),
+            moves: result.moves,
+            solution: Some(solution_to_freecell_pro(&actions)),
+        })
+    }
+}
+
+// Deals for seeds `first_seed`, `first_seed + 1`, ... With a filter, only the deals it
+// accepts are kept, the candidates being tried in parallel rounds.
+pub fn generate_deals(
+    count: usize,
+    first_seed: u64,
+    filter: Option<DealFilter>,
+) -> Vec<GeneratedDeal> {
+    let Some(filter) = filter else {
+        return (0..count as u64)
+            .map(|i| GeneratedDeal::unsolved(first_seed.wrapping_add(i)))
+            .collect();
+    };
+
+    let attempts = if filter.is_hard_search() {
+        MAX_ATTEMPTS_PER_HARD_DEAL
+    } else {
+        MAX_ATTEMPTS_PER_DEAL
+    };
+    let mut deals = Vec::with_capacity(count);
+    let mut next_seed = first_seed;
+    let max_seed = first_seed.saturating_add((count * attempts) as u64);
+
+    while deals.len() < count && next_seed < max_seed {
+        // Hard deals are rare, so try more candidates than missing deals at once
+        let wanted = (count - deals.len()) * if filter.is_hard_search() { 8 } else { 1 };
+        let round = (wanted as u64).min(max_seed - next_seed);
+        let mut kept: Vec<GeneratedDeal> = (next_seed..next_seed + round)
+            .into_par_iter()
+            .filter_map(|seed| filter.check(seed))
+            .collect();
+        kept.truncate(count - deals.len());
+        deals.extend(kept);
+        next_seed += round;
+    }
+    deals
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::batch::parse_batch;
+    use crate::solver::{HeuristicWeights, Strategy};
+
+    #[test]
+    fn test_generated_lines_read_back_in_batch() {
+        let deals = generate_deals(3, 100, None);
+        let text: Vec<String> = deals.iter().map(|d| d.to_line(true)).collect();
+        let parsed = parse_batch(&text.join("\n"));
+
+        assert_eq!(parsed.len(), 3);
+        for (deal, generated) in parsed.iter().zip(&deals) {
+            assert_eq!(
+                deal.game.as_ref().unwrap().layout_key(),
+                Game::new(&random_deal(generated.seed)).layout_key()
+            );
+        }
+    }
+
+    #[test]
+    fn test_hard_deals_pass_the_thresholds() {
+        let options = BatchOptions {
+            strategy: Strategy::Dfs,
+            max_nodes: 50_000,
+            time_limit: None,
+            weights: HeuristicWeights::default(),
+            optimize: true,
+            rate: false,
+        };
+        let filter = DealFilter {
+            min_moves: Some(100),
+            ..DealFilter::solvable(options)
+        };
+
+        let deals = generate_deals(1, 7, Some(filter));
+        assert_eq!(deals.len(), 1);
+        for deal in deals {
+            assert!(deal.moves.unwrap() > 100);
+            assert!(deal.to_line(false).contains(" moves"));
+        }
+    }
+}
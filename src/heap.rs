@@ -1,16 +1,19 @@
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashSet};
+use std::collections::{BinaryHeap, VecDeque};
 
 use crate::action::Action;
 use crate::game::Game;
 
+pub struct SearchNode {
+    pub state: Game,
+    pub path: Vec<Action>,
+}
+
 // Structure pour les éléments de la priority queue
-#[derive(Eq, PartialEq)]
 pub struct HeapNode {
     pub f_score: i32,
     pub counter: u64,
-    pub state: Game,
-    pub path: Vec<Action>,
+    pub node: SearchNode,
 }
 
 // we want a min-heap based on f_score
@@ -29,3 +32,190 @@ impl PartialOrd for HeapNode {
         Some(self.cmp(other))
     }
 }
+
+impl PartialEq for HeapNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.f_score == other.f_score && self.counter == other.counter
+    }
+}
+
+impl Eq for HeapNode {}
+
+// With small non-negative integer f-scores, one FIFO bucket per score gives O(1) push/pop,
+// and the FIFO order inside a bucket replaces the counter tie-break of the heap. The
+// solver only picks it for weights that keep the scores in that range.
+#[derive(Default)]
+pub struct BucketQueue {
+    buckets: Vec<VecDeque<SearchNode>>,
+    min_bucket: usize,
+    len: usize,
+}
+
+impl BucketQueue {
+    pub fn push(&mut self, f_score: i32, node: SearchNode) {
+        debug_assert!(
+            f_score >= 0,
+            "negative f-score {} in a bucket queue",
+            f_score
+        );
+        let index = f_score.max(0) as usize;
+        if index >= self.buckets.len() {
+            self.buckets.resize_with(index + 1, VecDeque::new);
+        }
+        self.buckets[index].push_back(node);
+        self.min_bucket = self.min_bucket.min(index);
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<SearchNode> {
+        if self.len == 0 {
+            return None;
+        }
+        while self.buckets[self.min_bucket].is_empty() {
+            self.min_bucket += 1;
+        }
+        self.len -= 1;
+        self.buckets[self.min_bucket].pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Nodes with their f-score, in the order `pop` would return them
+    pub fn iter(&self) -> impl Iterator<Item = (i32, &SearchNode)> {
+        self.buckets
+            .iter()
+            .enumerate()
+            .skip(self.min_bucket)
+            .flat_map(|(f_score, bucket)| bucket.iter().map(move |node| (f_score as i32, node)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueueKind {
+    #[default]
+    Buckets,
+    // For weights whose f-scores can be negative or too spread out for one bucket each
+    Heap,
+}
+
+pub enum OpenList {
+    Buckets(BucketQueue),
+    Heap {
+        heap: BinaryHeap<HeapNode>,
+        counter: u64,
+    },
+}
+
+impl OpenList {
+    pub fn new(kind: QueueKind) -> Self {
+        match kind {
+            QueueKind::Buckets => OpenList::Buckets(BucketQueue::default()),
+            QueueKind::Heap => OpenList::Heap {
+                heap: BinaryHeap::new(),
+                counter: 0,
+            },
+        }
+    }
+
+    pub fn push(&mut self, f_score: i32, node: SearchNode) {
+        match self {
+            OpenList::Buckets(buckets) => buckets.push(f_score, node),
+            OpenList::Heap { heap, counter } => {
+                *counter += 1;
+                heap.push(HeapNode {
+                    f_score,
+                    counter: *counter,
+                    node,
+                });
+            }
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<SearchNode> {
+        match self {
+            OpenList::Buckets(buckets) => buckets.pop(),
+            OpenList::Heap { heap, .. } => heap.pop().map(|n| n.node),
+        }
+    }
+
+    pub fn kind(&self) -> QueueKind {
+        match self {
+            OpenList::Buckets(_) => QueueKind::Buckets,
+            OpenList::Heap { .. } => QueueKind::Heap,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            OpenList::Buckets(buckets) => buckets.len(),
+            OpenList::Heap { heap, .. } => heap.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            OpenList::Buckets(buckets) => buckets.is_empty(),
+            OpenList::Heap { heap, .. } => heap.is_empty(),
+        }
+    }
+
+    // Pushing these nodes back in this order rebuilds the same list, tie-breaks included
+    pub fn in_pop_order(&self) -> Vec<(i32, &SearchNode)> {
+        match self {
+            OpenList::Buckets(buckets) => buckets.iter().collect(),
+            OpenList::Heap { heap, .. } => {
+                let mut nodes: Vec<&HeapNode> = heap.iter().collect();
+                nodes.sort_by(|a, b| b.cmp(a));
+                nodes.into_iter().map(|n| (n.f_score, &n.node)).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::action::Location;
+    use crate::deal::ms_deal;
+
+    // The length of the path tells the nodes apart
+    fn pop_order(kind: QueueKind, f_scores: &[i32]) -> Vec<usize> {
+        let game = Game::new(&ms_deal(1));
+        let action = Action::between(Location::Column(0), Location::Freecell(0), 1).unwrap();
+        let mut open = OpenList::new(kind);
+        for (id, &f_score) in f_scores.iter().enumerate() {
+            let path = vec![action; id];
+            open.push(
+                f_score,
+                SearchNode {
+                    state: game.clone(),
+                    path,
+                },
+            );
+        }
+        assert_eq!(open.len(), f_scores.len());
+        std::iter::from_fn(|| open.pop())
+            .map(|node| node.path.len())
+            .collect()
+    }
+
+    #[test]
+    fn test_pop_order() {
+        // Lowest f-score first, first pushed first among equal scores
+        let f_scores = [5, 1, 3, 1, 0, 5];
+        assert_eq!(pop_order(QueueKind::Buckets, &f_scores), [4, 1, 3, 2, 0, 5]);
+        assert_eq!(pop_order(QueueKind::Heap, &f_scores), [4, 1, 3, 2, 0, 5]);
+
+        assert_eq!(
+            pop_order(QueueKind::Heap, &[3, -40, 1_000_000, -2, 3]),
+            [1, 3, 0, 4, 2]
+        );
+    }
+}
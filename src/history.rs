@@ -0,0 +1,242 @@
+use std::fs;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{Value, json};
+
+use crate::action::Action;
+use crate::game::{Game, IllegalMove};
+use crate::output::{action_json, game_json};
+use crate::replay::{parse_action, parse_game};
+
+pub const HISTORY_FORMAT: &str = "freecell-hand-history";
+pub const HISTORY_VERSION: u64 = 1;
+
+// Who played a move: the player, or the solver after a takeover
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mover {
+    Player,
+    Solver,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryEvent {
+    Move(Action, Mover),
+    // Takes back the last move still standing
+    Undo,
+    // The move the solver suggested, None if it found nothing
+    Hint(Option<Action>),
+}
+
+// Everything that happened in one game, saved as JSON:
+//
+//   {
+//     "format": "freecell-hand-history", "version": 1,
+//     "deal": { "columns": [...], "freecells": [...], "foundations": [...] },
+//     "deal_number": 617,            // Microsoft deal, or null
+//     "seed": null,                  // seed of a random deal, or null
+//     "started": 1760000000,         // Unix time in seconds
+//     "events": [
+//       { "t_ms": 1520, "event": "move", "by": "player", "move": {...} },
+//       { "t_ms": 2210, "event": "undo" },
+//       { "t_ms": 4800, "event": "hint", "move": {...} },
+//       { "t_ms": 9000, "event": "move", "by": "solver", "move": {...} }
+//     ],
+//     "moves": 96,                   // moves still standing at the end
+//     "outcome": "won"               // or "abandoned"
+//   }
+//
+// Moves use the layout of `--format json` solutions, played from the position they
+// were made in. `t_ms` counts from the start of the game.
+pub struct HandHistory {
+    pub game: Game,
+    pub deal_number: Option<u32>,
+    pub seed: Option<u64>,
+    pub started: u64,
+    pub events: Vec<(u64, HistoryEvent)>,
+    clock: Instant,
+}
+
+impl HandHistory {
+    pub fn new(game: Game) -> Self {
+        HandHistory {
+            game,
+            deal_number: None,
+            seed: None,
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            events: Vec::new(),
+            clock: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, event: HistoryEvent) {
+        let elapsed = self.clock.elapsed().as_millis() as u64;
+        self.events.push((elapsed, event));
+    }
+
+    // The moves left once the undos are taken out
+    pub fn moves(&self) -> Vec<Action> {
+        let mut moves = Vec::new();
+        for (_, event) in &self.events {
+            match event {
+                HistoryEvent::Move(action, _) => moves.push(*action),
+                HistoryEvent::Undo => {
+                    moves.pop();
+                }
+                HistoryEvent::Hint(_) => {}
+            }
+        }
+        moves
+    }
+
+    pub fn is_won(&self) -> bool {
+        let mut state = self.game.clone();
+        self.moves()
+            .iter()
+            .all(|action| state.apply(action).is_ok())
+            && state.is_won()
+    }
+
+    pub fn to_json(&self) -> Value {
+        // Each move is written against the position it was played from
+        let mut state = self.game.clone();
+        let mut undo = Vec::new();
+        let events: Vec<Value> = self
+            .events
+            .iter()
+            .map(|(t_ms, event)| match event {
+                HistoryEvent::Move(action, by) => {
+                    let json = json!({
+                        "t_ms": t_ms,
+                        "event": "move",
+                        "by": match by {
+                            Mover::Player => "player",
+                            Mover::Solver => "solver",
+                        },
+                        "move": action_json(&state, action),
+                    });
+                    // Recorded from legal play, or checked by `from_json`
+                    undo.push(state.apply(action).expect("a legal move"));
+                    json
+                }
+                HistoryEvent::Undo => {
+                    if let Some(token) = undo.pop() {
+                        state.undo(token);
+                    }
+                    json!({ "t_ms": t_ms, "event": "undo" })
+                }
+                HistoryEvent::Hint(hint) => json!({
+                    "t_ms": t_ms,
+                    "event": "hint",
+                    "move": hint.map(|action| action_json(&state, &action)),
+                }),
+            })
+            .collect();
+
+        json!({
+            "format": HISTORY_FORMAT,
+            "version": HISTORY_VERSION,
+            "deal": game_json(&self.game),
+            "deal_number": self.deal_number,
+            "seed": self.seed,
+            "started": self.started,
+            "events": events,
+            "moves": self.moves().len(),
+            "outcome": if self.is_won() { "won" } else { "abandoned" },
+        })
+    }
+
+    pub fn from_json(json: &Value) -> Result<Self, String> {
+        if json["format"] != HISTORY_FORMAT {
+            return Err("Not a hand history".to_string());
+        }
+        let version = json["version"].as_u64().unwrap_or_default();
+        if version != HISTORY_VERSION {
+            return Err(format!("Unsupported hand history version {}", version));
+        }
+
+        let mut history = HandHistory::new(parse_game(&json["deal"])?);
+        history.deal_number = json["deal_number"].as_u64().map(|n| n as u32);
+        history.seed = json["seed"].as_u64();
+        history.started = json["started"].as_u64().unwrap_or_default();
+        for event in json["events"].as_array().ok_or("Missing events")? {
+            let t_ms = event["t_ms"].as_u64().unwrap_or_default();
+            let event = match event["event"].as_str() {
+                Some("move") => HistoryEvent::Move(
+                    parse_action(&event["move"])?,
+                    match event["by"].as_str() {
+                        Some("solver") => Mover::Solver,
+                        _ => Mover::Player,
+                    },
+                ),
+                Some("undo") => HistoryEvent::Undo,
+                Some("hint") if event["move"].is_null() => HistoryEvent::Hint(None),
+                Some("hint") => HistoryEvent::Hint(Some(parse_action(&event["move"])?)),
+                _ => return Err(format!("Unknown event {}", event)),
+            };
+            history.events.push((t_ms, event));
+        }
+
+        // Played again from the deal, `to_json` and the replay expect legal moves
+        let mut state = history.game.clone();
+        let mut undo = Vec::new();
+        for (number, (_, event)) in history.events.iter().enumerate() {
+            let illegal = |e: IllegalMove| format!("event #{} is illegal: {}", number + 1, e);
+            match event {
+                HistoryEvent::Move(action, _) => undo.push(state.apply(action).map_err(illegal)?),
+                HistoryEvent::Undo => {
+                    if let Some(token) = undo.pop() {
+                        state.undo(token);
+                    }
+                }
+                HistoryEvent::Hint(Some(action)) => state.check_move(action).map_err(illegal)?,
+                HistoryEvent::Hint(None) => {}
+            }
+        }
+        Ok(history)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&self.to_json()).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+    use crate::solver::Solver;
+
+    #[test]
+    fn test_history_round_trip() {
+        let game = Game::new(&ms_deal(1));
+        let solution = Solver::new(game.clone()).solve(100_000).unwrap();
+        let mut history = HandHistory::new(game);
+        history.deal_number = Some(1);
+        history.record(HistoryEvent::Hint(Some(solution[0])));
+        history.record(HistoryEvent::Move(solution[0], Mover::Player));
+        history.record(HistoryEvent::Undo);
+        for action in &solution {
+            history.record(HistoryEvent::Move(*action, Mover::Solver));
+        }
+
+        let json = history.to_json();
+        assert_eq!(json["outcome"], "won");
+        assert_eq!(json["moves"], solution.len());
+
+        let loaded = HandHistory::from_json(&json).unwrap();
+        assert_eq!(loaded.deal_number, Some(1));
+        assert_eq!(loaded.events, history.events);
+        assert_eq!(loaded.moves(), solution);
+        assert!(HandHistory::from_json(&json!({ "format": "other" })).is_err());
+
+        // The first move, after the hint, changed by hand
+        let mut corrupt = json.clone();
+        corrupt["events"][1]["move"]["pile_size"] = json!(13);
+        let error = HandHistory::from_json(&corrupt).err().unwrap();
+        assert!(error.starts_with("event #2 is illegal"), "{}", error);
+    }
+}
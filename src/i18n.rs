@@ -0,0 +1,594 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+use crate::card::Card;
+use crate::error::OcrError;
+
+// Language of the messages for people, chosen once at startup with --lang or LANG
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "tools", derive(clap::ValueEnum))]
+#[repr(u8)]
+pub enum Lang {
+    #[default]
+    Fr,
+    En,
+}
+
+static LANG: AtomicU8 = AtomicU8::new(Lang::Fr as u8);
+
+impl Lang {
+    // From a locale such as "en_US.UTF-8": English for "en", French otherwise
+    pub fn from_locale(locale: &str) -> Lang {
+        match locale.get(..2) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("en") => Lang::En,
+            _ => Lang::Fr,
+        }
+    }
+}
+
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        0 => Lang::Fr,
+        _ => Lang::En,
+    }
+}
+
+// Each message with its arguments, then its French and English text
+macro_rules! catalog {
+    ($($name:ident $(($($arg:ident: $ty:ty),*))? => $fr:literal, $en:literal;)*) => {
+        #[derive(Clone, Copy)]
+        pub enum Msg<'a> {
+            $($name $(($($ty),*))?,)*
+        }
+
+        impl Msg<'_> {
+            fn write(&self, f: &mut fmt::Formatter<'_>, lang: Lang) -> fmt::Result {
+                match *self {
+                    $(Msg::$name $(($($arg),*))? => match lang {
+                        Lang::Fr => write!(f, $fr),
+                        Lang::En => write!(f, $en),
+                    },)*
+                }
+            }
+        }
+
+        #[cfg(test)]
+        impl<'a> Msg<'a> {
+            // Every message, with sample arguments
+            fn samples() -> Vec<Msg<'a>> {
+                vec![$(Msg::$name $(($(<$ty as tests::Sample>::sample()),*))?,)*]
+            }
+        }
+    };
+}
+
+catalog! {
+    // Solver
+    CheckpointSaved(path: &'a dyn fmt::Display) =>
+        "💾 Recherche sauvegardée dans {path}",
+        "💾 Search saved to {path}";
+    CheckpointFailed(error: &'a dyn fmt::Display) =>
+        "⚠️ Sauvegarde de la recherche impossible : {error}",
+        "⚠️ Could not save the search: {error}";
+    AStarProgress(nodes: u64, queue: usize, depth: usize, h: i32) =>
+        "Explorés : {nodes}, file : {queue}, chemin : {depth}, H : {h}",
+        "Explored: {nodes}, Queue: {queue}, Path: {depth}, H: {h}";
+    DfsProgress(nodes: u64, depth: usize, h: i32) =>
+        "Explorés : {nodes}, profondeur : {depth}, H : {h}",
+        "Explored: {nodes}, Depth: {depth}, H: {h}";
+    SearchSolved(moves: usize) =>
+        "\n✓ Solution trouvée en {moves} coups!",
+        "\n✓ Solution found in {moves} moves!";
+    NodesExplored(nodes: u64) =>
+        "Nœuds explorés: {nodes}",
+        "Nodes explored: {nodes}";
+    SearchFailed(nodes: u64) =>
+        "\n✗ Pas de solution trouvée après {nodes} nœuds",
+        "\n✗ No solution found after {nodes} nodes";
+
+    // solve
+    Seed(seed: u64) =>
+        "🎲 Graine : {seed} (--seed {seed} pour rejouer cette donne)",
+        "🎲 Seed: {seed} (--seed {seed} to deal it again)";
+    ResumeFailed(error: &'a dyn fmt::Display) =>
+        "❌ Impossible de reprendre la recherche : {error}",
+        "❌ Could not resume the search: {error}";
+    PngSaved(path: &'a str) =>
+        "🖼️ Plateau enregistré dans `{path}`",
+        "🖼️ Board saved to `{path}`";
+    PngFailed(error: &'a dyn fmt::Display) =>
+        "❌ Impossible d'enregistrer l'image : {error}",
+        "❌ Could not save the picture: {error}";
+    PysolSaved(path: &'a str) =>
+        "🃏 Partie exportée pour PySolFC dans `{path}`",
+        "🃏 Game exported for PySolFC to `{path}`";
+    PysolFailed(error: &'a dyn fmt::Display) =>
+        "❌ Impossible d'exporter la partie : {error}",
+        "❌ Could not export the game: {error}";
+    Estimate(log10: f64, branching: f64, depth: f64, won: usize, probes: usize) =>
+        "Estimation: ~10^{log10:.1} nœuds, facteur de branchement {branching:.2}, profondeur moyenne {depth:.1} ({won}/{probes} sondes gagnantes)",
+        "Estimate: ~10^{log10:.1} nodes, branching factor {branching:.2}, mean depth {depth:.1} ({won}/{probes} winning probes)";
+    AutoLimits(strategy: &'a dyn fmt::Debug, nodes: u32) =>
+        "Stratégie: {strategy:?}, limite: {nodes} nœuds",
+        "Strategy: {strategy:?}, limit: {nodes} nodes";
+    Resuming(nodes: u64) =>
+        "⏩ Reprise de la recherche après {nodes} nœuds",
+        "⏩ Resuming the search after {nodes} nodes";
+    CheckpointAStarOnly =>
+        "⚠️ Les points de sauvegarde ne concernent que A*, --checkpoint est ignoré",
+        "⚠️ Checkpoints only apply to A*, --checkpoint is ignored";
+    Elapsed(elapsed: Duration) =>
+        "Durée : {elapsed:.2?}",
+        "Elapsed: {elapsed:.2?}";
+    Simplified(before: usize, after: usize) =>
+        "🧹 Solution simplifiée : {before} → {after} mouvements",
+        "🧹 Solution simplified: {before} → {after} moves";
+    SolutionFound(moves: usize) =>
+        "✅ Solution trouvée en {moves} mouvements:",
+        "✅ Solution found in {moves} moves:";
+    Unsolvable(positions: u64) =>
+        "❌ Donne insoluble : les {positions} positions atteignables ont été explorées",
+        "❌ Unsolvable deal: all {positions} reachable positions were explored";
+    NoSolution =>
+        "❌ Aucune solution trouvée dans la limite de mouvements.",
+        "❌ No solution found within the limits.";
+    SvgSaved(path: &'a str) =>
+        "🖼️ Diagramme enregistré dans `{path}`",
+        "🖼️ Diagram saved to `{path}`";
+    SvgFailed(error: &'a dyn fmt::Display) =>
+        "❌ Impossible d'enregistrer le diagramme : {error}",
+        "❌ Could not save the diagram: {error}";
+    Supermove(action: &'a dyn fmt::Debug, moves: usize) =>
+        "  - {action:?} (supermove de {moves} coups)",
+        "  - {action:?} (supermove of {moves} moves)";
+    RandomDeal(seed: u64) =>
+        "🎲 Donne aléatoire, graine {seed} (--seed {seed})",
+        "🎲 Random deal, seed {seed} (--seed {seed})";
+    TerminalUnusable(error: &'a dyn fmt::Display) =>
+        "❌ Terminal inutilisable : {error}",
+        "❌ Unusable terminal: {error}";
+
+    // Input files
+    ConfigInvalid(error: &'a dyn fmt::Display) =>
+        "❌ Configuration invalide : {error}",
+        "❌ Invalid configuration: {error}";
+    ReadFailed(path: &'a str, error: &'a dyn fmt::Display) =>
+        "Impossible de lire `{path}` : {error}",
+        "Could not read `{path}`: {error}";
+    WriteFailed(path: &'a str, error: &'a dyn fmt::Display) =>
+        "❌ Impossible d'écrire `{path}` : {error}",
+        "❌ Could not write `{path}`: {error}";
+    InvalidDeal(error: &'a dyn fmt::Display) =>
+        "Donne invalide : {error}",
+        "Invalid deal: {error}";
+    BoardLoadFailed(path: &'a str, error: &'a dyn fmt::Display) =>
+        "Impossible de charger `{path}` : {error}",
+        "Could not load `{path}`: {error}";
+    SolutionLoadFailed(error: &'a dyn fmt::Display) =>
+        "❌ Impossible de charger la solution : {error}",
+        "❌ Could not load the solution: {error}";
+
+    // batch, analyze and the results database
+    BatchResuming(done: usize, left: usize) =>
+        "⏩ Reprise : {done} donnes déjà traitées, {left} restantes",
+        "⏩ Resuming: {done} deals already done, {left} left";
+    CheckpointNotRecorded(error: &'a dyn fmt::Display) =>
+        "⚠️ Point de reprise non enregistré : {error}",
+        "⚠️ Checkpoint not recorded: {error}";
+    BatchInterrupted(error: &'a dyn fmt::Display) =>
+        "❌ Batch interrompu : {error}",
+        "❌ Batch interrupted: {error}";
+    SummarySaved(path: &'a str) =>
+        "📊 Statistiques enregistrées dans `{path}`",
+        "📊 Statistics saved to `{path}`";
+    DbUnusable(error: &'a dyn fmt::Display) =>
+        "❌ Base de résultats inutilisable : {error}",
+        "❌ Unusable results database: {error}";
+    DbUnreadable(error: &'a dyn fmt::Display) =>
+        "❌ Base de résultats illisible : {error}",
+        "❌ Unreadable results database: {error}";
+    DbAlreadySolved(solved: usize, left: usize) =>
+        "⏩ {solved} donnes déjà résolues dans la base, {left} restantes",
+        "⏩ {solved} deals already solved in the database, {left} left";
+    DbNotRecorded(error: &'a dyn fmt::Display) =>
+        "❌ Résultats non enregistrés dans la base : {error}",
+        "❌ Results not recorded in the database: {error}";
+    AnalyzeInterrupted(error: &'a dyn fmt::Display) =>
+        "❌ Analyse interrompue : {error}",
+        "❌ Analysis interrupted: {error}";
+    CellsSummary(cells: usize, solved: usize, deals: usize, p50: u64, p90: u64, max: u64) =>
+        "{cells} cellules : {solved}/{deals} résolues, nœuds p50 {p50} p90 {p90} max {max}",
+        "{cells} freecells: {solved}/{deals} solved, nodes p50 {p50} p90 {p90} max {max}";
+    BenchTotal(strategy: &'a dyn fmt::Display, solved: usize, runs: usize, nodes: u64, elapsed: Duration, moves: usize) =>
+        "{strategy:<6} résolues {solved}/{runs}, {nodes} nœuds, {elapsed:.2?}, {moves} coups",
+        "{strategy:<6} solved {solved}/{runs}, {nodes} nodes, {elapsed:.2?}, {moves} moves";
+
+    // rate and generate
+    Rating(deal: u32, grade: u8, label: &'a str) =>
+        "🎲 Donne {deal} : difficulté {grade}/5 ({label})",
+        "🎲 Deal {deal}: difficulty {grade}/5 ({label})";
+    GradeVeryEasy =>
+        "très facile",
+        "very easy";
+    GradeEasy =>
+        "facile",
+        "easy";
+    GradeMedium =>
+        "moyenne",
+        "medium";
+    GradeHard =>
+        "difficile",
+        "hard";
+    GradeVeryHard =>
+        "très difficile",
+        "very hard";
+    RateUnsolved(nodes: u32) =>
+        "aucune stratégie ne la résout en {nodes} nœuds",
+        "no strategy solves it within {nodes} nodes";
+    RateMoves(moves: usize) =>
+        "solution de {moves} coups",
+        "solution of {moves} moves";
+    RateNodes(nodes: u64, strategy: &'a dyn fmt::Display) =>
+        "{nodes} nœuds au mieux ({strategy})",
+        "{nodes} nodes at best ({strategy})";
+    RateCells(cells: usize, total: usize) =>
+        "{cells} cellule(s) libre(s) nécessaire(s) sur {total}",
+        "{cells} free cell(s) needed out of {total}";
+    DealsShort(kept: usize, asked: usize) =>
+        "⚠️ Seulement {kept} donnes retenues sur {asked} demandées",
+        "⚠️ Only {kept} deals kept out of {asked} asked for";
+    DealsWritten(count: usize, path: &'a str) =>
+        "🃏 {count} donnes écrites dans `{path}`",
+        "🃏 {count} deals written to `{path}`";
+
+    // serve
+    ServerListening(address: &'a str) =>
+        "🌐 Serveur à l'écoute sur http://{address}",
+        "🌐 Server listening on http://{address}";
+    ListenFailed(address: &'a str, error: &'a dyn fmt::Display) =>
+        "❌ Impossible d'écouter sur {address} : {error}",
+        "❌ Could not listen on {address}: {error}";
+    ResponseNotSent(error: &'a dyn fmt::Display) =>
+        "⚠️ Réponse non envoyée : {error}",
+        "⚠️ Response not sent: {error}";
+
+    // crosscheck
+    FcSolveMissing(binary: &'a str) =>
+        "⚠️ `{binary}` introuvable, seules nos solutions sont vérifiées",
+        "⚠️ `{binary}` not found, only our own solutions are checked";
+    CrosscheckSummary(agreed: usize, deals: usize) =>
+        "🔎 {agreed}/{deals} donnes sans désaccord",
+        "🔎 {agreed}/{deals} deals without disagreement";
+
+    // Screen capture and OCR
+    CaptureSaved(path: &'a str) =>
+        "✅ Zone capturée sauvegardée dans `{path}`",
+        "✅ Captured area saved to `{path}`";
+    BoardFound(x1: i32, y1: i32, x2: i32, y2: i32) =>
+        "🔲 Plateau trouvé de ({x1}, {y1}) à ({x2}, {y2})",
+        "🔲 Board found from ({x1}, {y1}) to ({x2}, {y2})";
+    WatchStarted(interval: f64) =>
+        "👀 Surveillance du jeu toutes les {interval} s, Ctrl+C pour arrêter",
+        "👀 Watching the game every {interval} s, Ctrl+C to stop";
+    HotkeyWaiting(keys: &'a str) =>
+        "⌨️ {keys} pour lire et résoudre la partie, Ctrl+C pour arrêter",
+        "⌨️ {keys} to read and solve the game, Ctrl+C to stop";
+    HotkeyInvalid(reason: &'a str) =>
+        "❌ Raccourci invalide : {reason}",
+        "❌ Invalid hotkey: {reason}";
+    ArchiveFailed(reason: &'a str) =>
+        "⚠️ Capture non archivée : {reason}",
+        "⚠️ Capture not archived: {reason}";
+    AutoplayMove(number: usize, notation: &'a str) =>
+        "🤖 Coup {number} : {notation}",
+        "🤖 Move {number}: {notation}";
+    AutoplayOffScript =>
+        "🔄 Le jeu ne montre pas la position attendue, nouvelle résolution",
+        "🔄 The game does not show the expected position, solving again";
+    AutoplayStopped(moves: usize) =>
+        "⏹️ Arrêt après {moves} coups",
+        "⏹️ Stopped after {moves} moves";
+    NextMove(notation: &'a str, reason: &'a str, left: usize) =>
+        "💡 Coup suivant : {notation}  — {reason} ({left} coups restants)",
+        "💡 Next move: {notation}  — {reason} ({left} moves left)";
+    NewDealFound =>
+        "🆕 Nouvelle donne à l'écran",
+        "🆕 New deal on screen";
+    ClickTwice =>
+        "🖱️ Faites glisser la souris sur la zone à capturer, ou cliquez ses deux coins...",
+        "🖱️ Drag over the area to capture, or click its two corners...";
+    WindowFound(title: &'a str, x1: i32, y1: i32, x2: i32, y2: i32) =>
+        "🪟 Fenêtre « {title} » de ({x1}, {y1}) à ({x2}, {y2})",
+        "🪟 Window \"{title}\" from ({x1}, {y1}) to ({x2}, {y2})";
+    RegionSaved(path: &'a str) =>
+        "💾 Zone enregistrée dans `{path}`, --reselect pour la choisir à nouveau",
+        "💾 Area saved to `{path}`, --reselect to pick it again";
+    RegionNotSaved(reason: &'a str) =>
+        "⚠️ Zone non enregistrée : {reason}",
+        "⚠️ Area not saved: {reason}";
+    DisplayFound(index: usize) =>
+        "🖥️ Zone sur l'écran {index}",
+        "🖥️ Area on screen {index}";
+    PointerAt(x: i32, y: i32) =>
+        "🖱️ Pointeur en ({x}, {y})",
+        "🖱️ Pointer at ({x}, {y})";
+    SelectingArea(x1: i32, y1: i32, x2: i32, y2: i32) =>
+        "📐 Zone de ({x1}, {y1}) à ({x2}, {y2})",
+        "📐 Area from ({x1}, {y1}) to ({x2}, {y2})";
+    KeepArea(width: u32, height: u32) =>
+        "🔍 Zone de {width}×{height} pixels, la garder ? [O/n] ",
+        "🔍 Area of {width}×{height} pixels, keep it? [Y/n] ";
+    ClickAt(x: i32, y: i32) =>
+        "📍 Clic à : ({x}, {y})",
+        "📍 Click at: ({x}, {y})";
+    OcrImage(path: &'a str, reason: &'a str) =>
+        "Impossible de charger l'image {path} : {reason}",
+        "Could not load image {path}: {reason}";
+    OcrEmptyImage(path: &'a str) =>
+        "L'image {path} est vide",
+        "Image {path} is empty";
+    OcrTemplates(pattern: &'a str, reason: &'a str) =>
+        "Motif de modèles invalide {pattern} : {reason}",
+        "Invalid template pattern {pattern}: {reason}";
+    OcrMatching(path: &'a str, reason: &'a str) =>
+        "Échec de la reconnaissance sur {path} : {reason}",
+        "Template matching failed for {path}: {reason}";
+    OcrTemplateName(path: &'a str, reason: &'a dyn fmt::Display) =>
+        "Le modèle {path} ne porte pas le nom d'une carte : {reason}",
+        "Template {path} is not named after a card: {reason}";
+    OcrCapture(reason: &'a str) =>
+        "Échec de la capture d'écran : {reason}",
+        "Screen capture failed: {reason}";
+    OcrLayout(reason: &'a str) =>
+        "Disposition des cartes ambiguë : {reason}",
+        "Ambiguous card layout: {reason}";
+    OcrWindow(reason: &'a str) =>
+        "Impossible d'afficher la carte : {reason}",
+        "Could not show the card: {reason}";
+    OcrMissing(cards: &'a str) =>
+        "❓ Cartes manquantes : {cards}",
+        "❓ Missing cards: {cards}";
+    OcrDuplicated(card: &'a Card, places: &'a str) =>
+        "👯 {card} trouvée plusieurs fois : {places}",
+        "👯 {card} found more than once: {places}";
+    OcrDoubtful(card: &'a Card, column: usize, row: usize, confidence: f64, method: &'a str) =>
+        "🤔 {card} en colonne {column}, rang {row} : confiance {confidence:.2} ({method})",
+        "🤔 {card} in column {column}, row {row}: confidence {confidence:.2} ({method})";
+    OcrTheme(theme: &'a str) =>
+        "🎨 Thème de cartes : {theme}",
+        "🎨 Card theme: {theme}";
+    OcrRecognized(cards: usize, elapsed: Duration) =>
+        "🔍 {cards} cartes repérées en {elapsed:.2?}",
+        "🔍 {cards} cards found in {elapsed:.2?}";
+    OcrDeckOk =>
+        "✅ Les 52 cartes ont été reconnues",
+        "✅ All 52 cards were recognized";
+    FixtureOk(name: &'a str) =>
+        "✅ {name} : plateau lu sans erreur",
+        "✅ {name}: board read without error";
+    FixtureDiffers(name: &'a str, cells: usize) =>
+        "❌ {name} : {cells} cases diffèrent (colonne/rang : attendu -> lu)",
+        "❌ {name}: {cells} cells differ (column/row: expected -> read)";
+    FixtureFailed(name: &'a str, error: &'a dyn fmt::Display) =>
+        "❌ {name} : {error}",
+        "❌ {name}: {error}";
+    FixturesPassed(passed: usize, total: usize) =>
+        "🧪 {passed}/{total} captures lues sans erreur",
+        "🧪 {passed}/{total} captures read without error";
+    OcrCandidate(column: usize, row: usize, card: &'a Card, confidence: f64) =>
+        "🤔 Colonne {column}, rang {row} : 1) {card} ({confidence:.2})",
+        "🤔 Column {column}, row {row}: 1) {card} ({confidence:.2})";
+    OcrRunnerUp(card: &'a Card, confidence: f64) =>
+        "   2) {card} ({confidence:.2})",
+        "   2) {card} ({confidence:.2})";
+    OcrReviewPrompt =>
+        "Entrée ou 1 pour garder, 2 pour l'autre, une carte (\"TD\") ou \"-\" pour retirer, q pour abandonner > ",
+        "Enter or 1 to keep, 2 for the other one, a card (\"TD\") or \"-\" to remove, q to give up > ";
+    OcrFixPrompt =>
+        "Correction `colonne rang carte` (\"3 5 TD\", \"-\" pour retirer, rang 0 pour cellules et fondations), Entrée pour valider, q pour abandonner > ",
+        "Fix `column row card` (\"3 5 TD\", \"-\" to remove, row 0 for freecells and foundations), Enter to accept, q to give up > ";
+    OcrLabelPrompt(column: usize, row: usize, guess: &'a str) =>
+        "Carte en colonne {column}, rang {row} ({guess}) : code de la carte, Entrée pour passer, q pour arrêter > ",
+        "Card in column {column}, row {row} ({guess}): card code, Enter to skip, q to stop > ";
+    TemplatesWritten(count: usize, folder: &'a str) =>
+        "🃏 {count} modèles de cartes écrits dans `{folder}`",
+        "🃏 {count} card templates written to `{folder}`";
+
+    // play
+    PlayHelp =>
+        "1-8 colonnes, a-d cellules, h fondation, Échap annuler, u défaire, ? indice, s solveur, q quitter",
+        "1-8 columns, a-d freecells, h foundation, Esc cancel, u undo, ? hint, s solver, q quit";
+    PlayTitle(moves: usize) =>
+        " FreeCell — coup {moves} ",
+        " FreeCell — move {moves} ";
+    Won(moves: usize) =>
+        "🎉 Gagné en {moves} coups !",
+        "🎉 Won in {moves} moves!";
+    Destination =>
+        "Destination ?",
+        "Destination?";
+    NothingToMove =>
+        "❌ Rien à déplacer ici",
+        "❌ Nothing to move here";
+    IllegalMove =>
+        "❌ Coup impossible",
+        "❌ Illegal move";
+    MoveUndone(number: usize) =>
+        "↩️ Coup {number} annulé",
+        "↩️ Move {number} undone";
+    NothingToUndo =>
+        "Rien à défaire",
+        "Nothing to undo";
+    HistorySaved(path: &'a str) =>
+        "📜 Historique de la partie enregistré dans `{path}`",
+        "📜 Hand history saved to `{path}`";
+    HistoryFailed(error: &'a dyn fmt::Display) =>
+        "❌ Impossible d'enregistrer l'historique : {error}",
+        "❌ Could not save the hand history: {error}";
+    SolverStuck =>
+        "❌ Le solveur ne trouve pas de solution depuis cette position",
+        "❌ The solver finds no solution from this position";
+
+    // replay
+    ReplayMove(number: usize, total: usize, notation: &'a str, reason: &'a str) =>
+        "Coup {number}/{total} : {notation}  — {reason}",
+        "Move {number}/{total}: {notation}  — {reason}";
+    ReplayEnd(moves: usize) =>
+        "Fin de la solution ({moves} coups)",
+        "End of the solution ({moves} moves)";
+    ReplayPrompt =>
+        "[n]ext, [p]rev, [f]irst, [l]ast, numéro de coup, [q]uit > ",
+        "[n]ext, [p]rev, [f]irst, [l]ast, move number, [q]uit > ";
+    UnknownCommand(command: &'a str) =>
+        "Commande inconnue : {command}",
+        "Unknown command: {command}";
+    ReplayIllegal(number: usize, error: &'a dyn fmt::Display) =>
+        "❌ Coup {number} illégal : {error}",
+        "❌ Move {number} is illegal: {error}";
+
+    // Move explanations
+    Column(number: usize) =>
+        "colonne {number}",
+        "column {number}";
+    PlaysToFoundation(card: &'a str) =>
+        "joue {card} en fondation",
+        "plays {card} to the foundation";
+    ParksToUnbury(card: &'a str, buried: &'a str) =>
+        "met {card} de côté pour dégager {buried}",
+        "parks {card} to unbury {buried}";
+    Parks(card: &'a str) =>
+        "met {card} de côté",
+        "parks {card}";
+    BuildsOn(card: &'a str, target: &'a str) =>
+        "pose {card} sur {target}",
+        "builds {card} on {target}";
+    PileToEmpty(count: usize, column: &'a str) =>
+        "déplace {count} cartes dans la {column} vide",
+        "moves {count} cards into empty {column}";
+    CardToEmpty(card: &'a str, column: &'a str) =>
+        "déplace {card} dans la {column} vide",
+        "moves {card} into empty {column}";
+    FreesForFoundation(card: &'a str) =>
+        "libère {card} pour la fondation",
+        "frees {card} for the foundation";
+    Empties(column: &'a str) =>
+        "vide la {column}",
+        "empties {column}";
+    FreesFreecell(freecell: char) =>
+        "libère la cellule {freecell}",
+        "frees freecell {freecell}";
+}
+
+impl Msg<'_> {
+    // The message in `lang`, whatever the language chosen at startup
+    pub fn in_lang(&self, lang: Lang) -> String {
+        struct Localized<'m, 'a>(&'m Msg<'a>, Lang);
+
+        impl fmt::Display for Localized<'_, '_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.write(f, self.1)
+            }
+        }
+
+        Localized(self, lang).to_string()
+    }
+}
+
+impl fmt::Display for Msg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, lang())
+    }
+}
+
+impl<'a> From<&'a OcrError> for Msg<'a> {
+    fn from(error: &'a OcrError) -> Self {
+        match error {
+            OcrError::Image { path, reason } => Msg::OcrImage(path, reason),
+            OcrError::EmptyImage(path) => Msg::OcrEmptyImage(path),
+            OcrError::Templates { pattern, reason } => Msg::OcrTemplates(pattern, reason),
+            OcrError::Matching { path, reason } => Msg::OcrMatching(path, reason),
+            OcrError::TemplateName { path, source } => Msg::OcrTemplateName(path, source),
+            OcrError::Capture(reason) => Msg::OcrCapture(reason),
+            OcrError::Layout(reason) => Msg::OcrLayout(reason),
+            OcrError::Window(reason) => Msg::OcrWindow(reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::card::Suit;
+
+    pub(super) trait Sample {
+        fn sample() -> Self;
+    }
+
+    macro_rules! sample {
+        ($($ty:ty => $value:expr;)*) => {
+            $(impl<'a> Sample for $ty {
+                fn sample() -> Self {
+                    $value
+                }
+            })*
+        };
+    }
+
+    static CARD: Card = Card {
+        rank: 12,
+        suit: Suit::Spade,
+    };
+
+    sample! {
+        u8 => 3;
+        u32 => 7;
+        u64 => 42;
+        usize => 5;
+        i32 => -1;
+        f64 => 0.5;
+        char => 'b';
+        Duration => Duration::from_millis(1500);
+        &'a str => "deals.txt";
+        &'a Card => &CARD;
+        &'a dyn fmt::Display => &"no such file";
+        &'a dyn fmt::Debug => &"AStar";
+    }
+
+    #[test]
+    fn test_every_message() {
+        for msg in Msg::samples() {
+            for lang in [Lang::Fr, Lang::En] {
+                let text = msg.in_lang(lang);
+                assert!(!text.trim().is_empty());
+                assert!(!text.contains('{'), "{}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_catalog() {
+        assert_eq!(Lang::from_locale("en_US.UTF-8"), Lang::En);
+        assert_eq!(Lang::from_locale("fr_FR.UTF-8"), Lang::Fr);
+        assert_eq!(Lang::from_locale("C"), Lang::Fr);
+
+        assert_eq!(
+            Msg::SolutionFound(42).in_lang(Lang::En),
+            "✅ Solution found in 42 moves:"
+        );
+        assert_eq!(
+            Msg::SolutionFound(42).in_lang(Lang::Fr),
+            "✅ Solution trouvée en 42 mouvements:"
+        );
+        let error = OcrError::EmptyImage("capture.png".to_string());
+        assert_eq!(
+            Msg::from(&error).in_lang(Lang::En),
+            "Image capture.png is empty"
+        );
+    }
+}
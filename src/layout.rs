@@ -0,0 +1,1100 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::action::{Action, Location};
+use crate::board::check_cards;
+use crate::card::{Card, Suit};
+use crate::error::{OcrError, SolverError};
+use crate::game::Game;
+
+// How a card was recognized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchMethod {
+    // The template scored above the confidence threshold
+    #[default]
+    Template,
+    // A weaker template match, confirmed by the colors of the area
+    ColorHistogram,
+    // Rank glyph and suit pip matched apart, see `pair_corners`
+    Corner,
+    // Only the top strip of the template matched: a card buried in its column
+    Strip,
+    // The rank read by the glyph classifier overruled the template, see `fuse_rank`
+    Classifier,
+    // Typed in by the player, see `set_card`
+    Manual,
+}
+
+impl MatchMethod {
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchMethod::Template => "template",
+            MatchMethod::ColorHistogram => "histogram",
+            MatchMethod::Corner => "corner",
+            MatchMethod::Strip => "strip",
+            MatchMethod::Classifier => "classifier",
+            MatchMethod::Manual => "manual",
+        }
+    }
+}
+
+// A card found on a screenshot: box and score of its template match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub confidence: f64,
+    pub method: MatchMethod,
+    pub card: Card,
+    // The best other card matched at this spot and its score, see `note_runner_ups`
+    pub runner_up: Option<(Card, f64)>,
+}
+
+// A corner glyph found on a screenshot: a rank (1 to 13) or a suit pip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph<T> {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub confidence: f64,
+    pub value: T,
+}
+
+// A scored box, as given by template matching
+pub trait TemplateMatch {
+    // x, y, width, height
+    fn area(&self) -> [i32; 4];
+    fn score(&self) -> f64;
+
+    // Intersection over union of the two boxes, 0 when apart and 1 when equal
+    fn overlap(&self, other: &impl TemplateMatch) -> f64 {
+        let [x1, y1, w1, h1] = self.area();
+        let [x2, y2, w2, h2] = other.area();
+        let width = (x1 + w1).min(x2 + w2) - x1.max(x2);
+        let height = (y1 + h1).min(y2 + h2) - y1.max(y2);
+        if width <= 0 || height <= 0 {
+            return 0.0;
+        }
+        let inter = (width * height) as f64;
+        let union = (w1 * h1 + w2 * h2) as f64 - inter;
+        inter / union
+    }
+}
+
+impl TemplateMatch for Detection {
+    fn area(&self) -> [i32; 4] {
+        [self.x, self.y, self.width, self.height]
+    }
+
+    fn score(&self) -> f64 {
+        self.confidence
+    }
+}
+
+impl<T> TemplateMatch for Glyph<T> {
+    fn area(&self) -> [i32; 4] {
+        [self.x, self.y, self.width, self.height]
+    }
+
+    fn score(&self) -> f64 {
+        self.confidence
+    }
+}
+
+// Greedy non-maximum suppression: the best scores are kept first, and a match
+// overlapping one already kept by more than `max_overlap` is dropped. Neighbouring
+// pixels of a peak score almost as high as the peak itself, this keeps the peak alone.
+pub fn non_max_suppression<T: TemplateMatch>(mut candidates: Vec<T>, max_overlap: f64) -> Vec<T> {
+    candidates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+    let mut kept: Vec<T> = Vec::new();
+    for candidate in candidates {
+        if kept.iter().all(|k| k.overlap(&candidate) <= max_overlap) {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+// Notes on each kept match the best candidate of another card it won its spot over,
+// the second choice offered when the match is doubtful
+pub fn note_runner_ups(kept: &mut [Detection], candidates: &[Detection], max_overlap: f64) {
+    for detection in kept {
+        detection.runner_up = candidates
+            .iter()
+            .filter(|c| c.card != detection.card && c.overlap(detection) > max_overlap)
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+            .map(|c| (c.card, c.confidence));
+    }
+}
+
+// Pairs each rank glyph with the suit pip right below it, as printed in the top-left
+// corner of a card. The pip must start under the glyph, within a glyph's height, and
+// be horizontally aligned with it; the closest one wins and serves a single rank.
+// Ranks without a pip are dropped. This only needs the top strip of each card, which
+// stays visible in tall columns.
+pub fn pair_corners(ranks: &[Glyph<u8>], suits: &[Glyph<Suit>]) -> Vec<Detection> {
+    let mut ranks = ranks.to_vec();
+    ranks.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    let mut used = vec![false; suits.len()];
+    let mut detections = Vec::new();
+
+    for rank in ranks {
+        let below = |suit: &Glyph<Suit>| {
+            let dy = suit.y - rank.y;
+            (suit.x - rank.x).abs() <= rank.width / 2 && dy > 0 && dy <= 2 * rank.height
+        };
+        let Some(index) = (0..suits.len())
+            .filter(|&i| !used[i] && below(&suits[i]))
+            .min_by_key(|&i| suits[i].y - rank.y)
+        else {
+            continue;
+        };
+        used[index] = true;
+        let suit = suits[index];
+        detections.push(Detection {
+            x: rank.x.min(suit.x),
+            y: rank.y,
+            width: (rank.x + rank.width).max(suit.x + suit.width) - rank.x.min(suit.x),
+            height: suit.y + suit.height - rank.y,
+            confidence: rank.confidence.min(suit.confidence),
+            method: MatchMethod::Corner,
+            card: Card {
+                rank: rank.value,
+                suit: suit.value,
+            },
+            runner_up: None,
+        });
+    }
+    detections
+}
+
+fn ambiguous(reason: String) -> SolverError {
+    SolverError::Ocr(OcrError::Layout(reason))
+}
+
+// Groups the detections into the 8 columns of the tableau, whatever order they were
+// found in: a new column starts wherever the gap to the previous card is more than
+// half a card wide. Each column is then read from top to bottom. With fewer than 8
+// groups, the empty columns are told from the spacing of the others: the narrowest
+// gap is one column apart, and the leftmost group is the first column.
+pub fn infer_columns(detections: &[Detection]) -> Result<Vec<Vec<Detection>>, SolverError> {
+    let expected = Game::new(&[]).columns.len();
+    let mut by_x = detections.to_vec();
+    by_x.sort_by_key(|d| d.x);
+
+    let mut groups: Vec<Vec<Detection>> = Vec::new();
+    for detection in by_x {
+        match groups.last_mut() {
+            Some(group) if detection.x - group[0].x <= detection.width / 2 => group.push(detection),
+            _ => groups.push(vec![detection]),
+        }
+    }
+    if groups.len() > expected {
+        return Err(ambiguous(format!(
+            "found {} columns, expected {}",
+            groups.len(),
+            expected
+        )));
+    }
+
+    let mut columns = vec![Vec::new(); expected];
+    let pitch = groups.windows(2).map(|w| w[1][0].x - w[0][0].x).min();
+    let first = groups.first().map_or(0, |group| group[0].x);
+    let count = groups.len();
+    for (i, group) in groups.into_iter().enumerate() {
+        let index = match pitch {
+            Some(pitch) if count < expected => {
+                ((group[0].x - first) as f64 / pitch as f64).round() as usize
+            }
+            _ => i,
+        };
+        let Some(column) = columns.get_mut(index) else {
+            return Err(ambiguous(format!(
+                "{} at x = {} is past the last column",
+                group[0].card, group[0].x
+            )));
+        };
+        *column = group;
+    }
+    sort_columns(columns)
+}
+
+// Left edges of the columns: where their cards are, evenly spread in between for the
+// empty ones. Empty when fewer than two columns hold cards.
+pub fn column_lefts(columns: &[Vec<Detection>]) -> Vec<i32> {
+    let known: Vec<(usize, i32)> = columns
+        .iter()
+        .enumerate()
+        .filter_map(|(i, column)| Some((i, column.iter().map(|d| d.x).min()?)))
+        .collect();
+    let (Some(&(i0, x0)), Some(&(i1, x1))) = (known.first(), known.last()) else {
+        return Vec::new();
+    };
+    if i1 == i0 {
+        return Vec::new();
+    }
+    let pitch = (x1 - x0) as f64 / (i1 - i0) as f64;
+    (0..columns.len())
+        .map(|i| x0 + ((i as f64 - i0 as f64) * pitch).round() as i32)
+        .collect()
+}
+
+// Splits the freecells and foundations off the tableau. The top row is the topmost
+// line of cards when the next line is more than two rows of a column below it, or a
+// whole card when the columns hold a single row. Otherwise the topmost line is the
+// first row of the columns, as in a fresh deal or with an empty top row.
+pub fn split_top_row(detections: &[Detection]) -> (Vec<Detection>, Vec<Detection>) {
+    let mut lines: Vec<i32> = detections.iter().map(|d| d.y).collect();
+    lines.sort();
+    // Cards of a line are a few pixels apart at most
+    lines.dedup_by(|below, above| *below - *above < 3);
+    if lines.len() < 2 {
+        return (Vec::new(), detections.to_vec());
+    }
+
+    let mut steps: Vec<i32> = lines[1..].windows(2).map(|w| w[1] - w[0]).collect();
+    steps.sort();
+    let gap = lines[1] - lines[0];
+    let is_top_row = match steps.get(steps.len() / 2) {
+        Some(&step) => gap > 2 * step,
+        None => detections
+            .iter()
+            .filter(|d| d.y < lines[1])
+            .all(|d| gap >= d.height * 9 / 10),
+    };
+    match is_top_row {
+        true => detections.iter().partition(|d| d.y < lines[1]),
+        false => (Vec::new(), detections.to_vec()),
+    }
+}
+
+// Index of the left edge nearest to the detection, which must be within half a card
+fn nearest(detection: &Detection, lefts: &[i32], what: &str) -> Result<usize, SolverError> {
+    lefts
+        .iter()
+        .map(|left| (detection.x - left).abs())
+        .enumerate()
+        .min_by_key(|&(_, distance)| distance)
+        .filter(|&(_, distance)| 2 * distance <= detection.width)
+        .map(|(i, _)| i)
+        .ok_or_else(|| {
+            ambiguous(format!(
+                "{} at x = {} is in no {}",
+                detection.card, detection.x, what
+            ))
+        })
+}
+
+// Puts each card of the top row in the slot whose left edge is nearest: the 4
+// freecells, then the 4 foundations
+pub fn place_top_row(
+    top: &[Detection],
+    lefts: &[i32],
+) -> Result<[Option<Detection>; 8], SolverError> {
+    let mut slots = [None; 8];
+    for detection in top {
+        let slot: &mut Option<Detection> = &mut slots[nearest(detection, lefts, "slot")?];
+        if let Some(other) = slot {
+            return Err(ambiguous(format!(
+                "{} and {} are at the same place",
+                other.card, detection.card
+            )));
+        }
+        *slot = Some(*detection);
+    }
+    Ok(slots)
+}
+
+fn sort_columns(mut columns: Vec<Vec<Detection>>) -> Result<Vec<Vec<Detection>>, SolverError> {
+    for column in &mut columns {
+        column.sort_by_key(|d| d.y);
+        // Cards of a column overlap but never share a row: the same y means two
+        // templates matched the same card
+        if let Some(pair) = column.windows(2).find(|w| w[1].y - w[0].y < 2) {
+            return Err(ambiguous(format!(
+                "{} and {} are at the same place",
+                pair[0].card, pair[1].card
+            )));
+        }
+    }
+    Ok(columns)
+}
+
+// FreeCell clients whose board geometry is known, see `Geometry`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutPreset {
+    // Microsoft Solitaire Collection
+    Microsoft,
+    // GNOME AisleRiot
+    AisleRiot,
+    // Browser games: the board fills the page, freecells left and foundations right
+    Web,
+    // Any client: the geometry is measured on the capture, see `detect::detect_board`
+    Auto,
+}
+
+// Where a client draws the board, in fractions of the captured game area so that it
+// holds at any window size: x as a fraction of its width, y of its height. Slots of a
+// row are evenly spaced, `*_step` apart from one left edge to the next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+    pub card_width: f64,
+    pub card_height: f64,
+    // Top of the freecells and foundations, and of the first card of each column
+    pub top_row: f64,
+    pub columns_top: f64,
+    pub first_column: f64,
+    pub column_step: f64,
+    pub first_freecell: f64,
+    pub first_foundation: f64,
+    pub slot_step: f64,
+}
+
+impl LayoutPreset {
+    // Rough proportions of each client's default board, none for `Auto`
+    pub fn geometry(self) -> Option<Geometry> {
+        Some(match self {
+            LayoutPreset::Auto => return None,
+            LayoutPreset::Microsoft => Geometry {
+                card_width: 0.085,
+                card_height: 0.17,
+                top_row: 0.06,
+                columns_top: 0.3,
+                first_column: 0.09,
+                column_step: 0.105,
+                first_freecell: 0.03,
+                first_foundation: 0.56,
+                slot_step: 0.105,
+            },
+            LayoutPreset::AisleRiot => Geometry {
+                card_width: 0.11,
+                card_height: 0.2,
+                top_row: 0.02,
+                columns_top: 0.26,
+                first_column: 0.01,
+                column_step: 0.125,
+                first_freecell: 0.01,
+                first_foundation: 0.51,
+                slot_step: 0.125,
+            },
+            LayoutPreset::Web => Geometry {
+                card_width: 0.1,
+                card_height: 0.18,
+                top_row: 0.04,
+                columns_top: 0.28,
+                first_column: 0.05,
+                column_step: 0.115,
+                first_freecell: 0.05,
+                first_foundation: 0.51,
+                slot_step: 0.115,
+            },
+        })
+    }
+}
+
+impl Geometry {
+    // Left edge of column `i` (from 0) on a game area `width` pixels wide
+    pub fn column_left(&self, i: usize, width: i32) -> i32 {
+        ((self.first_column + i as f64 * self.column_step) * width as f64).round() as i32
+    }
+
+    // Left edges of the 4 freecells then the 4 foundations
+    pub fn slot_lefts(&self, width: i32) -> Vec<i32> {
+        let cells = (0..4).map(|i| self.first_freecell + i as f64 * self.slot_step);
+        let foundations = (0..4).map(|i| self.first_foundation + i as f64 * self.slot_step);
+        cells
+            .chain(foundations)
+            .map(|left| (left * width as f64).round() as i32)
+            .collect()
+    }
+
+    // Pixel to click for a slot on a `width` x `height` game area: the middle of a
+    // freecell or foundation, the middle of the first card of a column
+    pub fn center(&self, location: Location, width: i32, height: i32) -> (i32, i32) {
+        let (left, top) = match location {
+            Location::Column(i) => (
+                self.first_column + i as f64 * self.column_step,
+                self.columns_top,
+            ),
+            Location::Freecell(i) => (
+                self.first_freecell + i as f64 * self.slot_step,
+                self.top_row,
+            ),
+            Location::Foundation(i) => (
+                self.first_foundation + i as f64 * self.slot_step,
+                self.top_row,
+            ),
+        };
+        (
+            ((left + self.card_width / 2.0) * width as f64).round() as i32,
+            ((top + self.card_height / 2.0) * height as f64).round() as i32,
+        )
+    }
+}
+
+// Groups the detections into the columns of a known client on a capture `width`
+// pixels wide: each card goes to the column whose left edge is nearest, which must be
+// within half a card
+pub fn preset_columns(
+    detections: &[Detection],
+    geometry: &Geometry,
+    width: i32,
+) -> Result<Vec<Vec<Detection>>, SolverError> {
+    let lefts: Vec<i32> = (0..Game::new(&[]).columns.len())
+        .map(|i| geometry.column_left(i, width))
+        .collect();
+    let mut columns = vec![Vec::new(); lefts.len()];
+    for detection in detections {
+        columns[nearest(detection, &lefts, "column")?].push(*detection);
+    }
+    sort_columns(columns)
+}
+
+// Everything read from a screenshot: the columns from top to bottom, and the top row
+// with the freecells in its first 4 slots and the foundation tops in the last 4
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoardDetections {
+    pub columns: Vec<Vec<Detection>>,
+    pub top_row: [Option<Detection>; 8],
+}
+
+impl BoardDetections {
+    // Where to press then release the mouse to play `action` on this board, in pixels
+    // of the capture `width` x `height`: on the visible part of the topmost card moved,
+    // then on the last card of the destination column. Empty places are found with
+    // `geometry`, and a card goes to the foundation holding its suit, else to the
+    // leftmost empty one.
+    pub fn move_points(
+        &self,
+        action: &Action,
+        geometry: &Geometry,
+        width: i32,
+        height: i32,
+    ) -> ((i32, i32), (i32, i32)) {
+        let middle = |d: &Detection| (d.x + d.width / 2, d.y + d.height / 2);
+        let empty = |location: Location| geometry.center(location, width, height);
+        let column = |i: usize| self.columns.get(i).map_or(&[][..], Vec::as_slice);
+
+        let (press, moved) = match action.source_location() {
+            Location::Column(i) => {
+                let cards = column(i);
+                let row = cards.len().saturating_sub(action.pile_size.max(1));
+                match (cards.get(row), cards.get(row + 1)) {
+                    // Only the top of a card is left visible by the next one
+                    (Some(card), Some(next)) => (
+                        (card.x + card.width / 2, (card.y + next.y) / 2),
+                        Some(card.card),
+                    ),
+                    (Some(card), None) => (middle(card), Some(card.card)),
+                    _ => (empty(Location::Column(i)), None),
+                }
+            }
+            location @ (Location::Freecell(i) | Location::Foundation(i)) => {
+                match self.top_row.get(i).and_then(Option::as_ref) {
+                    Some(card) => (middle(card), Some(card.card)),
+                    None => (empty(location), None),
+                }
+            }
+        };
+
+        let release = match action.dest_location() {
+            Location::Column(i) => column(i)
+                .last()
+                .map_or_else(|| empty(Location::Column(i)), middle),
+            Location::Freecell(i) => empty(Location::Freecell(i)),
+            Location::Foundation(_) => {
+                let slots = &self.top_row[4..];
+                let same_suit = slots.iter().position(|slot| {
+                    slot.is_some_and(|d| Some(d.card.suit) == moved.map(|c| c.suit))
+                });
+                match same_suit {
+                    Some(slot) => middle(slots[slot].as_ref().unwrap()),
+                    None => {
+                        let free = slots.iter().position(Option::is_none).unwrap_or(0);
+                        empty(Location::Foundation(free))
+                    }
+                }
+            }
+        };
+        (press, release)
+    }
+
+    // The position on the board, which must account for the 52 cards: those under the
+    // top card of a foundation are taken as there
+    pub fn to_game(&self) -> Result<Game, SolverError> {
+        let mut game = Game::new(&[]);
+        for (target, column) in game.columns.iter_mut().zip(&self.columns) {
+            target.extend(column.iter().map(|d| d.card));
+        }
+        let (cells, foundations) = self.top_row.split_at(game.freecells.len());
+        for (cell, slot) in game.freecells.iter_mut().zip(cells) {
+            *cell = slot.map(|d| d.card);
+        }
+        for detection in foundations.iter().flatten() {
+            game.foundations[detection.card.suit as usize] = detection.card.rank;
+        }
+        check_cards(&game)?;
+        Ok(game)
+    }
+}
+
+// Top row and columns of a board read with no preset, see `infer_columns`. The top
+// row slots are taken in line with the columns.
+pub fn infer_board(detections: &[Detection]) -> Result<BoardDetections, SolverError> {
+    let (top, tableau) = split_top_row(detections);
+    let columns = infer_columns(&tableau)?;
+    let top_row = place_top_row(&top, &column_lefts(&columns))?;
+    Ok(BoardDetections { columns, top_row })
+}
+
+// Top row and columns of a board read on a known client, see `preset_columns`
+pub fn preset_board(
+    detections: &[Detection],
+    geometry: &Geometry,
+    width: i32,
+) -> Result<BoardDetections, SolverError> {
+    let (top, tableau) = split_top_row(detections);
+    let columns = preset_columns(&tableau, geometry, width)?;
+    let top_row = place_top_row(&top, &geometry.slot_lefts(width))?;
+    Ok(BoardDetections { columns, top_row })
+}
+
+pub fn infer_layout(detections: &[Detection]) -> Result<Game, SolverError> {
+    infer_board(detections)?.to_game()
+}
+
+// What is wrong with the deck read from a screenshot, positions are 1-based
+// (column, row) as in `set_card`, row 0 being the top row
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeckReport {
+    pub missing: Vec<Card>,
+    pub duplicated: Vec<(Card, Vec<(usize, usize)>)>,
+    // Matches scoring below the warning threshold, right or wrong
+    pub doubtful: Vec<((usize, usize), Detection)>,
+}
+
+impl DeckReport {
+    pub fn new(board: &BoardDetections, warn_confidence: f64) -> Self {
+        let mut report = DeckReport::default();
+        let mut places: HashMap<Card, Vec<(usize, usize)>> = HashMap::new();
+        let top_row = board
+            .top_row
+            .iter()
+            .enumerate()
+            .map(|(slot, d)| (slot, 0, d.as_ref()));
+        let columns = board.columns.iter().enumerate().flat_map(|(i, column)| {
+            column
+                .iter()
+                .enumerate()
+                .map(move |(row, d)| (i, row + 1, Some(d)))
+        });
+        for (i, row, detection) in top_row.chain(columns) {
+            let Some(detection) = detection else {
+                continue;
+            };
+            let place = (i + 1, row);
+            places.entry(detection.card).or_default().push(place);
+            if detection.confidence < warn_confidence {
+                report.doubtful.push((place, *detection));
+            }
+            // A foundation holds every card of its suit up to the top one
+            if row == 0 && i >= 4 {
+                for rank in 1..detection.card.rank {
+                    let card = Card {
+                        rank,
+                        suit: detection.card.suit,
+                    };
+                    places.entry(card).or_default().push(place);
+                }
+            }
+        }
+        for suit in Suit::ALL {
+            for rank in 1..=13 {
+                let card = Card { rank, suit };
+                match places.remove(&card) {
+                    None => report.missing.push(card),
+                    Some(found) if found.len() > 1 => report.duplicated.push((card, found)),
+                    Some(_) => {}
+                }
+            }
+        }
+        report
+    }
+
+    // Missing and duplicated cards make the deck unplayable, doubtful ones do not
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.duplicated.is_empty()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let place = |(column, row): (usize, usize)| json!({ "column": column, "row": row });
+        json!({
+            "valid": self.is_valid(),
+            "missing": self.missing.iter().map(Card::to_string).collect::<Vec<_>>(),
+            "duplicated": self
+                .duplicated
+                .iter()
+                .map(|(card, found)| json!({
+                    "card": card.to_string(),
+                    "at": found.iter().map(|&p| place(p)).collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+            "doubtful": self
+                .doubtful
+                .iter()
+                .map(|(at, detection)| json!({
+                    "card": detection.card.to_string(),
+                    "at": place(*at),
+                    "confidence": detection.confidence,
+                    "method": detection.method.name(),
+                    "runner_up": detection.runner_up.map(|(card, _)| card.to_string()),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+// Merges the rank read from the glyph into a template match. Agreeing reads reinforce
+// each other (1 - (1 - a)(1 - b)); otherwise the most confident one wins, by the
+// margin between the two, so that the card gets reported as doubtful.
+pub fn fuse_rank(detection: &mut Detection, read: Option<(u8, f64)>) {
+    let Some((rank, confidence)) = read else {
+        return;
+    };
+    let template = detection.confidence;
+    if rank == detection.card.rank {
+        detection.confidence = 1.0 - (1.0 - template) * (1.0 - confidence);
+    } else if confidence > template {
+        detection.card.rank = rank;
+        detection.confidence = confidence - template;
+        detection.method = MatchMethod::Classifier;
+    } else {
+        detection.confidence = template - confidence;
+    }
+}
+
+// Fixes one cell from a "column row card" line, all 1-based: "3 5 TD" puts the ten
+// of diamonds in the 5th row of the 3rd column, one row past the end adds it below
+// the column, and "-" as the card removes the cell. Row 0 is the top row: "2 0 8H"
+// puts the eight of hearts in the 2nd freecell, "5 0 3S" makes the three of spades
+// the top of the 1st foundation. A fixed card is trusted.
+pub fn set_card(board: &mut BoardDetections, line: &str) -> Result<(), String> {
+    let [column, row, card] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(format!("Expected `column row card`, got `{}`", line));
+    };
+    let index = |text: &str| {
+        text.parse::<usize>()
+            .ok()
+            .filter(|&n| n >= 1)
+            .map(|n| n - 1)
+    };
+    let parse = |card: &str| {
+        card.parse::<Card>()
+            .map_err(|e: crate::error::CardError| e.to_string())
+    };
+
+    if row == "0" {
+        let slot = index(column)
+            .and_then(|i| board.top_row.get_mut(i))
+            .ok_or_else(|| format!("Invalid column {}", column))?;
+        let card = match card {
+            "-" => None,
+            card => Some(parse(card)?),
+        };
+        let like = *slot;
+        *slot = card.map(|card| Detection {
+            x: like.map_or(0, |d| d.x),
+            y: like.map_or(0, |d| d.y),
+            width: like.map_or(0, |d| d.width),
+            height: like.map_or(0, |d| d.height),
+            confidence: 1.0,
+            method: MatchMethod::Manual,
+            card,
+            runner_up: None,
+        });
+        return Ok(());
+    }
+
+    let column = index(column)
+        .and_then(|i| board.columns.get_mut(i))
+        .ok_or_else(|| format!("Invalid column {}", column))?;
+    let row = index(row)
+        .filter(|&r| r <= column.len())
+        .ok_or_else(|| format!("Invalid row {}", row))?;
+
+    if card == "-" {
+        if row == column.len() {
+            return Err(format!("Invalid row {}", row + 1));
+        }
+        column.remove(row);
+        return Ok(());
+    }
+    let card = parse(card)?;
+    match column.get_mut(row) {
+        Some(detection) => {
+            detection.card = card;
+            detection.confidence = 1.0;
+            detection.method = MatchMethod::Manual;
+            detection.runner_up = None;
+        }
+        None => {
+            let last = column.last().copied();
+            column.push(Detection {
+                x: last.map_or(0, |d| d.x),
+                y: last.map_or(0, |d| d.y + 1),
+                width: last.map_or(0, |d| d.width),
+                height: last.map_or(0, |d| d.height),
+                confidence: 1.0,
+                method: MatchMethod::Manual,
+                card,
+                runner_up: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Where a card sits on the screenshot but matched no template: holes in a column,
+// wherever two cards in a row are more than one step apart. The step is the usual gap
+// between two cards of a column. Boxes are x, y, width, height, the size of the card
+// above; positions are 1-based (column, row) once the holes are filled.
+pub fn unknown_regions(columns: &[Vec<Detection>]) -> Vec<((usize, usize), [i32; 4])> {
+    let mut steps: Vec<i32> = columns
+        .iter()
+        .flat_map(|column| column.windows(2).map(|w| w[1].y - w[0].y))
+        .collect();
+    steps.sort();
+    let Some(&step) = steps.get(steps.len() / 2) else {
+        return Vec::new();
+    };
+
+    let mut regions = Vec::new();
+    for (i, column) in columns.iter().enumerate() {
+        let mut row = 1;
+        for pair in column.windows(2) {
+            let missing = ((pair[1].y - pair[0].y) as f64 / step as f64).round() as i32 - 1;
+            for k in 1..=missing {
+                row += 1;
+                let [x, y, width, height] = pair[0].area();
+                regions.push(((i + 1, row), [x, y + k * step, width, height]));
+            }
+            row += 1;
+        }
+    }
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::action::ActionType;
+    use crate::deal::ms_deal;
+
+    // Where a deal sits on screen: 100px between columns, 30px between rows, and the
+    // top row 180px above the columns
+    fn detections(game: &Game) -> Vec<Detection> {
+        let top = |slot: usize, card: Card| Detection {
+            x: 20 + 100 * slot as i32,
+            y: 20,
+            width: 80,
+            height: 30,
+            confidence: 0.95,
+            method: MatchMethod::Template,
+            card,
+            runner_up: None,
+        };
+        let cells = game
+            .freecells
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, card)| Some(top(slot, (*card)?)));
+        let foundations = Suit::ALL.iter().enumerate().filter_map(|(i, &suit)| {
+            let rank = game.foundations[suit as usize];
+            (rank > 0).then(|| top(4 + i, Card { rank, suit }))
+        });
+        let mut detections: Vec<Detection> = cells.chain(foundations).collect();
+        detections.extend(game.columns.iter().enumerate().flat_map(|(i, column)| {
+            column
+                .iter()
+                .enumerate()
+                .map(move |(row, &card)| Detection {
+                    x: 20 + 100 * i as i32 + (row % 3) as i32,
+                    y: 200 + 30 * row as i32,
+                    width: 80,
+                    height: 30,
+                    confidence: 0.95,
+                    method: MatchMethod::Template,
+                    card,
+                    runner_up: None,
+                })
+        }));
+        // The OCR order: rows first, which is not the dealing order
+        detections.sort_by_key(|d| (d.y, d.x));
+        detections
+    }
+
+    #[test]
+    fn test_infer_layout() {
+        let game = Game::new(&ms_deal(617));
+        let mut found = detections(&game);
+        assert_eq!(
+            infer_layout(&found).unwrap().layout_key(),
+            game.layout_key()
+        );
+
+        // A column shifted onto its neighbour puts two cards in each of its rows
+        let mut merged = found.clone();
+        merged
+            .iter_mut()
+            .filter(|d| d.x >= 720)
+            .for_each(|d| d.x -= 60);
+        assert!(infer_layout(&merged).is_err());
+
+        // A card matched twice
+        found[0].card = found[8].card;
+        assert!(infer_layout(&found).is_err());
+    }
+
+    #[test]
+    fn test_preset_columns() {
+        let game = Game::new(&ms_deal(617));
+        let mut found = detections(&game);
+        // Columns 100px apart from x = 20, on a capture 1000px wide
+        let geometry = Geometry {
+            first_column: 0.02,
+            column_step: 0.1,
+            ..LayoutPreset::Web.geometry().unwrap()
+        };
+        assert_eq!(
+            preset_columns(&found, &geometry, 1000).unwrap(),
+            infer_columns(&found).unwrap()
+        );
+        assert_eq!(geometry.center(Location::Column(1), 1000, 500), (170, 185));
+
+        found[3].x += 50;
+        assert!(preset_columns(&found, &geometry, 1000).is_err());
+    }
+
+    #[test]
+    fn test_non_max_suppression() {
+        let card = |x: i32, y: i32, confidence: f64, code: &str| Detection {
+            x,
+            y,
+            width: 36,
+            height: 62,
+            confidence,
+            method: MatchMethod::Template,
+            card: code.parse().unwrap(),
+            runner_up: None,
+        };
+        let candidates = vec![
+            card(100, 100, 0.91, "TD"),
+            card(101, 100, 0.97, "TD"),
+            card(100, 101, 0.93, "TD"),
+            // The same template further down the column
+            card(100, 160, 0.85, "TD"),
+            // Another template on the same card
+            card(101, 101, 0.82, "TH"),
+        ];
+        let mut kept = non_max_suppression(candidates.clone(), 0.3);
+        assert_eq!(kept.len(), 2);
+        assert_eq!((kept[0].x, kept[0].confidence), (101, 0.97));
+        assert_eq!(kept[1].y, 160);
+
+        note_runner_ups(&mut kept, &candidates, 0.3);
+        assert_eq!(kept[0].runner_up, Some(("TH".parse().unwrap(), 0.82)));
+        assert_eq!(kept[1].runner_up, None);
+    }
+
+    #[test]
+    fn test_pair_corners() {
+        fn glyph<T>(x: i32, y: i32, value: T) -> Glyph<T> {
+            Glyph {
+                x,
+                y,
+                width: 14,
+                height: 18,
+                confidence: 0.9,
+                value,
+            }
+        }
+        // Two cards of a column, 30px apart: only their top strips show
+        let ranks = [glyph(100, 100, 10), glyph(100, 130, 4), glyph(300, 100, 1)];
+        let suits = [glyph(101, 120, Suit::Diamond), glyph(100, 150, Suit::Spade)];
+        let cards: Vec<String> = pair_corners(&ranks, &suits)
+            .iter()
+            .map(|d| d.card.to_string())
+            .collect();
+        assert_eq!(cards, ["TD", "4S"]);
+    }
+
+    #[test]
+    fn test_fuse_rank() {
+        let mut detection = Detection {
+            x: 0,
+            y: 0,
+            width: 36,
+            height: 62,
+            confidence: 0.8,
+            method: MatchMethod::Template,
+            card: "QH".parse().unwrap(),
+            runner_up: None,
+        };
+        fuse_rank(&mut detection, Some((12, 0.5)));
+        assert!((detection.confidence - 0.9).abs() < 1e-9);
+
+        fuse_rank(&mut detection, Some((13, 0.95)));
+        assert_eq!(detection.card.to_string(), "KH");
+        assert_eq!(detection.method, MatchMethod::Classifier);
+        assert!(detection.confidence < 0.1);
+    }
+
+    #[test]
+    fn test_deck_report_and_fix() {
+        let game = Game::new(&ms_deal(617));
+        let mut board = infer_board(&detections(&game)).unwrap();
+        let first = board.columns[0][0].card;
+        let second = board.columns[0][1].card;
+        board.columns[0][0].card = second;
+        board.columns[2][3].confidence = 0.85;
+
+        let report = DeckReport::new(&board, 0.9);
+        assert!(!report.is_valid());
+        assert_eq!(report.missing, vec![first]);
+        assert_eq!(report.duplicated, vec![(second, vec![(1, 1), (1, 2)])]);
+        assert_eq!(report.doubtful, vec![((3, 4), board.columns[2][3])]);
+
+        set_card(&mut board, &format!("1 1 {}", first)).unwrap();
+        assert_eq!(board.columns[0][0].method, MatchMethod::Manual);
+        assert!(DeckReport::new(&board, 0.9).is_valid());
+        assert_eq!(board.to_game().unwrap().layout_key(), game.layout_key());
+        assert!(set_card(&mut board, "9 1 TD").is_err());
+        assert!(set_card(&mut board, "1 1").is_err());
+
+        // The top row is row 0
+        let card = board.columns[7].pop().unwrap().card;
+        set_card(&mut board, &format!("2 0 {}", card)).unwrap();
+        assert_eq!(board.to_game().unwrap().freecells[1], Some(card));
+        set_card(&mut board, "2 0 -").unwrap();
+        assert_eq!(DeckReport::new(&board, 0.9).missing, vec![card]);
+    }
+
+    #[test]
+    fn test_move_points() {
+        let mut game = Game::new(&ms_deal(617));
+        let ace = Card {
+            rank: 1,
+            suit: Suit::Heart,
+        };
+        let two = Card {
+            rank: 2,
+            suit: Suit::Heart,
+        };
+        game.columns
+            .iter_mut()
+            .for_each(|c| c.retain(|&c| c != ace && c != two));
+        game.columns[7].push(two);
+        game.foundations[Suit::Heart as usize] = 1;
+        game.freecells[1] = game.columns[0].pop();
+        let board = infer_board(&detections(&game)).unwrap();
+        let geometry = LayoutPreset::Web.geometry().unwrap();
+        let action = |action_type, source, dest, pile_size| Action {
+            action_type,
+            source,
+            dest,
+            pile_size,
+        };
+
+        // Two cards of the 6th column onto the 3rd: pressed between the rows
+        let (press, release) =
+            board.move_points(&action(ActionType::ColToCol, 5, 2, 2), &geometry, 1000, 500);
+        let moved = &board.columns[5][game.columns[5].len() - 2];
+        assert_eq!(press, (moved.x + 40, moved.y + 15));
+        let last = &board.columns[2].last().unwrap();
+        assert_eq!(release, (last.x + 40, last.y + 15));
+
+        // From the freecell, to a free one and to the foundation of its suit
+        let (press, release) = board.move_points(
+            &action(ActionType::ColToFreecell, 0, 0, 1),
+            &geometry,
+            1000,
+            500,
+        );
+        assert_eq!(release, geometry.center(Location::Freecell(0), 1000, 500));
+        assert!(press.1 > 200);
+        let (_, release) = board.move_points(
+            &action(ActionType::ColToFoundation, 7, 0, 1),
+            &geometry,
+            1000,
+            500,
+        );
+        let heart = board.top_row[4..].iter().flatten().next().unwrap();
+        assert_eq!(release, (heart.x + 40, heart.y + 15));
+        let (press, _) = board.move_points(
+            &action(ActionType::FreecellToCol, 1, 3, 1),
+            &geometry,
+            1000,
+            500,
+        );
+        assert_eq!(press, (20 + 100 + 40, 35));
+    }
+
+    #[test]
+    fn test_mid_game_board() {
+        // Hearts up to the two on their foundation, a card in a freecell and the last
+        // column emptied onto the one before
+        let mut game = Game::new(&ms_deal(617));
+        for rank in 1..=2 {
+            let card = Card {
+                rank,
+                suit: Suit::Heart,
+            };
+            for column in &mut game.columns {
+                column.retain(|&c| c != card);
+            }
+        }
+        game.foundations[Suit::Heart as usize] = 2;
+        game.freecells[1] = game.columns[0].pop();
+        let last = std::mem::take(&mut game.columns[7]);
+        game.columns[6].extend(last);
+
+        let found = detections(&game);
+        let (top, tableau) = split_top_row(&found);
+        assert_eq!(top.len(), 2);
+        assert_eq!(tableau.len(), found.len() - 2);
+        assert_eq!(
+            infer_layout(&found).unwrap().layout_key(),
+            game.layout_key()
+        );
+        let board = infer_board(&found).unwrap();
+        assert!(DeckReport::new(&board, 0.9).is_valid());
+
+        // A fresh deal has no top row
+        assert!(
+            split_top_row(&detections(&Game::new(&ms_deal(617))))
+                .0
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_unknown_regions() {
+        let game = Game::new(&ms_deal(617));
+        let mut columns = infer_columns(&detections(&game)).unwrap();
+        assert!(unknown_regions(&columns).is_empty());
+
+        let gone = columns[4].remove(2);
+        columns[4].remove(2);
+        let regions = unknown_regions(&columns);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0], ((5, 3), [columns[4][1].x, gone.y, 80, 30]));
+        assert_eq!(regions[1].0, (5, 4));
+    }
+}
@@ -0,0 +1,106 @@
+//! FreeCell solver: deals, game rules, search strategies and the tooling built on
+//! them (batch runs, notation, board formats). The `freecell` binary is a command
+//! line front end over this crate.
+//!
+//! The stable API is re-exported at the root, see [`api`]: [`solve`] with a
+//! [`SolverConfig`] and its [`Limits`], giving a [`Solution`] and its [`SearchStats`],
+//! along with the [`Game`], [`Action`], [`Strategy`] and [`SolverError`] types they use,
+//! and the [`SearchObserver`] hooks a `solver::Solver` reports to while it searches.
+//! The other modules serve the binary and may change in any release.
+//!
+//! Without default features only the core is built (cards, game rules, solver), which
+//! depends on std and the thiserror derive alone. The `rand` feature adds random deals,
+//! `serde` the serialization of games and moves, and `tools` everything the binary uses.
+//!
+//! ```
+//! use freecell::deal::ms_deal;
+//! use freecell::game::Game;
+//! use freecell::solver::Solver;
+//!
+//! let mut solver = Solver::new(Game::new(&ms_deal(1)));
+//! solver.verbose = false;
+//! let solution = solver.solve(100_000).expect("deal 1 is solvable");
+//! assert!(!solution.is_empty());
+//! ```
+
+pub mod action;
+pub mod api;
+pub mod card;
+pub mod deal;
+pub mod error;
+pub mod game;
+mod heap;
+pub mod i18n;
+pub mod observer;
+pub mod snapshot;
+pub mod solver;
+pub mod stats;
+pub mod verify;
+
+// Tooling of the binary, behind the `tools` feature
+#[cfg(feature = "tools")]
+pub mod analyze;
+#[cfg(feature = "tools")]
+pub mod batch;
+#[cfg(feature = "tools")]
+pub mod bench;
+#[cfg(feature = "tools")]
+pub mod board;
+#[cfg(feature = "tools")]
+pub mod classify;
+#[cfg(feature = "tools")]
+pub mod compare;
+#[cfg(feature = "tools")]
+pub mod config;
+#[cfg(feature = "tools")]
+pub mod crosscheck;
+#[cfg(all(feature = "tools", not(target_arch = "wasm32")))]
+pub mod db;
+#[cfg(all(feature = "tools", feature = "png"))]
+pub mod detect;
+#[cfg(feature = "tools")]
+pub mod display;
+#[cfg(feature = "tools")]
+pub mod estimate;
+#[cfg(feature = "tools")]
+pub mod exit;
+#[cfg(feature = "tools")]
+pub mod explain;
+#[cfg(feature = "tools")]
+pub mod extract;
+#[cfg(feature = "tools")]
+pub mod fixtures;
+#[cfg(feature = "tools")]
+pub mod generate;
+#[cfg(feature = "tools")]
+pub mod history;
+#[cfg(feature = "tools")]
+pub mod layout;
+#[cfg(feature = "tools")]
+pub mod notation;
+#[cfg(feature = "tools")]
+pub mod output;
+#[cfg(feature = "tools")]
+pub mod postprocess;
+#[cfg(feature = "tools")]
+pub mod rate;
+#[cfg(feature = "tools")]
+pub mod render;
+#[cfg(feature = "tools")]
+pub mod replay;
+#[cfg(feature = "tools")]
+pub mod svg;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use action::Action;
+pub use api::{Limits, Solution, SolverConfig, solve};
+pub use deal::ms_deal;
+pub use error::SolverError;
+pub use game::Game;
+pub use observer::{NullObserver, SearchObserver};
+pub use solver::{HeuristicWeights, Strategy};
+pub use stats::SearchStats;
@@ -1,67 +1,1476 @@
-mod action;
-mod card;
-mod game;
-mod heap;
+mod cli;
+mod engine;
+mod metrics;
+#[cfg(feature = "vision")]
 mod ocr;
+mod play;
+#[cfg(feature = "automation")]
 mod screen;
-mod solver;
-use crate::card::{Card, Suit};
-use crate::game::Game;
-use crate::solver::Solver;
+mod serve;
+
+use crate::cli::{
+    AnalyzeArgs, BatchArgs, Cli, Command, CompareArgs, CrosscheckArgs, GenerateArgs, RateArgs,
+    ServeArgs, SolveArgs, TemplatesCommand,
+};
+use clap::Parser;
 use dotenv::dotenv;
-use rand::seq::SliceRandom;
-use std::time::Instant;
-
-#[allow(dead_code)]
-fn generate_random_deck() -> Vec<Card> {
-    let mut deck: Vec<Card> = (0..52)
-        .map(|i| Card {
-            rank: ((i % 13) + 1) as u8,
-            suit: match i / 13 {
-                0 => Suit::Diamond,
-                1 => Suit::Club,
-                2 => Suit::Spade,
-                _ => Suit::Heart,
-            },
-        })
-        .collect();
+use freecell::batch::{BatchOptions, BatchSummary, Checkpoint, ReportFormat};
+#[cfg(feature = "vision")]
+use freecell::config::OcrSettings;
+use freecell::config::{Config, load_config};
+use freecell::db::ResultsDb;
+use freecell::display::BoardStyle;
+use freecell::estimate::estimate_tree_size;
+use freecell::exit::Outcome;
+use freecell::game::Game;
+use freecell::generate::DealFilter;
+use freecell::i18n::{Lang, Msg, set_lang};
+use freecell::output::{OutputMode, OutputOptions, SolutionFormat, print_solution};
+use freecell::postprocess::{merge_supermoves, remove_wasted_moves};
+use freecell::snapshot::SearchSnapshot;
+use freecell::solver::{Solver, Strategy};
+use freecell::{
+    analyze, batch, bench, board, compare, crosscheck, db, deal, generate, output, rate, render,
+    replay, svg, verify,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+#[cfg(feature = "vision")]
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
-    let mut rng = rand::rng();
-    deck.shuffle(&mut rng);
-    deck
+// Messages for people on stderr, silenced by --format quiet
+macro_rules! status {
+    ($mode:expr, $($arg:tt)*) => {
+        if !$mode.is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
 }
 
-fn main() {
+fn main() -> ExitCode {
     dotenv().ok();
+    let cli = Cli::parse();
+    let mode = cli.format;
+    set_lang(
+        cli.lang
+            .unwrap_or_else(|| Lang::from_locale(&std::env::var("LANG").unwrap_or_default())),
+    );
+    let mut config = match load_config(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            status!(mode, "{}", Msg::ConfigInvalid(&e));
+            return Outcome::InputError.into();
+        }
+    };
+    if cli.theme.is_some() {
+        config.ocr.theme = cli.theme.clone();
+    }
+    #[cfg(feature = "automation")]
+    if cli.display.is_some() {
+        config.screen.display = cli.display;
+    }
+    #[cfg(feature = "automation")]
+    if cli.window.is_some() {
+        config.screen.window = cli.window.clone();
+    }
+    #[cfg(feature = "automation")]
+    if cli.click_region {
+        config.screen.detect = false;
+    }
+    #[cfg(feature = "automation")]
+    if cli.reselect {
+        config.screen.region = None;
+        config.screen.detect = false;
+    }
+    #[cfg(feature = "automation")]
+    if cli.rect.is_some() {
+        config.screen.region = cli.rect;
+        config.screen.window = None;
+    }
+    #[cfg(feature = "automation")]
+    if cli.save_captures {
+        config.screen.save_captures = true;
+    }
+    #[cfg(all(feature = "vision", feature = "automation"))]
+    if cli.archive.is_some() {
+        config.screen.archive = cli.archive.clone();
+    }
+
+    let style = BoardStyle::detect(cli.no_color, &config.display);
+
+    match &cli.command {
+        Some(Command::Play {
+            deal,
+            seed,
+            history,
+        }) => run_play(*deal, *seed, history.as_deref(), &config, mode, style).into(),
+        #[cfg(feature = "automation")]
+        Some(Command::Capture { output }) => run_capture(output, &config, mode).into(),
+        Some(Command::Replay { file }) => run_replay(file, mode, style).into(),
+        #[cfg(feature = "vision")]
+        Some(Command::Ocr {
+            fix,
+            output,
+            image,
+            solve,
+            debug_ocr,
+        }) => {
+            let mut settings = config.ocr.clone();
+            if let Some(image) = image {
+                settings.capture = image.clone();
+            }
+            if debug_ocr.is_some() {
+                settings.debug_image = debug_ocr.clone();
+            }
+            let solve = solve.then_some(&cli.solve);
+            run_ocr(
+                *fix,
+                output.as_deref(),
+                ocr::Capture::File(&settings.capture),
+                &settings,
+                solve,
+                &config,
+                mode,
+                style,
+            )
+            .into()
+        }
+        #[cfg(feature = "vision")]
+        Some(Command::OcrCheck { folder }) => run_ocr_check(folder, &config, mode).into(),
+        #[cfg(all(feature = "vision", feature = "automation"))]
+        Some(Command::Rescan { fix }) => run_rescan(
+            *fix,
+            cli.config.as_deref(),
+            &cli.solve,
+            &config,
+            mode,
+            style,
+        )
+        .into(),
+        #[cfg(all(feature = "vision", feature = "automation"))]
+        Some(Command::Autoplay { max_moves }) => {
+            let path = cli.config.as_deref();
+            run_autoplay(*max_moves, path, &cli.solve, &config, mode).into()
+        }
+        #[cfg(all(feature = "vision", feature = "automation"))]
+        Some(Command::Hotkey { keys }) => {
+            let path = cli.config.as_deref();
+            run_hotkey(keys, path, &cli.solve, &config, mode, style).into()
+        }
+        #[cfg(all(feature = "vision", feature = "automation"))]
+        Some(Command::Watch { interval, deals }) => {
+            let path = cli.config.as_deref();
+            run_watch(*interval, *deals, path, &cli.solve, &config, mode, style).into()
+        }
+        Some(Command::Templates {
+            command: TemplatesCommand::Extract { capture, deal, out },
+        }) => run_templates_extract(capture, *deal, out.as_deref(), &config, mode).into(),
+        #[cfg(feature = "vision")]
+        Some(Command::Templates {
+            command: TemplatesCommand::Label,
+        }) => run_templates_label(&config, mode).into(),
+        Some(Command::Solve(args)) => run_solve(args, &config, mode, style).into(),
+        Some(Command::Batch(args)) => run_batch(args, &config, mode).into(),
+        Some(Command::Analyze(args)) => run_analyze(args, &config, mode).into(),
+        Some(Command::Compare(args)) => run_compare(args, &config, mode).into(),
+        Some(Command::Crosscheck(args)) => run_crosscheck(args, &config, mode).into(),
+        Some(Command::Rate(args)) => run_rate(args, &config, mode).into(),
+        Some(Command::Generate(args)) => run_generate(args, &config, mode).into(),
+        Some(Command::Results { db }) => run_results(db, mode).into(),
+        Some(Command::Serve(args)) => run_serve(args, &config, mode).into(),
+        Some(Command::Engine { max_nodes }) => {
+            engine::run_engine(batch_options(
+                &config,
+                resolve_strategy(None, &config),
+                *max_nodes,
+                None,
+            ));
+            Outcome::Solved.into()
+        }
+        Some(Command::Bench {
+            max_nodes,
+            time_limit,
+        }) => {
+            bench::run_bench(
+                *max_nodes,
+                Duration::from_secs_f64(*time_limit),
+                config.heuristic,
+                mode,
+            );
+            Outcome::Solved.into()
+        }
+        None => run_solve(&cli.solve, &config, mode, style).into(),
+    }
+}
+
+// Only the capture, for scripts that read it later: with --rect or a region in the
+// config, nothing is asked
+#[cfg(feature = "automation")]
+fn run_capture(output: &str, config: &Config, mode: OutputMode) -> Outcome {
+    let saved = screen::start_screenshot(&config.screen, &config.automation).and_then(|shot| {
+        shot.img
+            .save(output)
+            .map_err(|e| freecell::error::OcrError::Capture(e.to_string()))
+    });
+    match saved {
+        Ok(()) => {
+            status!(mode, "{}", Msg::CaptureSaved(output));
+            Outcome::Solved
+        }
+        Err(e) => {
+            status!(mode, "❌ {}", Msg::from(&e));
+            Outcome::InputError
+        }
+    }
+}
+
+// Interactive in text mode, otherwise only checks that the solution wins
+fn run_replay(file: &str, mode: OutputMode, style: BoardStyle) -> Outcome {
+    let (game, actions) = match replay::load_solution(file) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            status!(mode, "{}", Msg::SolutionLoadFailed(&e));
+            return Outcome::InputError;
+        }
+    };
+    if mode == OutputMode::Text {
+        replay::run_replay(game, &actions, style);
+        return Outcome::Solved;
+    }
+
+    // Before anything plays the moves unchecked
+    let wins = match verify::verify_solution(&game, &actions) {
+        Ok(state) => state.is_won(),
+        Err(e) => {
+            status!(mode, "{}: {}", Msg::IllegalMove, e);
+            return Outcome::InputError;
+        }
+    };
+    if mode == OutputMode::Json {
+        let json = serde_json::json!({
+            "deal": output::game_json(&game),
+            "moves": output::moves_json(&game, &actions),
+            "valid": true,
+            "wins": wins,
+        });
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    }
+    match wins {
+        true => Outcome::Solved,
+        false => Outcome::Unsolvable,
+    }
+}
+
+// Limits given on the command line, else those of the config
+fn batch_options(
+    config: &Config,
+    strategy: Strategy,
+    max_nodes: Option<u32>,
+    time_limit: Option<f64>,
+) -> BatchOptions {
+    BatchOptions {
+        strategy,
+        max_nodes: max_nodes.unwrap_or(config.solver.max_nodes),
+        time_limit: time_limit
+            .or(config.solver.time_limit)
+            .map(Duration::from_secs_f64),
+        weights: config.heuristic,
+        optimize: true,
+        rate: false,
+    }
+}
+
+// Strategy given on the command line, else the config, else the STRATEGY variable of .env
+fn resolve_strategy(strategy: Option<Strategy>, config: &Config) -> Strategy {
+    strategy.or(config.solver.strategy).unwrap_or_else(|| {
+        dotenv::var("STRATEGY")
+            .ok()
+            .and_then(|s| s.parse::<Strategy>().ok())
+            .unwrap_or_default()
+    })
+}
+
+fn read_input(path: &str) -> Result<String, String> {
+    match path {
+        "-" => std::io::read_to_string(std::io::stdin()),
+        _ => std::fs::read_to_string(path),
+    }
+    .map_err(|e| Msg::ReadFailed(path, &e).to_string())
+}
+
+fn load_game(args: &SolveArgs, seed: u64) -> Result<Game, String> {
+    if let Some(number) = args.deal {
+        return Ok(Game::new(&deal::ms_deal(number)));
+    }
+    if let Some(cards) = &args.cards {
+        return deal::parse_deck(cards)
+            .map(|deck| Game::new(&deck))
+            .map_err(|e| Msg::InvalidDeal(&e).to_string());
+    }
+    let Some(path) = &args.board else {
+        return Ok(Game::new(&deal::random_deal(seed)));
+    };
+
+    let text = read_input(path)?;
+    board::parse_board(&text).map_err(|e| Msg::BoardLoadFailed(path, &e).to_string())
+}
+
+fn run_batch(args: &BatchArgs, config: &Config, mode: OutputMode) -> Outcome {
+    let text = match read_input(args.input.as_deref().unwrap_or("-")) {
+        Ok(text) => text,
+        Err(e) => {
+            status!(mode, "❌ {}", e);
+            return Outcome::InputError;
+        }
+    };
+
+    let mut deals = batch::parse_batch(&text);
+    let mut db = match args.db.as_deref().map(ResultsDb::open).transpose() {
+        Ok(db) => db,
+        Err(e) => {
+            status!(mode, "{}", Msg::DbUnusable(&e));
+            return Outcome::InputError;
+        }
+    };
+    if let Some(db) = &db {
+        let solved = match db.solved_deals() {
+            Ok(solved) => solved,
+            Err(e) => {
+                status!(mode, "{}", Msg::DbUnreadable(&e));
+                return Outcome::InputError;
+            }
+        };
+        let before = deals.len();
+        deals.retain(|deal| match &deal.game {
+            Ok(game) => !solved.contains(&(db::deal_hash(game), db::ruleset(game))),
+            Err(_) => true,
+        });
+        if deals.len() < before {
+            status!(
+                mode,
+                "{}",
+                Msg::DbAlreadySolved(before - deals.len(), deals.len())
+            );
+        }
+    }
+
+    let options = BatchOptions {
+        optimize: !args.no_optimize,
+        rate: args.rate,
+        ..batch_options(
+            config,
+            resolve_strategy(args.strategy, config),
+            args.max_nodes,
+            args.time_limit,
+        )
+    };
+    let checkpoint = args
+        .checkpoint
+        .as_deref()
+        .map(|path| Checkpoint::new(path, args.checkpoint_every));
+    // --format json turns the text report into JSON Lines, csv and jsonl are kept
+    let report = match (mode, args.report) {
+        (OutputMode::Quiet, _) => None,
+        (OutputMode::Json, ReportFormat::Text) => Some(ReportFormat::Jsonl),
+        (_, report) => Some(report),
+    };
+    let results = match batch::run_batch(&deals, options, args.jobs, report, checkpoint) {
+        Ok(results) => results,
+        Err(e) => {
+            status!(mode, "{}", Msg::BatchInterrupted(&e));
+            return Outcome::InputError;
+        }
+    };
+
+    let summary = BatchSummary::new(&results);
+    if let Some(report) = report {
+        println!("{}", summary.format(report));
+    }
+    if let Some(path) = &args.summary {
+        let json = serde_json::to_string_pretty(&summary.to_json()).unwrap();
+        match std::fs::write(path, json) {
+            Ok(()) => status!(mode, "{}", Msg::SummarySaved(path)),
+            Err(e) => status!(mode, "{}", Msg::WriteFailed(path, &e)),
+        }
+    }
+
+    if let Some(db) = &mut db {
+        let games: HashMap<&str, &Game> = deals
+            .iter()
+            .filter_map(|deal| Some((deal.id.as_str(), deal.game.as_ref().ok()?)))
+            .collect();
+        let rows = results
+            .iter()
+            .filter_map(|result| Some((result, *games.get(result.id.as_str())?)));
+        if let Err(e) = db.record(rows) {
+            status!(mode, "{}", Msg::DbNotRecorded(&e));
+            return Outcome::InputError;
+        }
+    }
+    Outcome::Solved
+}
+
+fn run_results(db: &str, mode: OutputMode) -> Outcome {
+    let trends = match ResultsDb::open(db).and_then(|db| db.version_trends()) {
+        Ok(trends) => trends,
+        Err(e) => {
+            status!(mode, "❌ {}", e);
+            return Outcome::InputError;
+        }
+    };
+
+    match mode {
+        OutputMode::Text => {
+            println!(
+                "{:<10} {:>8} {:>8} {:>12} {:>10} {:>8}",
+                "version", "runs", "solved", "avg nodes", "avg ms", "avg moves"
+            );
+            for trend in &trends {
+                println!(
+                    "{:<10} {:>8} {:>8} {:>12.0} {:>10.1} {:>8}",
+                    trend.version,
+                    trend.runs,
+                    trend.solved,
+                    trend.avg_nodes,
+                    trend.avg_time_ms,
+                    trend
+                        .avg_moves
+                        .map_or("-".to_string(), |m| format!("{:.1}", m))
+                );
+            }
+        }
+        OutputMode::Json => {
+            let json: Vec<_> = trends
+                .iter()
+                .map(|trend| {
+                    serde_json::json!({
+                        "version": trend.version,
+                        "runs": trend.runs,
+                        "solved": trend.solved,
+                        "avg_nodes": trend.avg_nodes,
+                        "avg_time_ms": trend.avg_time_ms,
+                        "avg_moves": trend.avg_moves,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        }
+        OutputMode::Quiet => {}
+    }
+    Outcome::Solved
+}
+
+fn run_analyze(args: &AnalyzeArgs, config: &Config, mode: OutputMode) -> Outcome {
+    let deals = match args.deal {
+        Some(number) => batch::parse_batch(&number.to_string()),
+        None => match read_input(args.input.as_deref().unwrap_or("-")) {
+            Ok(text) => batch::parse_batch(&text),
+            Err(e) => {
+                status!(mode, "❌ {}", e);
+                return Outcome::InputError;
+            }
+        },
+    };
+    let options = batch_options(
+        config,
+        resolve_strategy(args.strategy, config),
+        args.max_nodes,
+        args.time_limit,
+    );
+    let analyses = match analyze::analyze_cells(&deals, args.cells.clone(), options, args.jobs) {
+        Ok(analyses) => analyses,
+        Err(e) => {
+            status!(mode, "{}", Msg::AnalyzeInterrupted(&e));
+            return Outcome::InputError;
+        }
+    };
+    let summaries = analyze::summaries(&analyses);
+
+    match mode {
+        OutputMode::Text => {
+            for analysis in &analyses {
+                println!("{}", analysis.to_text());
+            }
+            println!();
+            for (cells, summary) in &summaries {
+                println!(
+                    "{}",
+                    Msg::CellsSummary(
+                        *cells,
+                        summary.solved,
+                        summary.deals - summary.invalid,
+                        summary.nodes[0],
+                        summary.nodes[1],
+                        summary.nodes[3]
+                    )
+                );
+            }
+        }
+        OutputMode::Json => {
+            let json = serde_json::json!({
+                "deals": analyses.iter().map(|a| a.to_json()).collect::<Vec<_>>(),
+                "cells": summaries
+                    .iter()
+                    .map(|(cells, summary)| {
+                        let mut json = summary.to_json();
+                        json["cells"] = serde_json::json!(cells);
+                        json
+                    })
+                    .collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        }
+        OutputMode::Quiet => {}
+    }
+    Outcome::Solved
+}
+
+fn run_serve(args: &ServeArgs, config: &Config, mode: OutputMode) -> Outcome {
+    let limits = batch_options(
+        config,
+        resolve_strategy(None, config),
+        args.max_nodes,
+        args.time_limit,
+    );
+    let address = format!("{}:{}", args.host, args.port);
+    if let Err(e) = serve::run_server(&address, args.jobs, limits) {
+        status!(mode, "{}", Msg::ListenFailed(&address, &e));
+        return Outcome::InputError;
+    }
+    Outcome::Solved
+}
+
+// The OCR settings with their theme, guessed from the capture if need be
+#[cfg(feature = "vision")]
+fn themed_settings(
+    capture: ocr::Capture,
+    settings: &OcrSettings,
+    mode: OutputMode,
+) -> Option<OcrSettings> {
+    match ocr::resolve_theme(capture, settings) {
+        Ok(settings) => {
+            if let Some(theme) = &settings.theme {
+                status!(mode, "{}", Msg::OcrTheme(theme));
+            }
+            Some(settings)
+        }
+        Err(e) => {
+            status!(mode, "❌ {}", Msg::from(&e));
+            None
+        }
+    }
+}
+
+#[cfg(feature = "vision")]
+#[allow(clippy::too_many_arguments)]
+fn run_ocr(
+    fix: bool,
+    output: Option<&str>,
+    capture: ocr::Capture,
+    settings: &OcrSettings,
+    solve: Option<&SolveArgs>,
+    config: &Config,
+    mode: OutputMode,
+    style: BoardStyle,
+) -> Outcome {
+    let settings = match themed_settings(capture, settings, mode) {
+        Some(settings) => settings,
+        None => return Outcome::InputError,
+    };
+    let now = Instant::now();
+    let mut board = match ocr::read_board(capture, &settings) {
+        Ok(board) => {
+            let cards = board.columns.iter().map(Vec::len).sum::<usize>()
+                + board.top_row.iter().flatten().count();
+            status!(mode, "{}", Msg::OcrRecognized(cards, now.elapsed()));
+            board
+        }
+        Err(e) => {
+            status!(mode, "❌ {}", e);
+            return Outcome::InputError;
+        }
+    };
+    let report = freecell::layout::DeckReport::new(&board, settings.warn_confidence);
+    // With --solve, the JSON solution is the only thing on stdout
+    if mode == OutputMode::Json && solve.is_none() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report.to_json()).unwrap()
+        );
+    } else if (fix || std::io::stdin().is_terminal()) && !report.is_valid() {
+        match ocr::review_board(&mut board, &settings) {
+            Ok(true) => {}
+            Ok(false) => return Outcome::InputError,
+            Err(e) => {
+                status!(mode, "❌ {}", e);
+                return Outcome::InputError;
+            }
+        }
+    } else if !mode.is_quiet() {
+        ocr::print_report(&report);
+    }
+
+    let game = match board.to_game() {
+        Ok(game) => game,
+        Err(e) => {
+            status!(mode, "❌ {}", e);
+            return Outcome::InputError;
+        }
+    };
+    if let Some(path) = output {
+        match std::fs::write(path, board::to_pysol(&game)) {
+            Ok(()) => status!(mode, "{}", Msg::PysolSaved(path)),
+            Err(e) => status!(mode, "{}", Msg::PysolFailed(&e)),
+        }
+    }
+    if let Some(args) = solve {
+        return solve_loaded(game, None, rand::random(), args, config, mode, style);
+    }
+    if mode == OutputMode::Text {
+        println!("{}", style.board(&game));
+    }
+    Outcome::Solved
+}
+
+// Reads the fixtures and compares each board with the expected one. A fixture passes
+// when every cell matches.
+#[cfg(feature = "vision")]
+fn run_ocr_check(folder: &str, config: &Config, mode: OutputMode) -> Outcome {
+    let fixtures = match freecell::fixtures::load_fixtures(folder) {
+        Ok(fixtures) => fixtures,
+        Err(e) => {
+            status!(mode, "❌ {}", e);
+            return Outcome::InputError;
+        }
+    };
+    let mut results = Vec::new();
+    for fixture in &fixtures {
+        let settings = fixture.settings(&config.ocr);
+        let capture = ocr::Capture::File(&settings.capture);
+        let themed = themed_settings(capture, &settings, mode);
+        let diffs = match themed.map(|themed| ocr::read_board(capture, &themed)) {
+            Some(Ok(board)) => Some(freecell::fixtures::diff_board(&fixture.expected, &board)),
+            Some(Err(e)) => {
+                status!(mode, "{}", Msg::FixtureFailed(&fixture.name, &e));
+                None
+            }
+            None => None,
+        };
+        match &diffs {
+            Some(diffs) if diffs.is_empty() => status!(mode, "{}", Msg::FixtureOk(&fixture.name)),
+            Some(diffs) => {
+                status!(mode, "{}", Msg::FixtureDiffers(&fixture.name, diffs.len()));
+                for diff in diffs {
+                    status!(mode, "   {}", diff);
+                }
+            }
+            None => {}
+        }
+        results.push((fixture, diffs));
+    }
+
+    let passed = results
+        .iter()
+        .filter(|(_, diffs)| diffs.as_ref().is_some_and(Vec::is_empty))
+        .count();
+    if mode == OutputMode::Json {
+        let json: Vec<_> = results
+            .iter()
+            .map(|(fixture, diffs)| {
+                serde_json::json!({
+                    "name": fixture.name,
+                    "read": diffs.is_some(),
+                    "diffs": diffs.iter().flatten().map(ToString::to_string).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    }
+    status!(mode, "{}", Msg::FixturesPassed(passed, fixtures.len()));
+    match passed == fixtures.len() {
+        true => Outcome::Solved,
+        false => Outcome::InputError,
+    }
+}
 
-    // let deck = if dotenv::var("USE_RANDOM").unwrap_or("0".to_string()) == "1" {
+// A capture of the game area, whose region is saved into the config file at `path`
+// when it was clicked
+#[cfg(all(feature = "vision", feature = "automation"))]
+fn capture_screen(
+    path: Option<&str>,
+    config: &Config,
+    mode: OutputMode,
+) -> Option<screen::Screenshot> {
+    let shot = match screen::start_screenshot(&config.screen, &config.automation) {
+        Ok(shot) => shot,
+        Err(e) => {
+            status!(mode, "❌ {}", Msg::from(&e));
+            return None;
+        }
+    };
+    if shot.clicked {
+        let region = [shot.x1, shot.y1, shot.x2, shot.y2];
+        match freecell::config::save_screen_region(path, region, shot.area.index) {
+            Ok(path) => status!(mode, "{}", Msg::RegionSaved(&path)),
+            Err(e) => status!(mode, "{}", Msg::RegionNotSaved(&e)),
+        }
+    }
+    Some(shot)
+}
+
+// The recovery path when a move went off-script: the position on screen, freecells and
+// foundations included, is read again and solved from scratch
+#[cfg(all(feature = "vision", feature = "automation"))]
+fn run_rescan(
+    fix: bool,
+    path: Option<&str>,
+    args: &SolveArgs,
+    config: &Config,
+    mode: OutputMode,
+    style: BoardStyle,
+) -> Outcome {
+    let Some(shot) = capture_screen(path, config, mode) else {
+        return Outcome::InputError;
+    };
+    let capture = ocr::Capture::Image(&shot.img);
+    run_ocr(
+        fix,
+        None,
+        capture,
+        &config.ocr,
+        Some(args),
+        config,
+        mode,
+        style,
+    )
+}
+
+// Keeps a capture and what was read on it in a new folder of `[screen] archive`, if
+// set: `capture.png`, then `read.board` and `read.json` or `error.txt`
+#[cfg(all(feature = "vision", feature = "automation"))]
+fn archive_read(
+    capture: &image::RgbaImage,
+    read: Result<&freecell::layout::BoardDetections, &freecell::error::SolverError>,
+    config: &Config,
+    mode: OutputMode,
+) {
+    let Some(root) = &config.screen.archive else {
+        return;
+    };
+    let archived = freecell::fixtures::archive_folder(Path::new(root), config.screen.archive_max)
+        .and_then(|folder| {
+            let write = |file: &str, text: String| {
+                std::fs::write(folder.join(file), text).map_err(|e| e.to_string())
+            };
+            capture
+                .save(folder.join("capture.png"))
+                .map_err(|e| e.to_string())?;
+            match read {
+                Ok(board) => {
+                    let report =
+                        freecell::layout::DeckReport::new(board, config.ocr.warn_confidence);
+                    write(
+                        "read.json",
+                        serde_json::to_string_pretty(&report.to_json()).unwrap(),
+                    )?;
+                    match board.to_game() {
+                        Ok(game) => write("read.board", board::to_pysol(&game)),
+                        Err(e) => write("error.txt", e.to_string()),
+                    }
+                }
+                Err(e) => write("error.txt", e.to_string()),
+            }
+        });
+    if let Err(e) = archived {
+        status!(mode, "{}", Msg::ArchiveFailed(&e));
+    }
+}
+
+// A solution of `game` found with the limits of the config, without printing anything
+#[cfg(all(feature = "vision", feature = "automation"))]
+fn quiet_solution(
+    game: &Game,
+    args: &SolveArgs,
+    config: &Config,
+) -> Option<Vec<freecell::action::Action>> {
+    let mut solver = Solver::new(game.clone());
+    solver.strategy = resolve_strategy(args.strategy, config);
+    solver.weights = config.heuristic;
+    solver.time_limit = config.solver.time_limit.map(Duration::from_secs_f64);
+    solver.verbose = false;
+    Some(remove_wasted_moves(
+        game,
+        &solver.solve(config.solver.max_nodes)?,
+    ))
+}
+
+// Hands-free play: each capture is read, and the next move suggested whenever the
+// position changed since the last one read. With `deals`, only the deals nothing was
+// played on yet are solved, in full. Boards that cannot be read, say in the middle of
+// a drag, are skipped. Runs until interrupted.
+#[cfg(all(feature = "vision", feature = "automation"))]
+fn run_watch(
+    interval: f64,
+    deals: bool,
+    path: Option<&str>,
+    args: &SolveArgs,
+    config: &Config,
+    mode: OutputMode,
+    style: BoardStyle,
+) -> Outcome {
+    let Some(first) = capture_screen(path, config, mode) else {
+        return Outcome::InputError;
+    };
+    let mut watch = match screen::RegionWatch::new(&first, &config.screen, &config.automation) {
+        Ok(watch) => watch,
+        Err(e) => {
+            status!(mode, "❌ {}", Msg::from(&e));
+            return Outcome::InputError;
+        }
+    };
+    let Some(settings) = themed_settings(ocr::Capture::Image(&first.img), &config.ocr, mode) else {
+        return Outcome::InputError;
+    };
+    status!(mode, "{}", Msg::WatchStarted(interval));
+
+    let mut cache = ocr::BoardCache::default();
+    let mut last = None;
+    loop {
+        let capture = match watch.capture() {
+            Ok(shot) => shot.img,
+            Err(e) => {
+                status!(mode, "❌ {}", Msg::from(&e));
+                return Outcome::InputError;
+            }
+        };
+        let read = cache.read(&capture, &settings);
+        archive_read(&capture, read.as_ref(), config, mode);
+        let game = read.and_then(|board| board.to_game());
+        if let Ok(game) = game
+            && last != Some(game.layout_key())
+            && (!deals || game.is_new_deal())
+        {
+            last = Some(game.layout_key());
+            if game.is_new_deal() {
+                status!(mode, "{}", Msg::NewDealFound);
+            }
+            if deals {
+                solve_loaded(game, None, rand::random(), args, config, mode, style);
+            } else if !game.is_won() {
+                if mode == OutputMode::Text {
+                    println!("{}", style.board(&game));
+                }
+                let solution = quiet_solution(&game, args, config);
+                match (mode, solution.as_deref()) {
+                    (OutputMode::Json, solution) => {
+                        println!("{}", output::next_move_json(&game, solution))
+                    }
+                    (OutputMode::Text, Some([action, ..])) => println!(
+                        "{}",
+                        Msg::NextMove(
+                            &freecell::notation::to_freecell_pro(action),
+                            &freecell::explain::explain(&game, action),
+                            solution.as_ref().map_or(0, Vec::len)
+                        )
+                    ),
+                    (OutputMode::Text, _) => status!(mode, "{}", Msg::NoSolution),
+                    (OutputMode::Quiet, _) => {}
+                }
+            }
+        }
+        std::thread::sleep(Duration::from_secs_f64(interval));
+    }
+}
+
+// Plays the game on screen: each move of the solution is dragged there with the
+// mouse, then the board is read again. When it does not show the position expected,
+// after a missed drop or cards the client sent home on its own, the solver starts
+// again from what it shows.
+#[cfg(all(feature = "vision", feature = "automation"))]
+fn run_autoplay(
+    max_moves: usize,
+    path: Option<&str>,
+    args: &SolveArgs,
+    config: &Config,
+    mode: OutputMode,
+) -> Outcome {
+    let Some(first) = capture_screen(path, config, mode) else {
+        return Outcome::InputError;
+    };
+    let mut watch = match screen::RegionWatch::new(&first, &config.screen, &config.automation) {
+        Ok(watch) => watch,
+        Err(e) => {
+            status!(mode, "❌ {}", Msg::from(&e));
+            return Outcome::InputError;
+        }
+    };
+    let Some(settings) = themed_settings(ocr::Capture::Image(&first.img), &config.ocr, mode) else {
+        return Outcome::InputError;
+    };
+    // Where the empty freecells, foundations and columns are
+    let geometry = config
+        .ocr
+        .layout
+        .and_then(|preset| preset.geometry())
+        .or_else(|| Some(freecell::detect::detect_board(&first.img).ok()?.geometry))
+        .or(freecell::layout::LayoutPreset::Microsoft.geometry());
+    let Some(geometry) = geometry else {
+        return Outcome::InputError;
+    };
+
+    let mut shot = first;
+    let mut cache = ocr::BoardCache::default();
+    let mut plan = Vec::new();
+    let mut expected = None;
+    for played in 0..max_moves {
+        let read = cache
+            .read(&shot.img, &settings)
+            .and_then(|board| Ok((board.to_game()?, board)));
+        let (game, board) = match read {
+            Ok(read) => read,
+            Err(e) => {
+                status!(mode, "❌ {}", e);
+                return Outcome::InputError;
+            }
+        };
+        if game.is_won() {
+            status!(mode, "{}", Msg::Won(played));
+            return Outcome::Solved;
+        }
+        if expected != Some(game.layout_key()) {
+            if expected.is_some() {
+                status!(mode, "{}", Msg::AutoplayOffScript);
+            }
+            match quiet_solution(&game, args, config) {
+                Some(solution) => plan = solution.into_iter().rev().collect(),
+                None => {
+                    status!(mode, "{}", Msg::NoSolution);
+                    return Outcome::Unsolvable;
+                }
+            }
+        }
+        let Some(action) = plan.pop() else {
+            return Outcome::Unsolvable;
+        };
+
+        let notation = freecell::notation::to_freecell_pro(&action);
+        status!(mode, "{}", Msg::AutoplayMove(played + 1, &notation));
+        let (width, height) = (shot.img.width() as i32, shot.img.height() as i32);
+        let (press, release) = board.move_points(&action, &geometry, width, height);
+        // Through the region of the last capture, the window may have moved since the first
+        let dragged = screen::drag(
+            shot.to_desktop(press.0, press.1),
+            shot.to_desktop(release.0, release.1),
+        );
+        if let Err(e) = dragged {
+            status!(mode, "❌ {}", Msg::from(&e));
+            return Outcome::InputError;
+        }
+        let mut next = game;
+        expected = next.apply(&action).ok().map(|_| next.layout_key());
+
+        std::thread::sleep(Duration::from_millis(config.automation.move_delay_ms));
+        shot = match watch.capture() {
+            Ok(shot) => shot,
+            Err(e) => {
+                status!(mode, "❌ {}", Msg::from(&e));
+                return Outcome::InputError;
+            }
+        };
+    }
+    status!(mode, "{}", Msg::AutoplayStopped(max_moves));
+    Outcome::LimitReached
+}
+
+// Resident mode: each press of the hotkey captures the game area, chosen on the first
+// press, reads it and solves it. Runs until interrupted.
+#[cfg(all(feature = "vision", feature = "automation"))]
+fn run_hotkey(
+    keys: &str,
+    path: Option<&str>,
+    args: &SolveArgs,
+    config: &Config,
+    mode: OutputMode,
+    style: BoardStyle,
+) -> Outcome {
+    let hotkey: screen::Hotkey = match keys.parse() {
+        Ok(hotkey) => hotkey,
+        Err(e) => {
+            status!(mode, "{}", Msg::HotkeyInvalid(&e));
+            return Outcome::InputError;
+        }
+    };
+    status!(mode, "{}", Msg::HotkeyWaiting(keys));
+
+    // The area to capture, the settings of its theme and the last board read, from the
+    // first press on
+    let mut session: Option<(screen::RegionWatch, OcrSettings, ocr::BoardCache)> = None;
+    let error = screen::on_hotkey(hotkey, || {
+        let captured = match &mut session {
+            Some((watch, ..)) => watch.capture().map(|shot| shot.img),
+            None => {
+                let Some(first) = capture_screen(path, config, mode) else {
+                    return;
+                };
+                let capture = ocr::Capture::Image(&first.img);
+                let Some(settings) = themed_settings(capture, &config.ocr, mode) else {
+                    return;
+                };
+                screen::RegionWatch::new(&first, &config.screen, &config.automation).map(
+                    |started| {
+                        session = Some((started, settings, ocr::BoardCache::default()));
+                        first.img
+                    },
+                )
+            }
+        };
+        let capture = match captured {
+            Ok(img) => img,
+            Err(e) => {
+                status!(mode, "❌ {}", Msg::from(&e));
+                return;
+            }
+        };
+        let Some((_, settings, cache)) = &mut session else {
+            return;
+        };
+        let read = cache.read(&capture, settings);
+        archive_read(&capture, read.as_ref(), config, mode);
+        match read.and_then(|board| board.to_game()) {
+            Ok(game) => {
+                solve_loaded(game, None, rand::random(), args, config, mode, style);
+            }
+            Err(e) => status!(mode, "❌ {}", e),
+        }
+    });
+    status!(mode, "❌ {}", Msg::from(&error));
+    Outcome::InputError
+}
+
+#[cfg(feature = "vision")]
+fn run_templates_label(config: &Config, mode: OutputMode) -> Outcome {
+    let capture = ocr::Capture::File(&config.ocr.capture);
+    let Some(settings) = themed_settings(capture, &config.ocr, mode) else {
+        return Outcome::InputError;
+    };
+    match ocr::label_templates(&settings) {
+        Ok(count) => {
+            status!(
+                mode,
+                "{}",
+                Msg::TemplatesWritten(count, &settings.templates)
+            );
+            Outcome::Solved
+        }
+        Err(e) => {
+            status!(mode, "❌ {}", e);
+            Outcome::InputError
+        }
+    }
+}
+
+fn run_templates_extract(
+    capture: &str,
+    deal: u32,
+    out: Option<&str>,
+    config: &Config,
+    mode: OutputMode,
+) -> Outcome {
+    let out = match (out, &config.ocr.theme) {
+        (Some(out), _) => out.to_string(),
+        (None, Some(theme)) => config.ocr.with_theme(theme).templates,
+        (None, None) => config.ocr.templates.clone(),
+    };
+    let game = Game::new(&deal::ms_deal(deal));
+    match freecell::extract::extract_templates(capture, &game, &out) {
+        Ok(count) => {
+            status!(mode, "{}", Msg::TemplatesWritten(count, &out));
+            Outcome::Solved
+        }
+        Err(e) => {
+            status!(mode, "❌ {}", e);
+            Outcome::InputError
+        }
+    }
+}
+
+fn run_play(
+    deal: Option<u32>,
+    seed: Option<u64>,
+    history: Option<&str>,
+    config: &Config,
+    mode: OutputMode,
+    style: BoardStyle,
+) -> Outcome {
+    let seed = seed.unwrap_or_else(rand::random);
+    let game = match deal {
+        Some(number) => Game::new(&deal::ms_deal(number)),
+        None => Game::new(&deal::random_deal(seed)),
+    };
+    let options = batch_options(config, resolve_strategy(None, config), None, None);
+
+    let mut log = match play::run_play(game, options, style) {
+        Ok(log) => log,
+        Err(e) => {
+            status!(mode, "{}", Msg::TerminalUnusable(&e));
+            return Outcome::InputError;
+        }
+    };
+    if let Some(path) = history {
+        log.deal_number = deal;
+        log.seed = deal.is_none().then_some(seed);
+        match log.save(path) {
+            Ok(()) => status!(mode, "{}", Msg::HistorySaved(path)),
+            Err(e) => status!(mode, "{}", Msg::HistoryFailed(&e)),
+        }
+    }
+    if deal.is_none() {
+        status!(mode, "{}", Msg::RandomDeal(seed));
+    }
+    Outcome::Solved
+}
+
+fn run_compare(args: &CompareArgs, config: &Config, mode: OutputMode) -> Outcome {
+    let text = if args.deal.is_empty() {
+        match read_input(args.input.as_deref().unwrap_or("-")) {
+            Ok(text) => text,
+            Err(e) => {
+                status!(mode, "❌ {}", e);
+                return Outcome::InputError;
+            }
+        }
+    } else {
+        args.deal
+            .iter()
+            .map(|number| format!("{}\n", number))
+            .collect()
+    };
+    let deals = batch::parse_batch(&text);
+    let options = batch_options(config, Strategy::default(), args.max_nodes, args.time_limit);
+    let races = compare::race(&deals, &args.strategies, options);
+
+    match mode {
+        OutputMode::Text => println!("{}", compare::format_table(&races, &args.strategies)),
+        OutputMode::Json => {
+            let json: Vec<_> = races.iter().map(compare::Race::to_json).collect();
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        }
+        OutputMode::Quiet => {}
+    }
+    Outcome::Solved
+}
+
+fn run_crosscheck(args: &CrosscheckArgs, config: &Config, mode: OutputMode) -> Outcome {
+    let text = if args.deal.is_empty() {
+        match read_input(args.input.as_deref().unwrap_or("-")) {
+            Ok(text) => text,
+            Err(e) => {
+                status!(mode, "❌ {}", e);
+                return Outcome::InputError;
+            }
+        }
+    } else {
+        args.deal
+            .iter()
+            .map(|number| format!("{}\n", number))
+            .collect()
+    };
+    let deals = batch::parse_batch(&text);
+    let options = batch_options(
+        config,
+        resolve_strategy(None, config),
+        args.max_nodes,
+        args.time_limit,
+    );
+    let fc_solve = crosscheck::fc_solve_available(&args.fc_solve).then_some(args.fc_solve.as_str());
+    if fc_solve.is_none() {
+        status!(mode, "{}", Msg::FcSolveMissing(&args.fc_solve));
+    }
+
+    let checks: Vec<_> = deals
+        .iter()
+        .map(|deal| crosscheck::crosscheck(deal, options, fc_solve))
+        .collect();
+    let agreed = checks.iter().filter(|check| check.agrees()).count();
+    match mode {
+        OutputMode::Text => {
+            for check in &checks {
+                println!("{}", crosscheck::format_line(check));
+            }
+        }
+        OutputMode::Json => {
+            let json: Vec<_> = checks.iter().map(crosscheck::Crosscheck::to_json).collect();
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        }
+        OutputMode::Quiet => {}
+    }
+    status!(mode, "{}", Msg::CrosscheckSummary(agreed, checks.len()));
+
+    // A disagreement fails the run, like an unsolved deal
+    match agreed == checks.len() {
+        true => Outcome::Solved,
+        false => Outcome::Unsolvable,
+    }
+}
+
+fn run_rate(args: &RateArgs, config: &Config, mode: OutputMode) -> Outcome {
+    let game = Game::new(&deal::ms_deal(args.deal));
+    let options = batch_options(
+        config,
+        resolve_strategy(None, config),
+        args.max_nodes,
+        args.time_limit,
+    );
+    let rating = rate::rate_game(&game, options);
+
+    match mode {
+        OutputMode::Text => {
+            println!("{}", Msg::Rating(args.deal, rating.grade, &rating.label()));
+            for reason in &rating.reasons {
+                println!("  - {}", reason);
+            }
+        }
+        OutputMode::Json => {
+            let mut json = rating.to_json();
+            json["deal"] = serde_json::json!(args.deal);
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        }
+        OutputMode::Quiet => {}
+    }
+    Outcome::Solved
+}
+
+fn run_generate(args: &GenerateArgs, config: &Config, mode: OutputMode) -> Outcome {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let hard = args.min_nodes.is_some() || args.min_moves.is_some() || args.min_estimate.is_some();
+    let filter = (args.solvable || args.solutions || hard).then(|| DealFilter {
+        min_nodes: args.min_nodes,
+        min_moves: args.min_moves,
+        min_log10_nodes: args.min_estimate,
+        ..DealFilter::solvable(batch_options(
+            config,
+            resolve_strategy(None, config),
+            Some(args.max_nodes),
+            None,
+        ))
+    });
+
+    let deals = generate::generate_deals(args.count, seed, filter);
+    let text: String = deals
+        .iter()
+        .map(|deal| deal.to_line(args.solutions) + "\n")
+        .collect();
+    if deals.len() < args.count {
+        status!(mode, "{}", Msg::DealsShort(deals.len(), args.count));
+    }
+
+    match &args.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &text) {
+                status!(mode, "{}", Msg::WriteFailed(path, &e));
+                return Outcome::InputError;
+            }
+            status!(mode, "{}", Msg::DealsWritten(deals.len(), path));
+        }
+        None if !mode.is_quiet() => print!("{}", text),
+        None => {}
+    }
+    Outcome::Solved
+}
+
+fn run_solve(args: &SolveArgs, config: &Config, mode: OutputMode, style: BoardStyle) -> Outcome {
+    // let game = if dotenv::var("USE_RANDOM").unwrap_or("0".to_string()) == "1" {
     //     eprintln!("🃏 Génération d'un jeu de cartes aléatoire...");
-    //     generate_random_deck()
+    //     Game::new(&deal::random_deal(seed))
     // } else {
     //     eprintln!("🃏 Génération d'un jeu de cartes basé sur un screenshot...");
-    //     let _screenshot = screen::start_screenshot();
-    //     let cards = ocr::run_ocr();
-    //     cards.iter().map(|p| p.card).collect::<Vec<_>>()
+    //     let _screenshot = screen::start_screenshot(&config.screen, &config.automation);
+    //     ocr::read_board(&config.ocr).and_then(|board| board.to_game())
     // };
 
-    let deck = generate_random_deck();
+    // Also seeds the estimate, so that --auto picks the same limits again
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let resume = match &args.resume {
+        Some(path) => match SearchSnapshot::load(Path::new(path)) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                status!(mode, "{}", Msg::ResumeFailed(&e));
+                return Outcome::InputError;
+            }
+        },
+        None => None,
+    };
+    if args.deal.is_none() && args.cards.is_none() && args.board.is_none() && resume.is_none() {
+        status!(mode, "{}", Msg::Seed(seed));
+    }
 
-    let game = Game::new(&deck);
-    println!("{:?}", game);
+    let loaded = match &resume {
+        Some(snapshot) => Ok(snapshot.game.clone()),
+        None => load_game(args, seed),
+    };
+    let game = match loaded {
+        Ok(game) => game,
+        Err(e) => {
+            status!(mode, "❌ {}", e);
+            return Outcome::InputError;
+        }
+    };
+    solve_loaded(game, resume, seed, args, config, mode, style)
+}
+
+// Solves and reports a deal however it was read, `resume` picks up a saved search
+fn solve_loaded(
+    game: Game,
+    resume: Option<SearchSnapshot>,
+    seed: u64,
+    args: &SolveArgs,
+    config: &Config,
+    mode: OutputMode,
+    style: BoardStyle,
+) -> Outcome {
+    let format = match mode {
+        OutputMode::Json => SolutionFormat::Json,
+        _ => args.output_format,
+    };
+    // Keep stdout clean for machine-readable output
+    let verbose = mode == OutputMode::Text && format != SolutionFormat::Json;
+    if verbose {
+        println!("{}", style.board(&game));
+    }
+    if let Some(path) = &args.png {
+        match render::save_png(&game, path) {
+            Ok(()) => status!(mode, "{}", Msg::PngSaved(path)),
+            Err(e) => status!(mode, "{}", Msg::PngFailed(&e)),
+        }
+    }
+
+    if let Some(path) = &args.pysol {
+        match std::fs::write(path, board::to_pysol(&game)) {
+            Ok(()) => status!(mode, "{}", Msg::PysolSaved(path)),
+            Err(e) => status!(mode, "{}", Msg::PysolFailed(&e)),
+        }
+    }
+
+    let mut strategy = resolve_strategy(args.strategy, config);
+    let mut max_nodes = config.solver.max_nodes;
+
+    if args.estimate || args.auto {
+        let estimate = estimate_tree_size(
+            &game,
+            args.probes,
+            500,
+            &mut ChaCha8Rng::seed_from_u64(seed),
+        );
+        status!(
+            mode,
+            "{}",
+            Msg::Estimate(
+                estimate.log10_nodes,
+                estimate.branching_factor,
+                estimate.mean_depth,
+                estimate.won_probes,
+                estimate.probes
+            )
+        );
+        if args.auto {
+            if args.strategy.is_none() {
+                strategy = estimate.suggested_strategy();
+            }
+            max_nodes = estimate.suggested_node_limit();
+            status!(mode, "{}", Msg::AutoLimits(&strategy, max_nodes));
+        }
+    }
 
     let now = Instant::now();
 
-    let solver = Solver::new(game);
-    let actions = solver.solve(1000000);
+    let mut solver = match resume {
+        Some(snapshot) => {
+            status!(mode, "{}", Msg::Resuming(snapshot.stats.nodes_explored));
+            Solver::from_snapshot(snapshot)
+        }
+        None => {
+            let mut solver = Solver::new(game.clone());
+            solver.strategy = strategy;
+            solver.weights = config.heuristic;
+            solver
+        }
+    };
+    solver.time_limit = config.solver.time_limit.map(Duration::from_secs_f64);
+    solver.verbose = verbose;
+    solver.profile = args.profile;
+    if let Some(path) = args.checkpoint.as_ref().or(args.resume.as_ref()) {
+        match solver.strategy {
+            Strategy::Dfs => status!(mode, "{}", Msg::CheckpointAStarOnly),
+            _ => solver.checkpoint = Some((PathBuf::from(path), args.checkpoint_every)),
+        }
+    }
+    let actions = if args.prove {
+        solver.solve_exhaustive()
+    } else {
+        solver.solve(max_nodes)
+    };
     let elapsed = now.elapsed();
-    println!("Elapsed: {:.2?}", elapsed);
+    if verbose {
+        println!("{}", Msg::Elapsed(elapsed));
+    }
 
-    if let Some(solution) = actions {
-        eprintln!("✅ Solution trouvée en {} mouvements:", solution.len());
-        for action in solution {
-            eprintln!("  - {:?}", action);
+    let steps = actions.map(|mut solution| {
+        if !args.no_optimize {
+            let optimized = remove_wasted_moves(&game, &solution);
+            if optimized.len() < solution.len() {
+                status!(mode, "{}", Msg::Simplified(solution.len(), optimized.len()));
+                solution = optimized;
+            }
         }
-    } else {
-        eprintln!("❌ Aucune solution trouvée dans la limite de mouvements.");
+        merge_supermoves(&game, &solution)
+    });
+
+    match &steps {
+        Some(steps) => status!(mode, "{}", Msg::SolutionFound(steps.len())),
+        None if args.prove && !solver.limit_reached => {
+            status!(mode, "{}", Msg::Unsolvable(solver.stats.nodes_explored + 1))
+        }
+        None => status!(mode, "{}", Msg::NoSolution),
+    }
+    if !mode.is_quiet() {
+        print_solution(
+            &game,
+            steps.as_deref(),
+            &solver.stats,
+            OutputOptions {
+                format,
+                expanded: args.expanded,
+                snapshot_every: args.snapshot_every,
+                style,
+            },
+        );
+    }
+
+    if let Some(path) = &args.svg {
+        let document = match &steps {
+            Some(steps) => {
+                let actions: Vec<_> = steps.iter().map(|s| s.action).collect();
+                svg::solution_to_svg(&game, &actions)
+            }
+            None => svg::game_to_svg(&game),
+        };
+        match std::fs::write(path, document) {
+            Ok(()) => status!(mode, "{}", Msg::SvgSaved(path)),
+            Err(e) => status!(mode, "{}", Msg::SvgFailed(&e)),
+        }
+    }
+
+    if (args.stats || args.profile) && verbose {
+        println!("{}", solver.stats);
+    }
+
+    match &steps {
+        Some(_) => Outcome::Solved,
+        None if solver.limit_reached => Outcome::LimitReached,
+        None => Outcome::Unsolvable,
     }
 }
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+use freecell::stats::SearchStats;
+
+// Solve durations in seconds, from a quick deal to one that hits a minute long limit
+const DURATION_BUCKETS: [f64; 10] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 60.0];
+const SPEED_BUCKETS: [f64; 8] = [1e3, 1e4, 5e4, 1e5, 2.5e5, 5e5, 1e6, 5e6];
+
+// Counters of the serve mode, read by Prometheus on GET /metrics
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    solves: IntCounterVec,
+    solve_seconds: Histogram,
+    nodes_per_second: Histogram,
+    nodes: IntCounter,
+    expansions: IntCounterVec,
+    duplicate_hits: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("freecell".to_string()), None)?;
+        let metrics = Metrics {
+            requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by route and status"),
+                &["route", "status"],
+            )?,
+            solves: IntCounterVec::new(
+                Opts::new(
+                    "solves_total",
+                    "Searches by outcome: solved, unsolvable or limit",
+                ),
+                &["outcome"],
+            )?,
+            solve_seconds: Histogram::with_opts(
+                HistogramOpts::new("solve_duration_seconds", "Wall-clock time of a search")
+                    .buckets(DURATION_BUCKETS.to_vec()),
+            )?,
+            nodes_per_second: Histogram::with_opts(
+                HistogramOpts::new("solve_nodes_per_second", "Search speed of each request")
+                    .buckets(SPEED_BUCKETS.to_vec()),
+            )?,
+            nodes: IntCounter::new("nodes_explored_total", "Nodes explored by all searches")?,
+            expansions: IntCounterVec::new(
+                Opts::new("expansions_total", "New states reached, by move type"),
+                &["action_type"],
+            )?,
+            duplicate_hits: IntCounter::new(
+                "duplicate_hits_total",
+                "States reached again and skipped",
+            )?,
+            registry,
+        };
+
+        let collectors: [Box<dyn Collector>; 7] = [
+            Box::new(metrics.requests.clone()),
+            Box::new(metrics.solves.clone()),
+            Box::new(metrics.solve_seconds.clone()),
+            Box::new(metrics.nodes_per_second.clone()),
+            Box::new(metrics.nodes.clone()),
+            Box::new(metrics.expansions.clone()),
+            Box::new(metrics.duplicate_hits.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector)?;
+        }
+        // Memory, CPU time and open files of the server process
+        #[cfg(target_os = "linux")]
+        metrics.registry.register(Box::new(
+            prometheus::process_collector::ProcessCollector::for_self(),
+        ))?;
+        Ok(metrics)
+    }
+
+    pub fn observe_request(&self, route: &str, status: u16) {
+        self.requests
+            .with_label_values(&[route, &status.to_string()])
+            .inc();
+    }
+
+    pub fn observe_search(&self, stats: &SearchStats, elapsed: Duration, outcome: &str) {
+        self.solves.with_label_values(&[outcome]).inc();
+        self.solve_seconds.observe(elapsed.as_secs_f64());
+        if elapsed > Duration::ZERO {
+            self.nodes_per_second
+                .observe(stats.nodes_explored as f64 / elapsed.as_secs_f64());
+        }
+        self.nodes.inc_by(stats.nodes_explored);
+        for (action_type, count) in stats.expansions_by_type() {
+            self.expansions
+                .with_label_values(&[&format!("{:?}", action_type)])
+                .inc_by(count);
+        }
+        self.duplicate_hits.inc_by(stats.duplicate_hits);
+    }
+
+    // Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = Vec::new();
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut out);
+        String::from_utf8(out).unwrap_or_default()
+    }
+}
@@ -0,0 +1,155 @@
+use crate::action::{Action, Location};
+use crate::game::Game;
+
+const FREECELL_LETTERS: [char; 4] = ['a', 'b', 'c', 'd'];
+
+// fc-solve / Freecell Pro notation: columns 1-8, freecells a-d, h for home (foundations)
+fn location_code(location: Location) -> char {
+    match location {
+        Location::Column(i) => char::from_digit(i as u32 + 1, 10).unwrap_or('?'),
+        Location::Freecell(i) => FREECELL_LETTERS.get(i).copied().unwrap_or('?'),
+        Location::Foundation(_) => 'h',
+    }
+}
+
+pub fn to_standard(action: &Action) -> String {
+    format!(
+        "{}{}",
+        location_code(action.source_location()),
+        location_code(action.dest_location())
+    )
+}
+
+pub fn solution_to_standard(actions: &[Action]) -> String {
+    actions
+        .iter()
+        .map(to_standard)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Freecell Pro standard solution notation: same codes, but a supermove carries the
+// number of cards it moves ("72v3") so that it can be replayed as a single move
+pub fn to_freecell_pro(action: &Action) -> String {
+    if action.pile_size > 1 {
+        format!("{}v{}", to_standard(action), action.pile_size)
+    } else {
+        to_standard(action)
+    }
+}
+
+pub fn solution_to_freecell_pro(actions: &[Action]) -> String {
+    actions
+        .iter()
+        .map(to_freecell_pro)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_location(code: char) -> Option<Location> {
+    match code {
+        '1'..='8' => Some(Location::Column(code as usize - '1' as usize)),
+        'a'..='d' => Some(Location::Freecell(code as usize - 'a' as usize)),
+        'h' => Some(Location::Foundation(0)),
+        _ => None,
+    }
+}
+
+// Reads a move in either notation, as played from `game`: "h" goes to the foundation of
+// the moved card, and a column to column move without "vN" takes the longest sequence
+// that fits. None if the code is unknown or the move is illegal.
+pub fn parse_move(game: &Game, code: &str) -> Option<Action> {
+    let (code, pile) = match code.split_once('v') {
+        Some((code, pile)) => (code, Some(pile.parse::<usize>().ok()?)),
+        None => (code, None),
+    };
+    let mut chars = code.chars();
+    let (Some(source), Some(dest), None) = (chars.next(), chars.next(), chars.next()) else {
+        return None;
+    };
+    let source = parse_location(source)?;
+    let dest = match parse_location(dest)? {
+        Location::Foundation(_) => {
+            let card = match source {
+                Location::Column(i) => game.columns.get(i)?.last(),
+                Location::Freecell(i) => game.freecells.get(i)?.as_ref(),
+                Location::Foundation(_) => None,
+            }?;
+            Location::Foundation(card.suit as usize)
+        }
+        dest => dest,
+    };
+    let longest = match (source, pile) {
+        (_, Some(pile)) => pile,
+        (Location::Column(i), None) => game.columns.get(i)?.len(),
+        _ => 1,
+    };
+    let shortest = pile.unwrap_or(1);
+    (shortest..=longest)
+        .rev()
+        .filter_map(|pile_size| Action::between(source, dest, pile_size))
+        .find(|action| game.check_move(action).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::card::Card;
+
+    #[test]
+    fn test_to_standard() {
+        let moves = [
+            Action::between(Location::Column(2), Location::Freecell(0), 1).unwrap(),
+            Action::between(Location::Column(6), Location::Column(1), 3).unwrap(),
+            Action::between(Location::Column(7), Location::Foundation(2), 1).unwrap(),
+            Action::between(Location::Freecell(3), Location::Column(0), 1).unwrap(),
+        ];
+
+        assert_eq!(solution_to_standard(&moves), "3a 72 8h d1");
+        assert_eq!(solution_to_freecell_pro(&moves), "3a 72v3 8h d1");
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let mut game = Game::new(&crate::deal::ms_deal(1));
+        for action in crate::solver::Solver::new(game.clone())
+            .solve(100_000)
+            .unwrap()
+        {
+            assert_eq!(parse_move(&game, &to_freecell_pro(&action)), Some(action));
+            game.apply(&action).unwrap();
+        }
+        assert!(game.is_won());
+        assert_eq!(parse_move(&game, "1h"), None);
+        assert_eq!(parse_move(&game, "x1"), None);
+    }
+
+    #[test]
+    fn test_freecell_pro_round_trip() {
+        let card = |code: &str| Card::try_from(code).unwrap();
+        let mut game = Game::new(&[]);
+        game.columns[0] = ["9D", "KS", "QH", "JC"].map(card).to_vec();
+        game.columns[1] = vec![card("AH")];
+        game.columns[2] = ["2S", "KC"].map(card).to_vec();
+        game.columns[3] = vec![card("5C")];
+
+        let hearts = Location::Foundation(card("AH").suit as usize);
+        let played = [
+            (Location::Column(3), Location::Freecell(0), 1, "4a"),
+            (Location::Column(1), hearts, 1, "2h"),
+            (Location::Column(0), Location::Column(2), 2, "13v2"),
+            (Location::Freecell(0), Location::Column(3), 1, "a4"),
+        ];
+        for (source, dest, pile_size, code) in played {
+            let action = Action::between(source, dest, pile_size).unwrap();
+            assert_eq!(to_freecell_pro(&action), code);
+            assert_eq!(parse_move(&game, code), Some(action));
+            // Without its count, the longest sequence that fits
+            assert_eq!(parse_move(&game, &to_standard(&action)), Some(action));
+            game.apply(&action).unwrap();
+        }
+        assert_eq!(game.columns[2].len(), 4);
+        assert_eq!(parse_move(&game, "13v2"), None);
+    }
+}
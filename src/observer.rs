@@ -0,0 +1,81 @@
+use crate::action::Action;
+use crate::game::Game;
+
+// Called by the solver at key points of a search, for visualizers, loggers or
+// training-data collectors. Every method does nothing by default, so that the
+// `NullObserver` of a plain `Solver` compiles away.
+pub trait SearchObserver {
+    // A new position, reached through `action` at `depth` moves from the deal: queued
+    // by A*, entered by the DFS
+    fn on_expand(&mut self, _state: &Game, _action: &Action, _depth: usize) {}
+
+    // `action` led to a position already seen, which is skipped
+    fn on_prune(&mut self, _state: &Game, _action: &Action) {}
+
+    fn on_solution(&mut self, _moves: &[Action]) {}
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullObserver;
+
+impl SearchObserver for NullObserver {}
+
+// So that the caller keeps the observer: `solver.with_observer(&mut recorder)`
+impl<T: SearchObserver + ?Sized> SearchObserver for &mut T {
+    fn on_expand(&mut self, state: &Game, action: &Action, depth: usize) {
+        (**self).on_expand(state, action, depth)
+    }
+
+    fn on_prune(&mut self, state: &Game, action: &Action) {
+        (**self).on_prune(state, action)
+    }
+
+    fn on_solution(&mut self, moves: &[Action]) {
+        (**self).on_solution(moves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+    use crate::solver::{Solver, Strategy};
+
+    #[derive(Default)]
+    struct Counter {
+        expanded: u64,
+        pruned: u64,
+        solutions: Vec<Vec<Action>>,
+    }
+
+    impl SearchObserver for Counter {
+        fn on_expand(&mut self, _state: &Game, _action: &Action, _depth: usize) {
+            self.expanded += 1;
+        }
+
+        fn on_prune(&mut self, _state: &Game, _action: &Action) {
+            self.pruned += 1;
+        }
+
+        fn on_solution(&mut self, moves: &[Action]) {
+            self.solutions.push(moves.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_observer_sees_the_search() {
+        for strategy in Strategy::ALL {
+            let mut counter = Counter::default();
+            let mut solver = Solver::new(Game::new(&ms_deal(1))).with_observer(&mut counter);
+            solver.verbose = false;
+            solver.strategy = strategy;
+            let solution = solver.solve(100_000);
+            let stats = solver.stats.clone();
+
+            assert_eq!(counter.expanded, stats.total_expansions());
+            assert_eq!(counter.pruned, stats.duplicate_hits);
+            assert_eq!(counter.solutions, vec![solution.unwrap()]);
+        }
+    }
+}
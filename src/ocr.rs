@@ -1,89 +1,1039 @@
-use ::core::panic;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, BufRead, Write};
 
 use glob::glob;
+use image::RgbaImage;
 use opencv::{
-    core::{self, Mat, Point},
-    imgcodecs, imgproc,
+    boxed_ref::BoxedRef,
+    core::{self, Mat, Point, Rect, Scalar, Size, UMat, Vec3b, Vector},
+    highgui, imgcodecs, imgproc,
     prelude::*,
 };
+use rayon::prelude::*;
 
-use crate::card::Card;
+use freecell::card::Card;
+use freecell::classify::{GlyphFeatures, RankClassifier};
+use freecell::config::OcrSettings;
+use freecell::detect::detect_board;
+use freecell::error::{CardError, OcrError, SolverError};
+use freecell::extract::new_template_path;
+use freecell::i18n::Msg;
+use freecell::layout::{
+    BoardDetections, DeckReport, Detection, Geometry, Glyph, MatchMethod, TemplateMatch, fuse_rank,
+    infer_board, non_max_suppression, note_runner_ups, pair_corners, preset_board, set_card,
+    unknown_regions,
+};
 
-#[derive(Debug, Clone)]
-pub struct CardPosition {
-    pub x: i32,
-    pub y: i32,
-    pub width: i32,
-    pub height: i32,
-    pub confidence: f64,
-    pub card: Card,
+// What the OCR reads: an image file, such as `[ocr] capture`, or a screen capture
+// handed over in memory, without the round trip through the disk
+#[derive(Clone, Copy)]
+pub enum Capture<'a> {
+    File(&'a str),
+    Image(&'a RgbaImage),
 }
 
-pub fn run_ocr() -> Vec<CardPosition> {
-    let mut card_positions: Vec<CardPosition> = Vec::new();
+impl<'a> Capture<'a> {
+    // How errors name it
+    fn name(&self) -> &'a str {
+        match *self {
+            Capture::File(path) => path,
+            Capture::Image(_) => "screen capture",
+        }
+    }
 
-    // Load images
-    let img_scene = imgcodecs::imread("capture.png", imgcodecs::IMREAD_COLOR)
-        .expect("Error while loading capture.png");
+    fn load(&self) -> Result<Mat, OcrError> {
+        match self {
+            Capture::File(path) => load_image(path),
+            Capture::Image(image) if image.width() == 0 || image.height() == 0 => {
+                Err(OcrError::EmptyImage(self.name().to_string()))
+            }
+            Capture::Image(image) => rgba_to_mat(image).map_err(matching_error(self.name())),
+        }
+    }
 
-    // Check if images loaded successfully
-    if img_scene.empty() {
-        panic!("Could not load the scene image");
+    fn rgba(&self) -> Result<Cow<'a, RgbaImage>, OcrError> {
+        match *self {
+            Capture::File(path) => image::open(path)
+                .map(|image| Cow::Owned(image.to_rgba8()))
+                .map_err(|e| OcrError::Image {
+                    path: path.to_string(),
+                    reason: e.to_string(),
+                }),
+            Capture::Image(image) => Ok(Cow::Borrowed(image)),
+        }
     }
+}
 
-    for path in glob("templates/*.png")
-        .expect("Failed to read glob pattern")
-        .flatten()
+// The same pixels, in the BGR order of OpenCV
+fn rgba_to_mat(image: &RgbaImage) -> opencv::Result<Mat> {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let bytes = Mat::new_rows_cols_with_data(height, width * 4, image.as_raw())?;
+    let rgba = bytes.reshape(4, height)?;
+    let mut bgr = Mat::default();
+    imgproc::cvt_color_def(&*rgba, &mut bgr, imgproc::COLOR_RGBA2BGR)?;
+    Ok(bgr)
+}
+
+fn load_image(path: &str) -> Result<Mat, OcrError> {
+    let img = imgcodecs::imread(path, imgcodecs::IMREAD_COLOR).map_err(|e| OcrError::Image {
+        path: path.to_string(),
+        reason: e.to_string(),
+    })?;
+    if img.empty() {
+        return Err(OcrError::EmptyImage(path.to_string()));
+    }
+    Ok(img)
+}
+
+// Scores of the template at every location. With `opencl`, the matching runs on the
+// GPU through UMats, and on the CPU again should that fail.
+fn match_scores(scene: &Mat, query: &Mat, opencl: bool) -> opencv::Result<Mat> {
+    if opencl {
+        let mut scores = UMat::new_def();
+        let matched = imgproc::match_template(
+            &scene.get_umat_def(core::AccessFlag::ACCESS_READ)?,
+            &query.get_umat_def(core::AccessFlag::ACCESS_READ)?,
+            &mut scores,
+            imgproc::TM_CCOEFF_NORMED,
+            &core::no_array(),
+        );
+        if matched.is_ok() {
+            return scores.get_mat(core::AccessFlag::ACCESS_READ)?.try_clone();
+        }
+    }
+    let mut result = Mat::default();
+    imgproc::match_template(
+        scene,
+        query,
+        &mut result,
+        imgproc::TM_CCOEFF_NORMED,
+        &Mat::default(),
+    )?;
+    Ok(result)
+}
+
+// Every location where the template scores at least `threshold`: (confidence, location)
+fn peaks(
+    scene: &Mat,
+    query: &Mat,
+    threshold: f64,
+    opencl: bool,
+) -> opencv::Result<Vec<(f64, Point)>> {
+    let result = match_scores(scene, query, opencl)?;
+
+    let mut peaks = Vec::new();
+    for y in 0..result.rows() {
+        for x in 0..result.cols() {
+            let score = *result.at_2d::<f32>(y, x)? as f64;
+            if score >= threshold {
+                peaks.push((score, Point::new(x, y)));
+            }
+        }
+    }
+    Ok(peaks)
+}
+
+// Downscaling blurs the shapes: coarse scores run below full size ones by up to this
+const COARSE_SLACK: f64 = 0.2;
+// Templates smaller than this once downscaled are matched at full size only
+const MIN_COARSE_SIZE: i32 = 8;
+
+fn downscale(image: &Mat, scale: i32) -> opencv::Result<Mat> {
+    let mut small = Mat::default();
+    let size = Size::new(image.cols() / scale, image.rows() / scale);
+    imgproc::resize(image, &mut small, size, 0.0, 0.0, imgproc::INTER_AREA)?;
+    Ok(small)
+}
+
+fn resize(image: &Mat, factor: f64) -> opencv::Result<Mat> {
+    let mut resized = Mat::default();
+    let interpolation = match factor < 1.0 {
+        true => imgproc::INTER_AREA,
+        false => imgproc::INTER_LINEAR,
+    };
+    imgproc::resize(
+        image,
+        &mut resized,
+        Size::default(),
+        factor,
+        factor,
+        interpolation,
+    )?;
+    Ok(resized)
+}
+
+// Templates tried by `calibrate`, the first ones of the folder
+const CALIBRATION_CARDS: usize = 4;
+
+// A scale found by `calibrate`, with the capture size and templates folder it holds for
+type MeasuredScale = Option<((i32, i32), String, f64)>;
+
+// The size of the cards on the capture relative to the templates: the scale, from
+// about half to twice, at which a few templates score best on it. Measured again
+// unless `measured` holds the scale of the same capture size and templates, as the
+// `BoardCache` of `watch` does for the many captures of the same window.
+fn calibrate(
+    scene: &Scene,
+    name: &str,
+    settings: &OcrSettings,
+    measured: &mut MeasuredScale,
+) -> Result<f64, OcrError> {
+    let size = (scene.cols, scene.rows);
+    if let Some((measured_size, templates, scale)) = measured
+        && *measured_size == size
+        && *templates == settings.templates
     {
-        let img_query = imgcodecs::imread(path.to_str().unwrap(), imgcodecs::IMREAD_COLOR)
-            .unwrap_or_else(|_| panic!("Error while loading {:?}", path));
+        return Ok(*scale);
+    }
 
-        if img_query.empty() {
-            panic!("Could not load the query image: {:?}", path);
+    let matching = matching_error(name);
+    let image = scene.mat().map_err(&matching)?;
+    let mut templates = Vec::new();
+    for (name, _, template) in load_templates(&settings.templates)?
+        .into_iter()
+        .take(CALIBRATION_CARDS)
+    {
+        templates.push(scene.prepare(template).map_err(matching_error(&name))?);
+    }
+    let mut best = (f64::MIN, 1.0);
+    for scale in (-7..=7).map(|step| 1.1f64.powi(step)) {
+        let mut total = 0.0;
+        for template in &templates {
+            let resized = resize(template, scale).map_err(&matching)?;
+            if resized.cols() > image.cols() || resized.rows() > image.rows() {
+                total = f64::MIN;
+                break;
+            }
+            let scores = match_scores(&image, &resized, scene.opencl).map_err(&matching)?;
+            let mut score = 0.0;
+            core::min_max_loc(
+                &scores,
+                None,
+                Some(&mut score),
+                None,
+                None,
+                &core::no_array(),
+            )
+            .map_err(&matching)?;
+            total += score;
+        }
+        if total > best.0 {
+            best = (total, scale);
         }
+    }
+    *measured = Some((size, settings.templates.clone(), best.1));
+    Ok(best.1)
+}
 
-        // Perform template matching
-        let mut result = Mat::default();
-        imgproc::match_template(
-            &img_scene,
-            &img_query,
-            &mut result,
-            imgproc::TM_CCOEFF_NORMED,
-            &Mat::default(),
-        )
-        .unwrap_or_else(|_| panic!("Template matching failed for {:?}", path));
-
-        // Find the best match location
-        let mut min_val = 0.0;
-        let mut max_val = 0.0;
-        let mut min_loc = Point::default();
-        let mut max_loc = Point::default();
-
-        core::min_max_loc(
-            &result,
-            Some(&mut min_val),
-            Some(&mut max_val),
-            Some(&mut min_loc),
-            Some(&mut max_loc),
-            &Mat::default(),
-        )
-        .unwrap_or_else(|_| panic!("min_max_loc failed for {:?}", path));
+// Hue-saturation histogram: the colors of a card corner, whatever the shapes
+fn color_histogram(image: &Mat) -> opencv::Result<Mat> {
+    let mut hsv = Mat::default();
+    imgproc::cvt_color_def(image, &mut hsv, imgproc::COLOR_BGR2HSV)?;
+    let mut hist = Mat::default();
+    imgproc::calc_hist_def(
+        &Vector::<Mat>::from_iter([hsv]),
+        &Vector::from_slice(&[0, 1]),
+        &Mat::default(),
+        &mut hist,
+        &Vector::from_slice(&[30, 32]),
+        &Vector::from_slice(&[0.0, 180.0, 0.0, 256.0]),
+    )?;
+    Ok(hist)
+}
+
+// Correlation between the colors of a scene area and a template's, 1 when they match
+fn histogram_similarity(scene: &Mat, area: Rect, query_hist: &Mat) -> opencv::Result<f64> {
+    let area = Mat::roi(scene, area)?.try_clone()?;
+    imgproc::compare_hist(
+        &color_histogram(&area)?,
+        query_hist,
+        imgproc::HISTCMP_CORREL,
+    )
+}
+
+// The templates of a folder, path and card: "10D" for "templates/10D.png" and for
+// its variant "templates/10D-2.png"
+fn template_paths(folder: &str) -> Result<Vec<(String, String)>, OcrError> {
+    let pattern = format!("{}/*.png", folder);
+    let paths = glob(&pattern).map_err(|e| OcrError::Templates {
+        pattern: pattern.clone(),
+        reason: e.to_string(),
+    })?;
+    Ok(paths
+        .flatten()
+        .map(|path| {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let card = stem.split('-').next().unwrap_or_default().to_string();
+            (path.to_string_lossy().into_owned(), card)
+        })
+        .collect())
+}
+
+fn load_templates(folder: &str) -> Result<Vec<(String, String, Mat)>, OcrError> {
+    template_paths(folder)?
+        .into_iter()
+        .map(|(name, stem)| {
+            let image = load_image(&name)?;
+            Ok((name, stem, image))
+        })
+        .collect()
+}
+
+// The capture as plain pixels, shared by the matching threads: each one wraps them in
+// its own matrix header, without copying them
+struct Scene {
+    pixels: Vec<Vec3b>,
+    rows: i32,
+    cols: i32,
+    // Normalized, see `normalize`, and mostly dark once normalized
+    normalized: bool,
+    dark: bool,
+    // The scene `scale` times smaller, see `coarse_scale`
+    coarse: Option<Box<Scene>>,
+    scale: i32,
+    // Matched on the GPU, see `match_scores`
+    opencl: bool,
+    // Size of the cards on the scene relative to the templates, see `calibrate`
+    template_scale: f64,
+}
+
+impl Scene {
+    fn new(image: &Mat, settings: &OcrSettings) -> opencv::Result<Self> {
+        let dark = settings.normalize && is_dark(image)?;
+        let mut scene = Scene::plain(image, settings.normalize, dark)?;
+        // Without an OpenCL device, or an OpenCV built without it, the CPU does it all
+        scene.opencl = settings.opencl && core::have_opencl()?;
+        if scene.opencl {
+            core::set_use_opencl(true)?;
+        }
+        let scale = settings.coarse_scale as i32;
+        if scale > 1 {
+            let small = downscale(image, scale)?;
+            scene.coarse = Some(Box::new(Scene::plain(&small, settings.normalize, dark)?));
+            scene.scale = scale;
+        }
+        Ok(scene)
+    }
+
+    fn plain(image: &Mat, normalized: bool, dark: bool) -> opencv::Result<Self> {
+        Ok(Scene {
+            pixels: image.data_typed::<Vec3b>()?.to_vec(),
+            rows: image.rows(),
+            cols: image.cols(),
+            normalized,
+            dark,
+            coarse: None,
+            scale: 1,
+            opencl: false,
+            template_scale: 1.0,
+        })
+    }
+
+    fn mat(&self) -> opencv::Result<BoxedRef<'_, Mat>> {
+        Mat::new_rows_cols_with_data(self.rows, self.cols, &self.pixels)
+    }
+
+    // Every location where a prepared template scores at least `threshold`, see
+    // `peaks`. With a coarse scene, the full size matching only runs around the spots
+    // where the downscaled template comes close: a template's scan is then about
+    // `scale`² times cheaper.
+    fn peaks(&self, query: &Mat, threshold: f64) -> opencv::Result<Vec<(f64, Point)>> {
+        let scene = self.mat()?;
+        let scale = self.scale;
+        let coarse = self
+            .coarse
+            .as_ref()
+            .filter(|_| query.cols().min(query.rows()) / scale >= MIN_COARSE_SIZE);
+        let Some(coarse) = coarse else {
+            return peaks(&scene, query, threshold, self.opencl);
+        };
+
+        let spots = peaks(
+            &coarse.mat()?,
+            &downscale(query, scale)?,
+            threshold - COARSE_SLACK,
+            self.opencl,
+        )?;
+        let mut best: HashMap<(i32, i32), f64> = HashMap::new();
+        for (_, spot) in spots {
+            // One coarse pixel of margin around the spot
+            let x = (spot.x * scale - scale).max(0);
+            let y = (spot.y * scale - scale).max(0);
+            let width = (query.cols() + 2 * scale).min(self.cols - x);
+            let height = (query.rows() + 2 * scale).min(self.rows - y);
+            if width < query.cols() || height < query.rows() {
+                continue;
+            }
+            let area = Mat::roi(&*scene, Rect::new(x, y, width, height))?;
+            for (score, point) in peaks(&area, query, threshold, self.opencl)? {
+                let kept = best.entry((x + point.x, y + point.y)).or_insert(score);
+                *kept = kept.max(score);
+            }
+        }
+        Ok(best
+            .into_iter()
+            .map(|((x, y), score)| (score, Point::new(x, y)))
+            .collect())
+    }
+
+    // A template made comparable with the scene: normalized the same way, and
+    // inverted when one is dark and the other light, as a light template on a dark
+    // theme would score below zero
+    fn prepare(&self, template: Mat) -> opencv::Result<Mat> {
+        let template = match self.template_scale == 1.0 {
+            true => template,
+            false => resize(&template, self.template_scale)?,
+        };
+        if !self.normalized {
+            return Ok(template);
+        }
+        let template = normalize(&template)?;
+        if is_dark(&template)? == self.dark {
+            return Ok(template);
+        }
+        let mut inverted = Mat::default();
+        core::bitwise_not_def(&template, &mut inverted)?;
+        Ok(inverted)
+    }
+}
+
+// Gray levels with local contrast equalization (CLAHE), back in 3 channels for the
+// matching. Washes out the tint of night-light filters and the overall brightness of
+// a theme, the shapes stay.
+fn normalize(image: &Mat) -> opencv::Result<Mat> {
+    let mut gray = Mat::default();
+    imgproc::cvt_color_def(image, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+    let mut equalized = Mat::default();
+    imgproc::create_clahe(2.0, Size::new(8, 8))?.apply(&gray, &mut equalized)?;
+    let mut normalized = Mat::default();
+    imgproc::cvt_color_def(&equalized, &mut normalized, imgproc::COLOR_GRAY2BGR)?;
+    Ok(normalized)
+}
+
+fn is_dark(image: &Mat) -> opencv::Result<bool> {
+    let mean = core::mean(image, &Mat::default())?;
+    Ok((mean[0] + mean[1] + mean[2]) / 3.0 < 128.0)
+}
+
+fn matching_error(name: &str) -> impl Fn(opencv::Error) -> OcrError + '_ {
+    move |e| OcrError::Matching {
+        path: name.to_string(),
+        reason: e.to_string(),
+    }
+}
+
+// Every card found on the capture, by whole cards or by corners
+pub fn run_ocr(capture: Capture, settings: &OcrSettings) -> Result<Vec<Detection>, OcrError> {
+    find_cards(capture, settings, &mut None)
+}
+
+fn find_cards(
+    capture: Capture,
+    settings: &OcrSettings,
+    measured: &mut MeasuredScale,
+) -> Result<Vec<Detection>, OcrError> {
+    let matching = matching_error(capture.name());
+    let mut img_scene = capture.load()?;
+    if settings.normalize {
+        img_scene = normalize(&img_scene).map_err(&matching)?;
+    }
+    let mut scene = Scene::new(&img_scene, settings).map_err(&matching)?;
+    if settings.calibrate {
+        scene.template_scale = calibrate(&scene, capture.name(), settings, measured)?;
+    }
+    let detections = match &settings.corner_templates {
+        Some(folder) => match_corners(&scene, folder, settings)?,
+        None => match_cards(&scene, settings)?,
+    };
+
+    let mut kept = non_max_suppression(detections.clone(), settings.max_overlap);
+    note_runner_ups(&mut kept, &detections, settings.max_overlap);
+    let mut detections = kept;
+    if settings.read_ranks {
+        let classifier = rank_classifier(&settings.templates, &scene)?;
+        // The rank glyph is cut in a strip, the classifier learned it from whole cards
+        for detection in detections
+            .iter_mut()
+            .filter(|d| d.method != MatchMethod::Strip)
+        {
+            let read = read_rank(&classifier, &img_scene, detection)?;
+            fuse_rank(detection, read);
+        }
+    }
+    detections.sort_by_key(|d| (d.y, d.x));
+    Ok(detections)
+}
 
-        // println!("Filename: {:?}", path.file_name());
-        // println!("Best match confidence: {:.4}", max_val);
-        // println!("Best match location: ({}, {})", max_loc.x, max_loc.y);
+// The rank corner of a match: its top half, in gray levels
+fn rank_features(
+    image: &Mat,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+) -> opencv::Result<GlyphFeatures> {
+    let corner = Mat::roi(image, Rect::new(x, y, width, height / 2))?.try_clone()?;
+    let mut gray = Mat::default();
+    imgproc::cvt_color_def(&corner, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+    Ok(GlyphFeatures::new(
+        gray.data_bytes()?,
+        gray.cols() as usize,
+        gray.rows() as usize,
+    ))
+}
 
-        card_positions.push(CardPosition {
-            x: max_loc.x,
-            y: max_loc.y,
-            width: img_query.cols(),
-            height: img_query.rows(),
-            confidence: max_val,
-            card: Card::from(path.file_stem().unwrap().to_str().unwrap()),
-        });
+// Learns every rank from the corners of the card templates
+fn rank_classifier(folder: &str, scene: &Scene) -> Result<RankClassifier, OcrError> {
+    let mut classifier = RankClassifier::default();
+    for (name, stem, template) in load_templates(folder)? {
+        let card = Card::try_from(stem.as_str()).map_err(|source| OcrError::TemplateName {
+            path: name.clone(),
+            source,
+        })?;
+        let glyph = scene
+            .prepare(template)
+            .and_then(|template| rank_features(&template, 0, 0, template.cols(), template.rows()))
+            .map_err(matching_error(&name))?;
+        classifier.learn(card.rank, glyph);
     }
+    Ok(classifier)
+}
+
+// Second opinion on the rank of a match, from the glyph in its corner
+fn read_rank(
+    classifier: &RankClassifier,
+    scene: &Mat,
+    detection: &Detection,
+) -> Result<Option<(u8, f64)>, OcrError> {
+    let glyph = rank_features(
+        scene,
+        detection.x,
+        detection.y,
+        detection.width,
+        detection.height,
+    )
+    .map_err(matching_error(&detection.card.to_string()))?;
+    Ok(classifier.classify(&glyph))
+}
+
+// One template per card, the templates are matched in parallel. Each template keeps
+// all its peaks above `min_confidence` after non-maximum suppression, so that a card is
+// still found when a lookalike scores higher elsewhere. Peaks down to
+// `fallback_confidence` are kept when their colors match the template's, see
+// `MatchMethod::ColorHistogram`. With `strip_height`, the top strip of each template is
+// matched too, for the cards the next one of their column mostly hides. Overlapping
+// matches of different templates are then settled the same way, the best score wins
+// the spot.
+fn match_cards(scene: &Scene, settings: &OcrSettings) -> Result<Vec<Detection>, OcrError> {
+    let found: Vec<Vec<Detection>> = template_paths(&settings.templates)?
+        .par_iter()
+        .map(|(name, stem)| match_card(scene, name, stem, settings))
+        .collect::<Result<_, _>>()?;
+    Ok(found.concat())
+}
+
+fn match_card(
+    scene: &Scene,
+    name: &str,
+    stem: &str,
+    settings: &OcrSettings,
+) -> Result<Vec<Detection>, OcrError> {
+    let matching = matching_error(name);
+    let img_query = scene.prepare(load_image(name)?).map_err(&matching)?;
+    // Normalized scenes have no colors left to check
+    let threshold = match settings.normalize {
+        true => settings.min_confidence,
+        false => settings.min_confidence.min(settings.fallback_confidence),
+    };
+    let found = scene.peaks(&img_query, threshold).map_err(&matching)?;
+    let (strip_rows, strips) = match settings.strip_height {
+        Some(fraction) => {
+            let rows = (img_query.rows() as f64 * fraction).round() as i32;
+            let rows = rows.clamp(1, img_query.rows());
+            let strip = Mat::roi(&img_query, Rect::new(0, 0, img_query.cols(), rows))
+                .and_then(|strip| strip.try_clone())
+                .map_err(&matching)?;
+            let strips = scene
+                .peaks(&strip, settings.min_confidence)
+                .map_err(&matching)?;
+            (rows, strips)
+        }
+        None => (0, Vec::new()),
+    };
+    let scene = scene.mat().map_err(&matching)?;
+    if found.is_empty() && strips.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Templates are named after their card: "TD.png"
+    let card = Card::try_from(stem).map_err(|source| OcrError::TemplateName {
+        path: name.to_string(),
+        source,
+    })?;
+    let detection = |(confidence, location): (f64, Point), height, method| Detection {
+        x: location.x,
+        y: location.y,
+        width: img_query.cols(),
+        height,
+        confidence,
+        method,
+        card,
+        runner_up: None,
+    };
+
+    let candidates = found
+        .into_iter()
+        .map(|peak| detection(peak, img_query.rows(), MatchMethod::Template))
+        .collect();
+
+    // Matches below the threshold are kept only if the colors agree
+    let query_hist = color_histogram(&img_query).map_err(&matching)?;
+    let mut detections = Vec::new();
+    for mut detection in non_max_suppression(candidates, settings.max_overlap) {
+        if detection.confidence < settings.min_confidence {
+            let area = Rect::new(detection.x, detection.y, detection.width, detection.height);
+            let similarity = histogram_similarity(&scene, area, &query_hist).map_err(&matching)?;
+            if similarity < settings.histogram_confidence {
+                continue;
+            }
+            detection.method = MatchMethod::ColorHistogram;
+        }
+        detections.push(detection);
+    }
+
+    // Strips at the top of a whole match are that match again
+    let strips = strips
+        .into_iter()
+        .map(|peak| detection(peak, strip_rows, MatchMethod::Strip))
+        .collect();
+    let wholes: Vec<Detection> = detections
+        .iter()
+        .map(|d| Detection {
+            height: strip_rows,
+            ..*d
+        })
+        .collect();
+    for strip in non_max_suppression(strips, settings.max_overlap) {
+        if wholes
+            .iter()
+            .all(|d| d.overlap(&strip) <= settings.max_overlap)
+        {
+            detections.push(strip);
+        }
+    }
+    Ok(detections)
+}
+
+// Peaks of every template of a folder, after non-maximum suppression across them all
+fn match_glyphs<T: Copy + Send>(
+    scene: &Scene,
+    folder: &str,
+    settings: &OcrSettings,
+    parse: impl Fn(&str) -> Result<T, CardError> + Sync,
+) -> Result<Vec<Glyph<T>>, OcrError> {
+    let found: Vec<Vec<Glyph<T>>> = template_paths(folder)?
+        .par_iter()
+        .map(|(name, stem)| {
+            let img_query = scene
+                .prepare(load_image(name)?)
+                .map_err(matching_error(name))?;
+            let found = scene
+                .peaks(&img_query, settings.min_confidence)
+                .map_err(matching_error(name))?;
+            if found.is_empty() {
+                return Ok(Vec::new());
+            }
+            let value = parse(stem).map_err(|source| OcrError::TemplateName {
+                path: name.clone(),
+                source,
+            })?;
+            let candidates = found
+                .into_iter()
+                .map(|(confidence, location)| Glyph {
+                    x: location.x,
+                    y: location.y,
+                    width: img_query.cols(),
+                    height: img_query.rows(),
+                    confidence,
+                    value,
+                })
+                .collect();
+            Ok(non_max_suppression(candidates, settings.max_overlap))
+        })
+        .collect::<Result<_, OcrError>>()?;
+    Ok(non_max_suppression(found.concat(), settings.max_overlap))
+}
 
-    card_positions.sort_by_key(|p| (p.y, p.x));
+// 13 rank glyphs in `{folder}/ranks` ("1.png" to "13.png", or "A.png", "T.png"...) and
+// 4 suit pips in `{folder}/suits` ("C.png"...), matched apart then paired
+fn match_corners(
+    scene: &Scene,
+    folder: &str,
+    settings: &OcrSettings,
+) -> Result<Vec<Detection>, OcrError> {
+    let ranks = match_glyphs(scene, &format!("{}/ranks", folder), settings, |stem| {
+        Ok(Card::try_from(format!("{}C", stem).as_str())?.rank)
+    })?;
+    let suits = match_glyphs(scene, &format!("{}/suits", folder), settings, |stem| {
+        Ok(Card::try_from(format!("A{}", stem).as_str())?.suit)
+    })?;
+    Ok(pair_corners(&ranks, &suits))
+}
+
+// The settings with the templates of the theme: the configured one, else the theme
+// folder whose templates find the most on the capture, by total confidence. Without
+// theme folders the templates folder is used as it is.
+pub fn resolve_theme(capture: Capture, settings: &OcrSettings) -> Result<OcrSettings, OcrError> {
+    if let Some(theme) = &settings.theme {
+        return Ok(settings.with_theme(theme));
+    }
+    let mut best: Option<(f64, OcrSettings)> = None;
+    let mut failure = None;
+    for theme in settings.themes() {
+        let themed = settings.with_theme(&theme);
+        match run_ocr(capture, &themed) {
+            Ok(detections) => {
+                let score: f64 = detections.iter().map(|d| d.confidence).sum();
+                if best.as_ref().is_none_or(|(best, _)| score > *best) {
+                    best = Some((score, themed));
+                }
+            }
+            Err(e) => failure = failure.or(Some(e)),
+        }
+    }
+    match (best, failure) {
+        (Some((_, themed)), _) => Ok(themed),
+        (None, Some(e)) => Err(e),
+        (None, None) => Ok(settings.clone()),
+    }
+}
+
+// The cards on the screenshot: the columns of the tableau, where the `layout` preset
+// puts them if set, and the freecells and foundations above. The matches are drawn
+// onto `debug_image` if set, even when they do not make a board.
+pub fn read_board(
+    capture: Capture,
+    settings: &OcrSettings,
+) -> Result<BoardDetections, SolverError> {
+    board_from(capture, settings, &mut None)
+}
+
+fn board_from(
+    capture: Capture,
+    settings: &OcrSettings,
+    measured: &mut MeasuredScale,
+) -> Result<BoardDetections, SolverError> {
+    let detections = find_cards(capture, settings, measured)?;
+    let board = match settings.layout {
+        Some(preset) => {
+            let width = capture.load()?.cols();
+            let geometry = match preset.geometry() {
+                Some(geometry) => geometry,
+                None => measure_board(capture)?,
+            };
+            preset_board(&detections, &geometry, width)
+        }
+        None => infer_board(&detections),
+    };
+    if let Some(path) = &settings.debug_image {
+        let columns = board.as_ref().ok().map(|board| board.columns.as_slice());
+        draw_debug(capture, &detections, columns, settings, path)?;
+    }
+    board
+}
+
+// The last board read, kept by the loops that capture the same area again and again,
+// and the template scale measured on their captures
+#[derive(Default)]
+pub struct BoardCache {
+    last: Option<(u64, BoardDetections)>,
+    measured: MeasuredScale,
+}
+
+impl BoardCache {
+    // `read_board`, or the last board read when nothing it depends on changed since,
+    // see `capture_key`. With a debug image, the board is always read and drawn again.
+    pub fn read(
+        &mut self,
+        image: &RgbaImage,
+        settings: &OcrSettings,
+    ) -> Result<BoardDetections, SolverError> {
+        if settings.debug_image.is_some() {
+            return board_from(Capture::Image(image), settings, &mut self.measured);
+        }
+        let key = capture_key(image, settings)?;
+        if let Some((last_key, board)) = &self.last
+            && *last_key == key
+        {
+            return Ok(board.clone());
+        }
+        let board = board_from(Capture::Image(image), settings, &mut self.measured)?;
+        self.last = Some((key, board.clone()));
+        Ok(board)
+    }
+}
 
-    card_positions
+// What a board read depends on: the pixels of the capture, every setting, and the
+// templates by name and modification time, so that new templates are matched
+fn capture_key(image: &RgbaImage, settings: &OcrSettings) -> Result<u64, OcrError> {
+    let mut hasher = DefaultHasher::new();
+    (image.width(), image.height(), image.as_raw()).hash(&mut hasher);
+    format!("{:?}", settings).hash(&mut hasher);
+    let mut folders = vec![settings.templates.clone()];
+    if let Some(folder) = &settings.corner_templates {
+        folders.push(format!("{}/ranks", folder));
+        folders.push(format!("{}/suits", folder));
+    }
+    for folder in folders {
+        for (path, _) in template_paths(&folder)? {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified).hash(&mut hasher);
+        }
+    }
+    Ok(hasher.finish())
+}
+
+// The board geometry of the `auto` layout, measured on the capture itself
+fn measure_board(capture: Capture) -> Result<Geometry, OcrError> {
+    detect_board(&capture.rgba()?)
+        .map(|board| board.geometry)
+        .map_err(|reason| OcrError::Image {
+            path: capture.name().to_string(),
+            reason,
+        })
+}
+
+// A copy of the capture with what was read on it: the box, card and score of every
+// match, green when sure and orange when doubtful, and the left edge of each column
+// they were grouped into, if they could be
+pub fn draw_debug(
+    capture: Capture,
+    detections: &[Detection],
+    columns: Option<&[Vec<Detection>]>,
+    settings: &OcrSettings,
+    path: &str,
+) -> Result<(), OcrError> {
+    let drawing = |e: opencv::Error| OcrError::Image {
+        path: path.to_string(),
+        reason: e.to_string(),
+    };
+    let mut image = capture.load()?;
+    let bottom = image.rows();
+    let grid = Scalar::new(255.0, 128.0, 0.0, 0.0);
+    let sure = Scalar::new(0.0, 180.0, 0.0, 0.0);
+    let doubtful = Scalar::new(0.0, 140.0, 255.0, 0.0);
+
+    for detection in detections {
+        let color = match detection.confidence < settings.warn_confidence {
+            true => doubtful,
+            false => sure,
+        };
+        let [x, y, width, height] = detection.area();
+        imgproc::rectangle_def(&mut image, Rect::new(x, y, width, height), color)
+            .map_err(drawing)?;
+        let label = format!("{} {:.2}", detection.card, detection.confidence);
+        put_label(&mut image, &label, x + 2, y + 12, color).map_err(drawing)?;
+    }
+    for (i, column) in columns.unwrap_or_default().iter().enumerate() {
+        let (Some(left), Some(top)) = (
+            column.iter().map(|d| d.x).min(),
+            column.iter().map(|d| d.y).min(),
+        ) else {
+            continue;
+        };
+        let (start, end) = (Point::new(left, top - 20), Point::new(left, bottom));
+        imgproc::line_def(&mut image, start, end, grid).map_err(drawing)?;
+        put_label(&mut image, &(i + 1).to_string(), left + 3, top - 6, grid).map_err(drawing)?;
+    }
+    imgcodecs::imwrite_def(path, &image).map_err(drawing)?;
+    Ok(())
+}
+
+fn put_label(image: &mut Mat, text: &str, x: i32, y: i32, color: Scalar) -> opencv::Result<()> {
+    imgproc::put_text_def(
+        image,
+        text,
+        Point::new(x, y),
+        imgproc::FONT_HERSHEY_SIMPLEX,
+        0.35,
+        color,
+    )
+}
+
+pub fn print_report(report: &DeckReport) {
+    if !report.missing.is_empty() {
+        let cards: Vec<String> = report.missing.iter().map(Card::to_string).collect();
+        eprintln!("{}", Msg::OcrMissing(&cards.join(" ")));
+    }
+    for (card, places) in &report.duplicated {
+        let places: Vec<String> = places
+            .iter()
+            .map(|(column, row)| format!("{}/{}", column, row))
+            .collect();
+        eprintln!("{}", Msg::OcrDuplicated(card, &places.join(", ")));
+    }
+    for ((column, row), detection) in &report.doubtful {
+        eprintln!(
+            "{}",
+            Msg::OcrDoubtful(
+                &detection.card,
+                *column,
+                *row,
+                detection.confidence,
+                detection.method.name()
+            )
+        );
+    }
+    if report.is_valid() {
+        eprintln!("{}", Msg::OcrDeckOk);
+    }
+}
+
+// Shows the board as read and lets the player fix cells until the deck is whole: the
+// top row first, as row 0, then the columns. Returns false if they gave up.
+pub fn fix_board(board: &mut BoardDetections, settings: &OcrSettings) -> io::Result<bool> {
+    let stdin = io::stdin();
+    loop {
+        let slots: Vec<String> = board
+            .top_row
+            .iter()
+            .map(|slot| slot.map_or("--".to_string(), |d| d.card.to_string()))
+            .collect();
+        eprintln!("0: {} | {}", slots[..4].join(" "), slots[4..].join(" "));
+        for (i, column) in board.columns.iter().enumerate() {
+            let cards: Vec<String> = column.iter().map(|d| d.card.to_string()).collect();
+            eprintln!("{}: {}", i + 1, cards.join(" "));
+        }
+        let report = DeckReport::new(board, settings.warn_confidence);
+        print_report(&report);
+        eprint!("{}", Msg::OcrFixPrompt);
+        io::stderr().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(false);
+        }
+        match line.trim() {
+            "q" => return Ok(false),
+            "" if report.is_valid() => return Ok(true),
+            "" => {}
+            fix => {
+                if let Err(e) = set_card(board, fix) {
+                    eprintln!("❌ {}", e);
+                }
+            }
+        }
+    }
+}
+
+// Offers the two best cards of each doubtful cell, bottom cells first so that removing
+// one leaves the places of the others as shown, then goes on with `fix_board` if the
+// deck is still not whole. Returns false if the player gave up.
+pub fn review_board(board: &mut BoardDetections, settings: &OcrSettings) -> io::Result<bool> {
+    let stdin = io::stdin();
+    let report = DeckReport::new(board, settings.warn_confidence);
+    print_report(&report);
+    for &((column, row), detection) in report.doubtful.iter().rev() {
+        let runner_up = detection
+            .runner_up
+            .filter(|(card, _)| *card != detection.card);
+        eprintln!(
+            "{}",
+            Msg::OcrCandidate(column, row, &detection.card, detection.confidence)
+        );
+        if let Some((card, confidence)) = runner_up {
+            eprintln!("{}", Msg::OcrRunnerUp(&card, confidence));
+        }
+        eprint!("{}", Msg::OcrReviewPrompt);
+        io::stderr().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(false);
+        }
+        let card = match (line.trim(), runner_up) {
+            ("" | "1", _) => continue,
+            ("q", _) => return Ok(false),
+            ("2", Some((card, _))) => card.to_string(),
+            ("2", None) => continue,
+            (card, _) => card.to_string(),
+        };
+        if let Err(e) = set_card(board, &format!("{} {} {}", column, row, card)) {
+            eprintln!("❌ {}", e);
+        }
+    }
+    if DeckReport::new(board, settings.warn_confidence).is_valid() {
+        return Ok(true);
+    }
+    fix_board(board, settings)
+}
+
+// Shows each spot the templates could not read for sure, the holes in the columns and
+// the doubtful matches, and saves it as a new template of the card the player types.
+// Returns how many templates were written.
+pub fn label_templates(settings: &OcrSettings) -> Result<usize, SolverError> {
+    let scene = load_image(&settings.capture)?;
+    let board = read_board(Capture::File(&settings.capture), settings)?;
+    let mut spots: Vec<((usize, usize), [i32; 4], String)> = unknown_regions(&board.columns)
+        .into_iter()
+        .map(|(place, area)| (place, area, "?".to_string()))
+        .collect();
+    let report = DeckReport::new(&board, settings.warn_confidence);
+    spots.extend(report.doubtful.iter().map(|(place, detection)| {
+        let guess = format!("{} {:.2}", detection.card, detection.confidence);
+        (*place, detection.area(), guess)
+    }));
+
+    let window = |e: opencv::Error| OcrError::Window(e.to_string());
+    let stdin = io::stdin();
+    let mut written = 0;
+    'spots: for ((column, row), [x, y, width, height], guess) in spots {
+        let crop = Mat::roi(&scene, Rect::new(x, y, width, height))
+            .and_then(|crop| crop.try_clone())
+            .map_err(window)?;
+        let mut zoomed = Mat::default();
+        imgproc::resize(
+            &crop,
+            &mut zoomed,
+            Size::default(),
+            3.0,
+            3.0,
+            imgproc::INTER_NEAREST,
+        )
+        .map_err(window)?;
+        highgui::imshow("freecell", &zoomed).map_err(window)?;
+        highgui::wait_key(1).map_err(window)?;
+
+        loop {
+            eprint!("{}", Msg::OcrLabelPrompt(column, row, &guess));
+            io::stderr().flush()?;
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line)? == 0 {
+                break 'spots;
+            }
+            let card = match line.trim() {
+                "q" => break 'spots,
+                "" => break,
+                code => match code.parse::<Card>() {
+                    Ok(card) => card,
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        continue;
+                    }
+                },
+            };
+            let path = new_template_path(&settings.templates, &card);
+            let path = path.to_string_lossy();
+            imgcodecs::imwrite_def(&path, &crop).map_err(|e| OcrError::Image {
+                path: path.to_string(),
+                reason: e.to_string(),
+            })?;
+            written += 1;
+            break;
+        }
+    }
+    highgui::destroy_all_windows().map_err(window)?;
+    Ok(written)
 }
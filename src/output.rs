@@ -0,0 +1,204 @@
+use clap::ValueEnum;
+use serde_json::{Value, json};
+
+use crate::action::Action;
+use crate::display::BoardStyle;
+use crate::explain::explain;
+use crate::game::Game;
+use crate::i18n::Msg;
+use crate::notation;
+use crate::postprocess::SolutionStep;
+use crate::stats::SearchStats;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SolutionFormat {
+    // One Action struct per line
+    #[default]
+    Debug,
+    // fc-solve notation on a single line: "3a 72 8h ..."
+    Standard,
+    // Freecell Pro notation, supermoves with their card count: "3a 72v3 8h ..."
+    Pro,
+    // Deal, moves and search statistics as a single JSON document
+    Json,
+    // Each move followed by the resulting board
+    Verbose,
+}
+
+// Global --format switch, honored by every subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputMode {
+    // Human readable output, as chosen by each subcommand's own options
+    #[default]
+    Text,
+    // A machine-readable document on stdout, nothing else
+    Json,
+    // Nothing printed, only the exit code tells the result
+    Quiet,
+}
+
+impl OutputMode {
+    pub fn is_quiet(self) -> bool {
+        self == OutputMode::Quiet
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OutputOptions {
+    pub format: SolutionFormat,
+    // Print supermoves card by card
+    pub expanded: bool,
+    // Verbose format: print the board after every N moves
+    pub snapshot_every: usize,
+    pub style: BoardStyle,
+}
+
+fn flatten(steps: &[SolutionStep], expanded: bool) -> Vec<Action> {
+    if expanded {
+        steps
+            .iter()
+            .flat_map(|s| s.expanded.iter().copied())
+            .collect()
+    } else {
+        steps.iter().map(|s| s.action).collect()
+    }
+}
+
+// Only the JSON format has something to say when no solution was found
+pub fn print_solution(
+    game: &Game,
+    solution: Option<&[SolutionStep]>,
+    stats: &SearchStats,
+    options: OutputOptions,
+) {
+    let expanded = options.expanded;
+    if options.format == SolutionFormat::Json {
+        let json = solution_json(game, solution, stats, expanded);
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        return;
+    }
+
+    let Some(steps) = solution else {
+        return;
+    };
+
+    match options.format {
+        SolutionFormat::Debug => {
+            for step in steps {
+                if expanded || step.expanded.len() == 1 {
+                    for action in &step.expanded {
+                        eprintln!("  - {:?}", action);
+                    }
+                } else {
+                    eprintln!("{}", Msg::Supermove(&step.action, step.expanded.len()));
+                }
+            }
+        }
+        SolutionFormat::Standard => {
+            let actions = flatten(steps, expanded);
+            println!("{}", notation::solution_to_standard(&actions));
+        }
+        SolutionFormat::Pro => {
+            let actions = flatten(steps, expanded);
+            println!("{}", notation::solution_to_freecell_pro(&actions));
+        }
+        SolutionFormat::Verbose => {
+            let actions = flatten(steps, expanded);
+            let every = options.snapshot_every.max(1);
+            let mut state = game.clone();
+
+            for (i, action) in actions.iter().enumerate() {
+                let cards: Vec<String> = state
+                    .cards_moved_by(action)
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect();
+                println!(
+                    "{:>4}. {}  {:?} {}  — {}",
+                    i + 1,
+                    notation::to_standard(action),
+                    action.action_type,
+                    cards.join(" "),
+                    explain(&state, action)
+                );
+                state.apply_unchecked(action);
+
+                if (i + 1) % every == 0 || i + 1 == actions.len() {
+                    println!();
+                    println!("{}", options.style.board(&state));
+                }
+            }
+        }
+        SolutionFormat::Json => unreachable!(),
+    }
+}
+
+pub fn game_json(game: &Game) -> Value {
+    json!({
+        "columns": game
+            .columns
+            .iter()
+            .map(|col| col.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        "freecells": game
+            .freecells
+            .iter()
+            .map(|cell| cell.map(|c| c.to_string()))
+            .collect::<Vec<_>>(),
+        "foundations": game.foundations,
+    })
+}
+
+pub(crate) fn action_json(state: &Game, action: &Action) -> Value {
+    json!({
+        "type": format!("{:?}", action.action_type),
+        "source": action.source,
+        "dest": action.dest,
+        "pile_size": action.pile_size,
+        "cards": state
+            .cards_moved_by(action)
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>(),
+        "notation": notation::to_standard(action),
+        "explanation": explain(state, action),
+    })
+}
+
+pub fn moves_json(game: &Game, actions: &[Action]) -> Vec<Value> {
+    let mut state = game.clone();
+    actions
+        .iter()
+        .map(|action| {
+            let json = action_json(&state, action);
+            state.apply_unchecked(action);
+            json
+        })
+        .collect()
+}
+
+// The hint `watch` gives for a position: the first move of its solution, if any, and
+// how many moves the solution has
+pub fn next_move_json(game: &Game, solution: Option<&[Action]>) -> Value {
+    json!({
+        "deal": game_json(game),
+        "solved": solution.is_some(),
+        "next_move": solution.and_then(<[Action]>::first).map(|action| action_json(game, action)),
+        "moves_left": solution.map_or(0, <[Action]>::len),
+    })
+}
+
+pub fn solution_json(
+    game: &Game,
+    solution: Option<&[SolutionStep]>,
+    stats: &SearchStats,
+    expanded: bool,
+) -> Value {
+    let actions = flatten(solution.unwrap_or_default(), expanded);
+    json!({
+        "deal": game_json(game),
+        "solved": solution.is_some(),
+        "moves": moves_json(game, &actions),
+        "stats": stats.to_json(),
+    })
+}
@@ -0,0 +1,358 @@
+use std::io;
+use std::time::Duration;
+
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+
+use freecell::action::{Action, Location};
+use freecell::batch::BatchOptions;
+use freecell::card::{Card, Suit};
+use freecell::display::BoardStyle;
+use freecell::explain::explain;
+use freecell::game::{Game, UndoToken};
+use freecell::history::{HandHistory, HistoryEvent, Mover};
+use freecell::i18n::Msg;
+use freecell::notation;
+use freecell::postprocess::remove_wasted_moves;
+use freecell::solver::Solver;
+
+// Delay between two moves when the solver plays
+const TAKEOVER_TICK: Duration = Duration::from_millis(300);
+
+pub struct Play {
+    pub game: Game,
+    history: Vec<UndoToken>,
+    // Source picked by the first key of a move
+    selected: Option<Location>,
+    // Moves left to play after a takeover
+    takeover: Vec<Action>,
+    message: String,
+    options: BatchOptions,
+    // Hand history of the game, saved with --history
+    pub log: HandHistory,
+}
+
+impl Play {
+    pub fn new(game: Game, options: BatchOptions) -> Self {
+        Play {
+            log: HandHistory::new(game.clone()),
+            game,
+            history: Vec::new(),
+            selected: None,
+            takeover: Vec::new(),
+            message: Msg::PlayHelp.to_string(),
+            options,
+        }
+    }
+
+    fn top_card(&self, location: Location) -> Option<Card> {
+        match location {
+            Location::Column(i) => self.game.columns.get(i)?.last().copied(),
+            Location::Freecell(i) => *self.game.freecells.get(i)?,
+            Location::Foundation(_) => None,
+        }
+    }
+
+    // The largest legal move between the two locations: a column to column move takes
+    // the longest sequence that fits
+    fn find_move(&self, source: Location, dest: Location) -> Option<Action> {
+        let dest = match (dest, self.top_card(source)) {
+            (Location::Foundation(_), Some(card)) => Location::Foundation(card.suit as usize),
+            _ => dest,
+        };
+        let longest = match source {
+            Location::Column(i) => self.game.columns[i].len(),
+            _ => 1,
+        };
+        (1..=longest)
+            .rev()
+            .filter_map(|pile_size| Action::between(source, dest, pile_size))
+            .find(|action| self.game.check_move(action).is_ok())
+    }
+
+    fn play(&mut self, action: &Action, by: Mover) {
+        let before = self.game.clone();
+        match self.game.apply(action) {
+            Ok(token) => {
+                self.history.push(token);
+                self.log.record(HistoryEvent::Move(*action, by));
+                self.message = format!(
+                    "{}  — {}",
+                    notation::to_freecell_pro(action),
+                    explain(&before, action)
+                );
+                if self.game.is_won() {
+                    self.message = Msg::Won(self.history.len()).to_string();
+                }
+            }
+            Err(e) => self.message = format!("❌ {}", e),
+        }
+    }
+
+    // First key picks the source, the second one the destination
+    pub fn select(&mut self, location: Location) {
+        self.takeover.clear();
+        let Some(source) = self.selected.take() else {
+            if self.top_card(location).is_some() {
+                self.selected = Some(location);
+                self.message = Msg::Destination.to_string();
+            } else {
+                self.message = Msg::NothingToMove.to_string();
+            }
+            return;
+        };
+        match self.find_move(source, location) {
+            Some(action) => self.play(&action, Mover::Player),
+            None => self.message = Msg::IllegalMove.to_string(),
+        }
+    }
+
+    pub fn undo(&mut self) {
+        self.takeover.clear();
+        self.selected = None;
+        match self.history.pop() {
+            Some(token) => {
+                self.game.undo(token);
+                self.log.record(HistoryEvent::Undo);
+                self.message = Msg::MoveUndone(self.history.len() + 1).to_string();
+            }
+            None => self.message = Msg::NothingToUndo.to_string(),
+        }
+    }
+
+    fn solve(&mut self) -> Option<Vec<Action>> {
+        let mut solver = Solver::new(self.game.clone());
+        solver.strategy = self.options.strategy;
+        solver.weights = self.options.weights;
+        solver.time_limit = self.options.time_limit;
+        solver.verbose = false;
+        let solution = solver.solve(self.options.max_nodes);
+        if solution.is_none() {
+            self.message = Msg::SolverStuck.to_string();
+        }
+        solution.map(|s| remove_wasted_moves(&self.game, &s))
+    }
+
+    pub fn hint(&mut self) {
+        self.selected = None;
+        let first = self.solve().and_then(|s| s.first().copied());
+        self.log.record(HistoryEvent::Hint(first));
+        if let Some(first) = first {
+            self.message = format!(
+                "💡 {}  — {}",
+                notation::to_freecell_pro(&first),
+                explain(&self.game, &first)
+            );
+        }
+    }
+
+    // The solver plays the rest of the game, one move per tick
+    pub fn take_over(&mut self) {
+        self.selected = None;
+        if let Some(mut solution) = self.solve() {
+            solution.reverse();
+            self.takeover = solution;
+        }
+    }
+
+    pub fn tick(&mut self) {
+        if let Some(action) = self.takeover.pop() {
+            self.play(&action, Mover::Solver);
+        }
+    }
+
+    fn card_span(
+        &self,
+        card: &Card,
+        style: BoardStyle,
+        location: Option<Location>,
+    ) -> Span<'static> {
+        let mut look = Style::default();
+        if style.color && !card.is_black() {
+            look = look.fg(Color::Red);
+        }
+        if location.is_some() && self.selected == location {
+            look = look.add_modifier(Modifier::REVERSED);
+        }
+        let plain = BoardStyle {
+            color: false,
+            ..style
+        };
+        Span::styled(plain.card(card), look)
+    }
+
+    fn board_lines(&self, style: BoardStyle) -> Vec<Line<'static>> {
+        let mut top = Vec::new();
+        for (i, cell) in self.game.freecells.iter().enumerate() {
+            match cell {
+                Some(card) => top.push(self.card_span(card, style, Some(Location::Freecell(i)))),
+                None if i < self.game.cells => top.push(Span::raw(style.empty("-"))),
+                None => top.push(Span::raw(style.empty("X"))),
+            }
+        }
+        top.push(Span::raw("  "));
+        for (suit, &count) in self.game.foundations.iter().enumerate() {
+            let card = Card {
+                rank: count,
+                suit: Suit::ALL[suit],
+            };
+            match count {
+                0 => top.push(Span::raw(style.empty("."))),
+                _ => top.push(self.card_span(&card, style, None)),
+            }
+        }
+
+        // Key of each pile, centered over it
+        let keys = |keys: &str| -> String {
+            keys.chars()
+                .map(|k| format!("{:^width$}", k, width = style.width()))
+                .collect()
+        };
+        let mut lines = vec![
+            Line::from(keys("abcd")),
+            Line::from(top),
+            Line::from(""),
+            Line::from(keys("12345678")),
+        ];
+        let rows = self.game.columns.iter().map(Vec::len).max().unwrap_or(0);
+        for row in 0..rows {
+            let spans: Vec<Span> = self
+                .game
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| match column.get(row) {
+                    Some(card) => {
+                        let top = (row + 1 == column.len()).then_some(Location::Column(i));
+                        self.card_span(card, style, top)
+                    }
+                    None => Span::raw(style.empty("")),
+                })
+                .collect();
+            lines.push(Line::from(spans));
+        }
+        lines
+    }
+}
+
+fn location_for_key(key: char) -> Option<Location> {
+    match key {
+        '1'..='8' => Some(Location::Column(key as usize - '1' as usize)),
+        'a'..='d' => Some(Location::Freecell(key as usize - 'a' as usize)),
+        'h' => Some(Location::Foundation(0)),
+        _ => None,
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, play: &mut Play, style: BoardStyle) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| {
+            let [board, status] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+            let title = Msg::PlayTitle(play.history.len()).to_string();
+            frame.render_widget(
+                Paragraph::new(play.board_lines(style))
+                    .block(Block::default().borders(Borders::ALL).title(title)),
+                board,
+            );
+            frame.render_widget(
+                Paragraph::new(play.message.as_str()).block(Block::default().borders(Borders::ALL)),
+                status,
+            );
+        })?;
+
+        let timeout = if play.takeover.is_empty() {
+            Duration::from_secs(60)
+        } else {
+            TAKEOVER_TICK
+        };
+        if !event::poll(timeout)? {
+            play.tick();
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') => return Ok(()),
+            KeyCode::Esc => {
+                play.selected = None;
+                play.takeover.clear();
+                play.message = Msg::PlayHelp.to_string();
+            }
+            KeyCode::Char('u') | KeyCode::Backspace => play.undo(),
+            KeyCode::Char('?') => play.hint(),
+            KeyCode::Char('s') => play.take_over(),
+            KeyCode::Char(c) => match location_for_key(c) {
+                Some(location) => play.select(location),
+                None => play.message = Msg::PlayHelp.to_string(),
+            },
+            _ => {}
+        }
+    }
+}
+
+// Takes over the terminal until the player quits, then hands back what was played
+pub fn run_play(game: Game, options: BatchOptions, style: BoardStyle) -> io::Result<HandHistory> {
+    let mut terminal = ratatui::init();
+    let mut play = Play::new(game, options);
+    let result = run(&mut terminal, &mut play, style);
+    ratatui::restore();
+    result.map(|()| play.log)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use freecell::deal::ms_deal;
+    use freecell::solver::{HeuristicWeights, Strategy};
+
+    fn play(deal: u32) -> Play {
+        let options = BatchOptions {
+            strategy: Strategy::AStar,
+            max_nodes: 100_000,
+            time_limit: None,
+            weights: HeuristicWeights::default(),
+            optimize: true,
+            rate: false,
+        };
+        Play::new(Game::new(&ms_deal(deal)), options)
+    }
+
+    #[test]
+    fn test_select_and_undo() {
+        let mut play = play(1);
+        let start = play.game.layout_key();
+
+        play.select(Location::Column(0));
+        play.select(Location::Freecell(0));
+        assert!(play.game.freecells[0].is_some());
+        assert_eq!(play.history.len(), 1);
+
+        // An empty freecell is not a source
+        play.select(Location::Freecell(1));
+        assert!(play.selected.is_none());
+
+        play.undo();
+        assert_eq!(play.game.layout_key(), start);
+    }
+
+    #[test]
+    fn test_take_over_wins() {
+        let mut play = play(1);
+        play.take_over();
+        while !play.takeover.is_empty() {
+            play.tick();
+        }
+        assert!(play.game.is_won());
+        assert!(play.log.is_won());
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::action::{Action, ActionType, Location};
+use crate::game::Game;
+use crate::solver::Solver;
+use crate::verify::verify_solution;
+
+// The card-by-card pass replays the whole solution for every candidate
+const MAX_MOVES_FOR_MERGE_PASS: usize = 5000;
+
+// A supermove of 13 cards takes at most 25 single moves through freecells and empty columns
+const MAX_SUPERMOVE_WINDOW: usize = 25;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolutionStep {
+    pub action: Action,
+    // Single moves the step stands for: just the action itself unless it is a merged supermove
+    pub expanded: Vec<Action>,
+}
+
+// Shorten a solution by removing moves that nothing needed. First, whenever a later
+// position of the solution is reachable in a single move, the detour is cut out
+// (DFS solutions are mostly detours). Then a card parked somewhere and later moved on
+// is sent straight to its final destination, and round trips are dropped.
+// Every candidate is replayed through the validated Game::apply and kept only if it
+// still wins, so the result is never worse than the input.
+pub fn remove_wasted_moves(game: &Game, solution: &[Action]) -> Vec<Action> {
+    if !replays_to_win(game, solution) {
+        return solution.to_vec();
+    }
+
+    let mut moves = cut_detours(game, solution);
+    if moves.len() > MAX_MOVES_FOR_MERGE_PASS {
+        return moves;
+    }
+
+    let mut start = 0;
+    while let Some((shorter, changed_at)) = shorten_from(game, &moves, start) {
+        moves = shorter;
+        start = changed_at.saturating_sub(1);
+    }
+
+    moves
+}
+
+pub fn replays_to_win(game: &Game, moves: &[Action]) -> bool {
+    verify_solution(game, moves).is_ok_and(|state| state.is_won())
+}
+
+fn cut_detours(game: &Game, moves: &[Action]) -> Vec<Action> {
+    // Index of each position of the solution, as laid out (column order matters here)
+    let mut positions = HashMap::new();
+    let mut state = game.clone();
+    positions.insert(state.layout_key(), 0);
+    for (i, mov) in moves.iter().enumerate() {
+        state.apply_unchecked(mov);
+        positions.insert(state.layout_key(), i + 1);
+    }
+
+    let solver = Solver::new(game.clone());
+    let mut state = game.clone();
+    let mut shortened = Vec::with_capacity(moves.len());
+    let mut i = 0;
+
+    while i < moves.len() {
+        let mut best = (i + 1, moves[i]);
+        for mov in solver.get_moves(&state) {
+            let token = state.apply_unchecked(&mov);
+            if let Some(&j) = positions.get(&state.layout_key())
+                && j > best.0
+            {
+                best = (j, mov);
+            }
+            state.undo(token);
+        }
+
+        state.apply_unchecked(&best.1);
+        shortened.push(best.1);
+        i = best.0;
+    }
+
+    shortened
+}
+
+fn shorten_from(game: &Game, moves: &[Action], start: usize) -> Option<(Vec<Action>, usize)> {
+    // Position before each move
+    let mut states = Vec::with_capacity(moves.len());
+    let mut state = game.clone();
+    for mov in moves {
+        states.push(state.clone());
+        state.apply_unchecked(mov);
+    }
+
+    for i in start..moves.len() {
+        let Some(&lead) = states[i].cards_moved_by(&moves[i]).first() else {
+            continue;
+        };
+
+        // Next time this card moves, it must be carried the same way for the two moves to merge
+        let Some(j) =
+            (i + 1..moves.len()).find(|&k| states[k].cards_moved_by(&moves[k]).contains(&lead))
+        else {
+            continue;
+        };
+        if states[j].cards_moved_by(&moves[j]).first() != Some(&lead)
+            || moves[j].pile_size != moves[i].pile_size
+        {
+            continue;
+        }
+
+        let source = moves[i].source_location();
+        let dest = moves[j].dest_location();
+
+        if source == dest {
+            let mut candidate = moves.to_vec();
+            candidate.remove(j);
+            candidate.remove(i);
+            if replays_to_win(game, &candidate) {
+                return Some((candidate, i));
+            }
+            continue;
+        }
+
+        let Some(merged) = Action::between(source, dest, moves[i].pile_size) else {
+            continue;
+        };
+
+        // The direct move can happen when the card used to leave, or when it used to arrive
+        for position in [j, i] {
+            let mut candidate = moves.to_vec();
+            candidate[position] = merged;
+            candidate.remove(if position == j { i } else { j });
+            if replays_to_win(game, &candidate) {
+                return Some((candidate, i));
+            }
+        }
+    }
+
+    None
+}
+
+// Collapse runs of single-card moves whose net effect is one legal column-to-column move
+// of several cards, the way a human would play it.
+pub fn merge_supermoves(game: &Game, moves: &[Action]) -> Vec<SolutionStep> {
+    let mut steps = Vec::with_capacity(moves.len());
+    let mut state = game.clone();
+    let mut i = 0;
+
+    while i < moves.len() {
+        match find_supermove(&state, &moves[i..]) {
+            Some((action, len)) => {
+                state.apply_unchecked(&action);
+                steps.push(SolutionStep {
+                    action,
+                    expanded: moves[i..i + len].to_vec(),
+                });
+                i += len;
+            }
+            None => {
+                state.apply_unchecked(&moves[i]);
+                steps.push(SolutionStep {
+                    action: moves[i],
+                    expanded: vec![moves[i]],
+                });
+                i += 1;
+            }
+        }
+    }
+
+    steps
+}
+
+// Longest prefix of `moves` that is equivalent to a single supermove from `state`
+fn find_supermove(state: &Game, moves: &[Action]) -> Option<(Action, usize)> {
+    let Location::Column(source) = moves.first()?.source_location() else {
+        return None;
+    };
+
+    let mut after = state.clone();
+    let mut best = None;
+    for (len, mov) in moves.iter().take(MAX_SUPERMOVE_WINDOW).enumerate() {
+        if mov.pile_size != 1 || matches!(mov.dest_location(), Location::Foundation(_)) {
+            break;
+        }
+        after.apply_unchecked(mov);
+
+        let Location::Column(dest) = mov.dest_location() else {
+            continue;
+        };
+        if len == 0 || dest == source {
+            continue;
+        }
+        let Some(pile_size) = after.columns[dest]
+            .len()
+            .checked_sub(state.columns[dest].len())
+        else {
+            continue;
+        };
+
+        let action = Action {
+            action_type: ActionType::ColToCol,
+            source,
+            dest,
+            pile_size,
+        };
+        let mut merged = state.clone();
+        if pile_size > 1
+            && merged.apply(&action).is_ok()
+            && merged.layout_key() == after.layout_key()
+        {
+            best = Some((action, len + 1));
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::card::{Card, Suit};
+    use crate::solver::Strategy;
+
+    #[test]
+    fn test_remove_wasted_moves_keeps_a_winning_solution() {
+        let deck: Vec<Card> = (0..52)
+            .map(|i| Card {
+                rank: 13 - (i % 13),
+                suit: Suit::ALL[(i / 13) as usize],
+            })
+            .collect();
+        let game = Game::new(&deck);
+
+        let mut solver = Solver::new(game.clone());
+        solver.strategy = Strategy::Dfs;
+        let solution = solver.solve(100000).unwrap();
+        let optimized = remove_wasted_moves(&game, &solution);
+
+        assert!(replays_to_win(&game, &solution));
+        assert!(replays_to_win(&game, &optimized));
+        assert!(optimized.len() <= solution.len());
+    }
+
+    #[test]
+    fn test_merge_supermoves_collapses_card_by_card_move() {
+        let mut game = Game::new(&[]);
+        game.columns[0] = vec![
+            Card::try_from("9C").unwrap(),
+            Card::try_from("5S").unwrap(),
+            Card::try_from("4H").unwrap(),
+        ];
+        game.columns[1] = vec![Card::try_from("6D").unwrap()];
+
+        let moves = [
+            Action::between(Location::Column(0), Location::Freecell(0), 1).unwrap(),
+            Action::between(Location::Column(0), Location::Column(1), 1).unwrap(),
+            Action::between(Location::Freecell(0), Location::Column(1), 1).unwrap(),
+        ];
+        let steps = merge_supermoves(&game, &moves);
+
+        assert_eq!(steps.len(), 1);
+        assert_eq!(
+            steps[0].action,
+            Action::between(Location::Column(0), Location::Column(1), 2).unwrap()
+        );
+        assert_eq!(steps[0].expanded, moves);
+    }
+}
@@ -0,0 +1,161 @@
+use serde_json::{Value, json};
+
+use crate::batch::{BatchOptions, solve_game};
+use crate::game::Game;
+use crate::i18n::Msg;
+use crate::solver::Strategy;
+
+const GRADES: [Msg<'static>; 5] = [
+    Msg::GradeVeryEasy,
+    Msg::GradeEasy,
+    Msg::GradeMedium,
+    Msg::GradeHard,
+    Msg::GradeVeryHard,
+];
+
+#[derive(Debug, Clone)]
+pub struct Rating {
+    // 1 (very easy) to 5 (very hard or not solved within the limits)
+    pub grade: u8,
+    // Shortest solution found by any strategy
+    pub moves: Option<usize>,
+    // Fewest nodes any strategy needed, and which one
+    pub nodes: Option<(Strategy, u64)>,
+    // Fewest freecells the best strategy still solves the deal with
+    pub min_cells: Option<usize>,
+    pub reasons: Vec<String>,
+}
+
+impl Rating {
+    pub fn label(&self) -> String {
+        GRADES[self.grade as usize - 1].to_string()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "grade": self.grade,
+            "label": self.label(),
+            "moves": self.moves,
+            "nodes": self.nodes.map(|(_, nodes)| nodes),
+            "strategy": self.nodes.map(|(strategy, _)| strategy.to_string()),
+            "min_cells": self.min_cells,
+            "reasons": self.reasons,
+        })
+    }
+}
+
+fn moves_points(moves: usize) -> u8 {
+    match moves {
+        0..=90 => 0,
+        91..=110 => 1,
+        _ => 2,
+    }
+}
+
+fn nodes_points(nodes: u64) -> u8 {
+    match nodes {
+        0..1_000 => 0,
+        1_000..20_000 => 1,
+        20_000..200_000 => 2,
+        _ => 3,
+    }
+}
+
+fn cells_points(cells: usize) -> u8 {
+    cells.saturating_sub(1) as u8
+}
+
+// Solves the deal with every strategy, then with fewer and fewer freecells. Each
+// criterion scores points, 0 to 8 in total, mapped onto the 1-5 grade.
+pub fn rate_game(game: &Game, options: BatchOptions) -> Rating {
+    let runs: Vec<_> = Strategy::ALL
+        .iter()
+        .map(|&strategy| {
+            solve_game(
+                "",
+                game,
+                BatchOptions {
+                    strategy,
+                    ..options
+                },
+            )
+            .0
+        })
+        .filter(|result| result.solved())
+        .collect();
+
+    let Some(best) = runs.iter().min_by_key(|result| result.nodes) else {
+        return Rating {
+            grade: 5,
+            moves: None,
+            nodes: None,
+            min_cells: None,
+            reasons: vec![Msg::RateUnsolved(options.max_nodes).to_string()],
+        };
+    };
+    let moves = runs
+        .iter()
+        .filter_map(|r| r.moves)
+        .min()
+        .unwrap_or_default();
+
+    let options = BatchOptions {
+        strategy: best.strategy,
+        ..options
+    };
+    let min_cells = (0..game.cells)
+        .rev()
+        .take_while(|&cells| {
+            solve_game("", &game.clone().with_cells(cells), options)
+                .0
+                .solved()
+        })
+        .last()
+        .unwrap_or(game.cells);
+
+    let points = moves_points(moves) + nodes_points(best.nodes) + cells_points(min_cells);
+    Rating {
+        grade: 1 + points.div_ceil(2).min(4),
+        moves: Some(moves),
+        nodes: Some((best.strategy, best.nodes)),
+        min_cells: Some(min_cells),
+        reasons: vec![
+            Msg::RateMoves(moves).to_string(),
+            Msg::RateNodes(best.nodes, &best.strategy).to_string(),
+            Msg::RateCells(min_cells, game.cells).to_string(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+    use crate::solver::HeuristicWeights;
+
+    #[test]
+    fn test_rate_game() {
+        let options = BatchOptions {
+            strategy: Strategy::AStar,
+            max_nodes: 50_000,
+            time_limit: None,
+            weights: HeuristicWeights::default(),
+            optimize: true,
+            rate: false,
+        };
+        let rating = rate_game(&Game::new(&ms_deal(164)), options);
+        assert!((1..=5).contains(&rating.grade));
+        assert!(rating.min_cells.unwrap() <= 4);
+        assert_eq!(rating.reasons.len(), 3);
+
+        let stuck = rate_game(
+            &Game::new(&ms_deal(11982)),
+            BatchOptions {
+                max_nodes: 100,
+                ..options
+            },
+        );
+        assert_eq!((stuck.grade, stuck.label().as_str()), (5, "très difficile"));
+    }
+}
@@ -0,0 +1,192 @@
+#[cfg(feature = "png")]
+use image::{Rgba, RgbaImage};
+
+use crate::card::{Card, Suit};
+use crate::game::Game;
+
+// Layout shared with the SVG exporter
+pub const CARD_WIDTH: u32 = 44;
+pub const CARD_HEIGHT: u32 = 60;
+pub const GAP: u32 = 8;
+pub const MARGIN: u32 = 12;
+// Vertical offset between two cards of the same column
+pub const OVERLAP: u32 = 20;
+pub const COLUMNS_Y: u32 = MARGIN + CARD_HEIGHT + 2 * GAP;
+pub const BOARD_WIDTH: u32 = 2 * MARGIN + 8 * CARD_WIDTH + 7 * GAP;
+#[cfg(feature = "png")]
+const GLYPH_SCALE: u32 = 2;
+
+#[cfg(feature = "png")]
+const FELT: Rgba<u8> = Rgba([27, 94, 32, 255]);
+#[cfg(feature = "png")]
+const SLOT: Rgba<u8> = Rgba([67, 140, 72, 255]);
+#[cfg(feature = "png")]
+const CARD_FACE: Rgba<u8> = Rgba([250, 250, 245, 255]);
+#[cfg(feature = "png")]
+const CARD_EDGE: Rgba<u8> = Rgba([60, 60, 60, 255]);
+#[cfg(feature = "png")]
+const RED: Rgba<u8> = Rgba([200, 20, 30, 255]);
+#[cfg(feature = "png")]
+const BLACK: Rgba<u8> = Rgba([20, 20, 20, 255]);
+
+// 5x7 bitmap glyphs, one byte per row, the 5 low bits are the pixels
+#[cfg(feature = "png")]
+fn glyph(c: char) -> [u8; 7] {
+    match c {
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1E, 0x01, 0x01, 0x0E, 0x01, 0x01, 0x1E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        '♦' => [0x04, 0x0E, 0x1F, 0x0E, 0x04, 0x00, 0x00],
+        '♣' => [0x0E, 0x0E, 0x1F, 0x1F, 0x04, 0x0E, 0x00],
+        '♠' => [0x04, 0x0E, 0x1F, 0x1F, 0x04, 0x0E, 0x00],
+        '♥' => [0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00],
+        _ => [0; 7],
+    }
+}
+
+pub fn suit_char(suit: Suit) -> char {
+    match suit {
+        Suit::Diamond => '♦',
+        Suit::Club => '♣',
+        Suit::Spade => '♠',
+        Suit::Heart => '♥',
+    }
+}
+
+pub fn rank_text(card: &Card) -> String {
+    match card.rank {
+        10 => "10".to_string(),
+        _ => card.rank_char().to_string(),
+    }
+}
+
+#[cfg(feature = "png")]
+fn fill_rect(img: &mut RgbaImage, x: u32, y: u32, w: u32, h: u32, color: Rgba<u8>) {
+    for py in y..(y + h).min(img.height()) {
+        for px in x..(x + w).min(img.width()) {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+#[cfg(feature = "png")]
+fn outline_rect(img: &mut RgbaImage, x: u32, y: u32, w: u32, h: u32, color: Rgba<u8>) {
+    fill_rect(img, x, y, w, 1, color);
+    fill_rect(img, x, y + h - 1, w, 1, color);
+    fill_rect(img, x, y, 1, h, color);
+    fill_rect(img, x + w - 1, y, 1, h, color);
+}
+
+// Returns the x coordinate after the text
+#[cfg(feature = "png")]
+fn draw_text(img: &mut RgbaImage, text: &str, x: u32, y: u32, color: Rgba<u8>) -> u32 {
+    let mut cursor = x;
+    for c in text.chars() {
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..5 {
+                if bits & (0x10 >> col) != 0 {
+                    fill_rect(
+                        img,
+                        cursor + col * GLYPH_SCALE,
+                        y + row as u32 * GLYPH_SCALE,
+                        GLYPH_SCALE,
+                        GLYPH_SCALE,
+                        color,
+                    );
+                }
+            }
+        }
+        cursor += 6 * GLYPH_SCALE;
+    }
+    cursor
+}
+
+#[cfg(feature = "png")]
+fn draw_card(img: &mut RgbaImage, card: &Card, x: u32, y: u32) {
+    fill_rect(img, x, y, CARD_WIDTH, CARD_HEIGHT, CARD_FACE);
+    outline_rect(img, x, y, CARD_WIDTH, CARD_HEIGHT, CARD_EDGE);
+
+    let color = if card.is_black() { BLACK } else { RED };
+    let after_rank = draw_text(img, &rank_text(card), x + 3, y + 3, color);
+    draw_text(
+        img,
+        &suit_char(card.suit).to_string(),
+        after_rank,
+        y + 3,
+        color,
+    );
+}
+
+pub fn slot_x(index: u32) -> u32 {
+    MARGIN + index * (CARD_WIDTH + GAP)
+}
+
+pub fn board_height(game: &Game) -> u32 {
+    let max_rows = game.columns.iter().map(Vec::len).max().unwrap_or(0).max(1) as u32;
+    COLUMNS_Y + (max_rows - 1) * OVERLAP + CARD_HEIGHT + MARGIN
+}
+
+#[cfg(feature = "png")]
+pub fn render_game(game: &Game) -> RgbaImage {
+    let mut img = RgbaImage::from_pixel(BOARD_WIDTH, board_height(game), FELT);
+
+    // Top row: freecells on the left, foundations on the right
+    for (i, cell) in game.freecells.iter().enumerate() {
+        let x = slot_x(i as u32);
+        match cell {
+            Some(card) => draw_card(&mut img, card, x, MARGIN),
+            None => outline_rect(&mut img, x, MARGIN, CARD_WIDTH, CARD_HEIGHT, SLOT),
+        }
+    }
+    for (i, &count) in game.foundations.iter().enumerate() {
+        let x = slot_x(4 + i as u32);
+        let suit = Suit::ALL[i];
+        if count > 0 {
+            draw_card(&mut img, &Card { rank: count, suit }, x, MARGIN);
+        } else {
+            outline_rect(&mut img, x, MARGIN, CARD_WIDTH, CARD_HEIGHT, SLOT);
+            draw_text(
+                &mut img,
+                &suit_char(suit).to_string(),
+                x + 3,
+                MARGIN + 3,
+                SLOT,
+            );
+        }
+    }
+
+    for (i, column) in game.columns.iter().enumerate() {
+        let x = slot_x(i as u32);
+        if column.is_empty() {
+            outline_rect(&mut img, x, COLUMNS_Y, CARD_WIDTH, CARD_HEIGHT, SLOT);
+        }
+        for (row, card) in column.iter().enumerate() {
+            draw_card(&mut img, card, x, COLUMNS_Y + row as u32 * OVERLAP);
+        }
+    }
+
+    img
+}
+
+#[cfg(feature = "png")]
+pub fn save_png(game: &Game, path: &str) -> Result<(), String> {
+    render_game(game)
+        .save(path)
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+#[cfg(not(feature = "png"))]
+pub fn save_png(_game: &Game, path: &str) -> Result<(), String> {
+    Err(format!("{}: built without the `png` feature", path))
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value;
+
+use crate::action::{Action, ActionType, Location};
+use crate::card::Card;
+use crate::display::BoardStyle;
+use crate::error::CardError;
+use crate::explain::explain;
+use crate::game::{Game, UndoToken};
+use crate::history::{HISTORY_FORMAT, HandHistory};
+use crate::i18n::Msg;
+use crate::notation;
+use crate::verify::verify_solution;
+
+fn parse_card(value: &Value) -> Result<Card, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("Expected a card code, got {}", value))?
+        .parse()
+        .map_err(|e: CardError| e.to_string())
+}
+
+pub(crate) fn parse_game(deal: &Value) -> Result<Game, String> {
+    let mut game = Game::new(&[]);
+
+    let columns = deal["columns"].as_array().ok_or("Missing deal.columns")?;
+    for (i, column) in columns.iter().enumerate().take(game.columns.len()) {
+        let cards = column.as_array().ok_or("A column is not an array")?;
+        game.columns[i] = cards.iter().map(parse_card).collect::<Result<_, _>>()?;
+    }
+
+    if let Some(freecells) = deal["freecells"].as_array() {
+        for (i, cell) in freecells.iter().enumerate().take(game.freecells.len()) {
+            game.freecells[i] = if cell.is_null() {
+                None
+            } else {
+                Some(parse_card(cell)?)
+            };
+        }
+    }
+
+    if let Some(foundations) = deal["foundations"].as_array() {
+        for (i, count) in foundations.iter().enumerate().take(game.foundations.len()) {
+            game.foundations[i] = count
+                .as_u64()
+                .filter(|&count| count <= 13)
+                .ok_or_else(|| format!("Invalid foundation count {}", count))?
+                as u8;
+        }
+    }
+
+    Ok(game)
+}
+
+pub(crate) fn parse_action(value: &Value) -> Result<Action, String> {
+    let action_type = match value["type"].as_str() {
+        Some("ColToFoundation") => ActionType::ColToFoundation,
+        Some("FreecellToFoundation") => ActionType::FreecellToFoundation,
+        Some("ColToFreecell") => ActionType::ColToFreecell,
+        Some("FreecellToCol") => ActionType::FreecellToCol,
+        Some("ColToCol") => ActionType::ColToCol,
+        _ => return Err(format!("Invalid move type in {}", value)),
+    };
+    let field = |name: &str| {
+        value[name]
+            .as_u64()
+            .map(|v| v as usize)
+            .ok_or_else(|| format!("Missing {} in {}", name, value))
+    };
+
+    let action = Action {
+        action_type,
+        source: field("source")?,
+        dest: field("dest")?,
+        pile_size: field("pile_size")?,
+    };
+    // Columns 0-7, freecells and foundations 0-3
+    let in_range = |location: Location| match location {
+        Location::Column(i) => i < 8,
+        Location::Freecell(i) | Location::Foundation(i) => i < 4,
+    };
+    if !in_range(action.source_location()) || !in_range(action.dest_location()) {
+        return Err(format!("Invalid source or dest in {}", value));
+    }
+    Ok(action)
+}
+
+// Read a solution written with `--output-format json`, or the moves of a hand history
+pub fn load_solution(path: &str) -> Result<(Game, Vec<Action>), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let json: Value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+    if json["format"] == HISTORY_FORMAT {
+        let history = HandHistory::from_json(&json)?;
+        let moves = history.moves();
+        return Ok((history.game, moves));
+    }
+
+    let game = parse_game(&json["deal"])?;
+    let actions = json["moves"]
+        .as_array()
+        .ok_or("Missing moves")?
+        .iter()
+        .map(parse_action)
+        .collect::<Result<Vec<_>, _>>()?;
+    // The stepper explains each move before playing it, on the position it expects
+    verify_solution(&game, &actions).map_err(|e| format!("{}: {}", path, e))?;
+
+    Ok((game, actions))
+}
+
+pub fn run_replay(game: Game, actions: &[Action], style: BoardStyle) {
+    let mut state = game;
+    let mut history: Vec<UndoToken> = Vec::new();
+    let stdin = io::stdin();
+
+    loop {
+        let position = history.len();
+        println!("{}", style.board(&state));
+        match actions.get(position) {
+            Some(next) => println!(
+                "{}",
+                Msg::ReplayMove(
+                    position + 1,
+                    actions.len(),
+                    &notation::to_standard(next),
+                    &explain(&state, next)
+                )
+            ),
+            None => println!("{}", Msg::ReplayEnd(actions.len())),
+        }
+        print!("{}", Msg::ReplayPrompt);
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+
+        let target = match line.trim() {
+            "" | "n" => position + 1,
+            "p" => position.saturating_sub(1),
+            "f" => 0,
+            "l" => actions.len(),
+            "q" => break,
+            other => match other.parse::<usize>() {
+                Ok(n) => n.saturating_sub(1),
+                Err(_) => {
+                    println!("{}", Msg::UnknownCommand(other));
+                    continue;
+                }
+            },
+        }
+        .min(actions.len());
+
+        while history.len() < target {
+            let action = &actions[history.len()];
+            match state.apply(action) {
+                Ok(token) => history.push(token),
+                Err(e) => {
+                    println!("{}", Msg::ReplayIllegal(history.len() + 1, &e));
+                    break;
+                }
+            }
+        }
+        while history.len() > target {
+            if let Some(token) = history.pop() {
+                state.undo(token);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_load_solution_rejects_bad_moves() {
+        let deal = json!({"columns": [["AS"], ["2S"]], "foundations": [0, 0, 0, 0]});
+        assert!(parse_game(&deal).is_ok());
+        assert!(parse_game(&json!({"columns": [], "foundations": [300, 0, 0, 0]})).is_err());
+        let action = |source, dest| json!({"type": "ColToCol", "source": source, "dest": dest, "pile_size": 1});
+        assert!(parse_action(&action(1, 0)).is_ok());
+        assert!(parse_action(&action(9, 0)).is_err());
+        assert!(parse_action(&action(1, 8)).is_err());
+
+        // In range but illegal: 2S does not go on AS
+        let path =
+            std::env::temp_dir().join(format!("freecell-replay-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            json!({"deal": deal, "moves": [action(1, 0)]}).to_string(),
+        )
+        .unwrap();
+        let error = load_solution(path.to_str().unwrap()).unwrap_err();
+        assert!(error.contains("move #1"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
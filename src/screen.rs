@@ -1,107 +1,890 @@
 use image::{ImageBuffer, RgbaImage};
-use rdev::{Button, Event, EventType, listen};
+use rdev::{Button, Event, EventType, Key, listen, simulate};
 use scrap::{Capturer, Display};
+use screenshots::display_info::DisplayInfo;
+use std::collections::HashSet;
+use std::io::{self, IsTerminal, Write};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use freecell::config::{AutomationSettings, CaptureKind, ScreenSettings};
+use freecell::detect::{detect_board, frame_difference};
+use freecell::error::OcrError;
+use freecell::i18n::Msg;
+
+// Where captures are saved with `[screen] save_captures`, the OCR reads them in memory
+pub const CAPTURE_PATH: &str = "capture.png";
 
 pub struct Screenshot {
+    // Screen the region is on, its index as in `[screen] display`
+    pub area: ScreenArea,
+    // Pixels of the capture on the frames of that screen
     pub x1: i32,
     pub y1: i32,
     pub x2: i32,
     pub y2: i32,
     pub img: RgbaImage,
+    // Region selected with the mouse, worth saving into the config
+    pub clicked: bool,
+}
+
+impl Screenshot {
+    // A pixel of the capture on the desktop, to point the mouse at it
+    pub fn to_desktop(&self, x: i32, y: i32) -> (f64, f64) {
+        self.area
+            .to_desktop(self.x1.min(self.x2) + x, self.y1.min(self.y2) + y)
+    }
 }
 
-fn capture_region(x1: i32, y1: i32, x2: i32, y2: i32) -> RgbaImage {
-    let display = Display::primary().unwrap();
-    let mut capturer = Capturer::new(display).unwrap();
-    let h = capturer.height();
+// Grabs a whole screen. Region selection, cropping and OCR only go through this
+// trait, so that backends for other platforms or display servers plug in here.
+pub trait CaptureBackend {
+    fn capture(&mut self) -> Result<RgbaImage, OcrError>;
+}
+
+// Longest wait between two attempts at grabbing a frame, the waits doubling from
+// `frame_retry_ms`
+const MAX_FRAME_WAIT: Duration = Duration::from_millis(500);
+
+// Pixels the pointer moves, button down, before a click becomes a drag
+const DRAG_DISTANCE: i32 = 4;
 
-    let frame = loop {
-        if let Ok(buffer) = capturer.frame() {
-            break buffer;
+// Errors of the capture libraries, with what to do about the usual ones
+fn capture_error(e: io::Error) -> OcrError {
+    let hint = match e.kind() {
+        io::ErrorKind::PermissionDenied => {
+            ", allow screen recording for this terminal (on macOS in System Settings > Privacy & Security)"
         }
-        thread::sleep(Duration::from_millis(10));
+        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => {
+            ", no display found: is a graphical session running?"
+        }
+        _ => "",
+    };
+    OcrError::Capture(format!("{}{}", e, hint))
+}
+
+// Failures after which a capturer opened again usually works: the display changed
+// mode, went to sleep or was locked
+fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::Interrupted
+    )
+}
+
+pub struct ScrapBackend {
+    capturer: Capturer,
+    display: Option<usize>,
+    retry: Duration,
+    timeout: Duration,
+}
+
+fn open_capturer(display: Option<usize>) -> Result<Capturer, OcrError> {
+    let display = match display {
+        None => Display::primary().map_err(capture_error)?,
+        Some(index) => Display::all()
+            .map_err(capture_error)?
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| OcrError::Capture(format!("no display number {}", index)))?,
     };
+    Capturer::new(display).map_err(capture_error)
+}
+
+impl ScrapBackend {
+    pub fn new(display: Option<usize>, automation: &AutomationSettings) -> Result<Self, OcrError> {
+        Ok(ScrapBackend {
+            capturer: open_capturer(display)?,
+            display,
+            retry: Duration::from_millis(automation.frame_retry_ms),
+            timeout: Duration::from_millis(automation.frame_timeout_ms),
+        })
+    }
+}
 
+impl CaptureBackend for ScrapBackend {
+    fn capture(&mut self) -> Result<RgbaImage, OcrError> {
+        let started = Instant::now();
+        let mut wait = self.retry;
+        let mut reopened = false;
+        loop {
+            let (width, height) = (self.capturer.width(), self.capturer.height());
+            if width == 0 || height == 0 {
+                return Err(OcrError::Capture(
+                    "the screen has no size, it may be asleep".to_string(),
+                ));
+            }
+            let failure = match self.capturer.frame() {
+                Ok(frame) => {
+                    // Rows may be padded past the screen width
+                    let stride = frame.len() / height;
+                    let mut img: RgbaImage = ImageBuffer::new(width as u32, height as u32);
+                    for (x, y, pixel) in img.enumerate_pixels_mut() {
+                        let idx = y as usize * stride + 4 * x as usize;
+                        if let Some(&[r, g, b, a]) = frame.get(idx..idx + 4) {
+                            *pixel = image::Rgba([r, g, b, a]);
+                        }
+                    }
+                    return Ok(img);
+                }
+                Err(e) => e,
+            };
+            match failure.kind() {
+                // No new frame yet
+                io::ErrorKind::WouldBlock => {}
+                _ if is_transient(&failure) && !reopened => {
+                    self.capturer = open_capturer(self.display)?;
+                    reopened = true;
+                }
+                _ => return Err(capture_error(failure)),
+            }
+            if started.elapsed() >= self.timeout {
+                return Err(OcrError::Capture(format!(
+                    "no new frame in {} ms, the screen may be asleep or locked",
+                    self.timeout.as_millis()
+                )));
+            }
+            thread::sleep(wait);
+            wait = (wait * 2).min(MAX_FRAME_WAIT);
+        }
+    }
+}
+
+// A screenshot of a whole screen each time, with the screenshots crate which talks
+// to the system rather than mapping its frame buffer. On Wayland it goes through
+// xdg-desktop-portal, where the mouse clicks cannot be heard: the region comes from
+// the config, the window or the board detection.
+pub struct ScreenshotsBackend {
+    screen: screenshots::Screen,
+}
+
+impl ScreenshotsBackend {
+    pub fn new(display: Option<usize>) -> Result<Self, OcrError> {
+        let screens = screenshots::Screen::all().map_err(|e| OcrError::Capture(e.to_string()))?;
+        let screen = match display {
+            Some(index) => screens.get(index),
+            None => screens.iter().find(|screen| screen.display_info.is_primary),
+        };
+        let screen = screen.or(screens.first()).copied().ok_or_else(|| {
+            OcrError::Capture(format!("no display number {}", display.unwrap_or_default()))
+        })?;
+        Ok(ScreenshotsBackend { screen })
+    }
+}
+
+impl CaptureBackend for ScreenshotsBackend {
+    fn capture(&mut self) -> Result<RgbaImage, OcrError> {
+        let shot = self
+            .screen
+            .capture()
+            .map_err(|e| OcrError::Capture(e.to_string()))?;
+        // Same pixels, from the older image version the crate uses
+        let (width, height) = shot.dimensions();
+        RgbaImage::from_raw(width, height, shot.into_raw())
+            .ok_or_else(|| OcrError::Capture("truncated screenshot".to_string()))
+    }
+}
+
+// Same test as the screenshots crate, which goes through the portal on these sessions
+fn is_wayland() -> bool {
+    let var = |name: &str| std::env::var(name).unwrap_or_default().to_lowercase();
+    var("XDG_SESSION_TYPE") == "wayland" || var("WAYLAND_DISPLAY").contains("wayland")
+}
+
+pub fn capture_backend(
+    screen: &ScreenSettings,
+    automation: &AutomationSettings,
+) -> Result<Box<dyn CaptureBackend>, OcrError> {
+    match screen.backend.resolve(is_wayland()) {
+        CaptureKind::Screenshots => Ok(Box::new(ScreenshotsBackend::new(screen.display)?)),
+        CaptureKind::Scrap | CaptureKind::Auto => {
+            Ok(Box::new(ScrapBackend::new(screen.display, automation)?))
+        }
+    }
+}
+
+// The part of `screen` between two corners, pixels off the screen stay transparent
+fn crop_region(screen: &RgbaImage, x1: i32, y1: i32, x2: i32, y2: i32) -> RgbaImage {
     let (x_min, x_max) = (x1.min(x2), x1.max(x2));
     let (y_min, y_max) = (y1.min(y2), y1.max(y2));
     let width = x_max - x_min;
     let height = y_max - y_min;
 
     let mut img: RgbaImage = ImageBuffer::new(width as u32, height as u32);
-
-    let stride = frame.len() / h;
-
-    for y in y_min..y_max {
-        for x in x_min..x_max {
-            let idx = y as usize * stride + 4 * x as usize;
-            if idx + 3 < frame.len() {
-                let pixel = image::Rgba([
-                    frame[idx],     // R
-                    frame[idx + 1], // G
-                    frame[idx + 2], // B
-                    frame[idx + 3], // A
-                ]);
-                img.put_pixel((x - x_min) as u32, (y - y_min) as u32, pixel);
+    for y in y_min.max(0)..y_max {
+        for x in x_min.max(0)..x_max {
+            if let Some(pixel) = screen.get_pixel_checked(x as u32, y as u32) {
+                img.put_pixel((x - x_min) as u32, (y - y_min) as u32, *pixel);
             }
         }
     }
+    img
+}
 
-    img.save("capture.png").unwrap();
-    println!("✅ Zone capturée sauvegardée dans `capture.png`");
+// Frames of the region until two in a row, `stable_ms` apart, look the same: deal and
+// move animations would otherwise be read half-drawn
+fn stable_region(
+    backend: &mut dyn CaptureBackend,
+    automation: &AutomationSettings,
+    [x1, y1, x2, y2]: [i32; 4],
+) -> Result<RgbaImage, OcrError> {
+    let mut frame = crop_region(&backend.capture()?, x1, y1, x2, y2);
+    for _ in 0..automation.stable_attempts {
+        thread::sleep(Duration::from_millis(automation.stable_ms));
+        let next = crop_region(&backend.capture()?, x1, y1, x2, y2);
+        if frame_difference(&frame, &next) <= automation.stable_tolerance {
+            return Ok(next);
+        }
+        frame = next;
+    }
+    Err(OcrError::Capture("the screen kept changing".to_string()))
+}
 
-    img
+fn capture_region(
+    backend: &mut dyn CaptureBackend,
+    screen: &ScreenSettings,
+    automation: &AutomationSettings,
+    x1: i32,
+    y1: i32,
+    x2: i32,
+    y2: i32,
+) -> Result<RgbaImage, OcrError> {
+    let img = stable_region(backend, automation, [x1, y1, x2, y2])?;
+    if screen.save_captures {
+        img.save(CAPTURE_PATH)
+            .map_err(|e| OcrError::Capture(e.to_string()))?;
+        println!("{}", Msg::CaptureSaved(CAPTURE_PATH));
+    }
+
+    Ok(img)
+}
+
+// A screen of the desktop, numbered as by `Display::all` which lists them in the
+// system's order too. Points of the desktop, clicks and windows, are in the logical
+// units of the system like rdev and display-info give them, and frames in pixels:
+// `scale` of them per unit on a HiDPI screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenArea {
+    pub index: Option<usize>,
+    pub x: i32,
+    pub y: i32,
+    pub scale: f64,
+}
+
+impl ScreenArea {
+    // A point of the desktop on the frames of this screen
+    fn to_pixels(self, x: i32, y: i32) -> (i32, i32) {
+        let pixels = |units: i32| (units as f64 * self.scale).round() as i32;
+        (pixels(x - self.x), pixels(y - self.y))
+    }
+
+    // A pixel of the frames of this screen on the desktop, where the mouse goes
+    pub fn to_desktop(self, x: i32, y: i32) -> (f64, f64) {
+        (
+            self.x as f64 + x as f64 / self.scale,
+            self.y as f64 + y as f64 / self.scale,
+        )
+    }
+}
+
+fn screen_area(screen: &ScreenSettings, index: usize, info: &DisplayInfo) -> ScreenArea {
+    let scale = screen.scale.unwrap_or(info.scale_factor as f64);
+    ScreenArea {
+        index: Some(index),
+        x: info.x,
+        y: info.y,
+        scale: if scale > 0.0 { scale } else { 1.0 },
+    }
+}
+
+// The screen holding a point of the desktop
+fn screen_at(screen: &ScreenSettings, x: i32, y: i32) -> Option<ScreenArea> {
+    let screens = screenshots::Screen::all().ok()?;
+    screens.iter().enumerate().find_map(|(index, found)| {
+        let info = found.display_info;
+        let inside = (info.x..info.x + info.width as i32).contains(&x)
+            && (info.y..info.y + info.height as i32).contains(&y);
+        inside.then(|| screen_area(screen, index, &info))
+    })
+}
+
+// The screen captured when no point tells which: `[screen] display`, the primary one
+// otherwise
+fn default_screen(screen: &ScreenSettings) -> ScreenArea {
+    let screens = screenshots::Screen::all().unwrap_or_default();
+    let found = match screen.display {
+        Some(index) => screens.get(index).map(|found| (index, found)),
+        None => screens
+            .iter()
+            .enumerate()
+            .find(|(_, found)| found.display_info.is_primary),
+    };
+    match found {
+        Some((index, found)) => ScreenArea {
+            index: screen.display,
+            ..screen_area(screen, index, &found.display_info)
+        },
+        None => ScreenArea {
+            index: screen.display,
+            x: 0,
+            y: 0,
+            scale: screen.scale.unwrap_or(1.0),
+        },
+    }
+}
+
+// The client area of the first visible window with `title` in its title, as
+// [x1, y1, x2, y2] on the whole desktop. xdotool reads it from the X server, window
+// decorations excluded.
+fn find_window(title: &str) -> Result<[i32; 4], OcrError> {
+    let xdotool = |args: &[&str]| {
+        let output = Command::new("xdotool")
+            .args(args)
+            .output()
+            .map_err(|e| OcrError::Capture(format!("xdotool: {}", e)))?;
+        match output.status.success() {
+            true => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+            false => Err(OcrError::Capture(format!("no window titled \"{}\"", title))),
+        }
+    };
+    // --name takes a regex, escaped so that the title is matched as written
+    let pattern: String = title
+        .chars()
+        .flat_map(|c| match c.is_alphanumeric() || c == ' ' {
+            true => vec![c],
+            false => vec!['\\', c],
+        })
+        .collect();
+    let found = xdotool(&["search", "--onlyvisible", "--name", &pattern])?;
+    let id = found
+        .lines()
+        .next()
+        .ok_or_else(|| OcrError::Capture(format!("no window titled \"{}\"", title)))?;
+
+    // Lines of KEY=value
+    let shell = xdotool(&["getwindowgeometry", "--shell", id])?;
+    let value = |key: &str| {
+        shell.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            (name == key).then(|| value.trim().parse::<i32>().ok())?
+        })
+    };
+    match (value("X"), value("Y"), value("WIDTH"), value("HEIGHT")) {
+        (Some(x), Some(y), Some(width), Some(height)) => Ok([x, y, x + width, y + height]),
+        _ => Err(OcrError::Capture(format!("no geometry for window {}", id))),
+    }
+}
+
+// The window's client area on the screen showing its top left corner
+fn window_region(screen: &ScreenSettings, title: &str) -> Result<(ScreenArea, [i32; 4]), OcrError> {
+    let [x1, y1, x2, y2] = find_window(title)?;
+    let area = screen_at(screen, x1, y1).unwrap_or_else(|| default_screen(screen));
+    let ((x1, y1), (x2, y2)) = (area.to_pixels(x1, y1), area.to_pixels(x2, y2));
+    Ok((area, [x1, y1, x2, y2]))
 }
 
-#[allow(dead_code)]
-pub fn start_screenshot() -> Screenshot {
+pub fn start_screenshot(
+    screen: &ScreenSettings,
+    automation: &AutomationSettings,
+) -> Result<Screenshot, OcrError> {
+    // The game window first, found again on each capture so that moving it is fine
+    if let Some(title) = &screen.window {
+        let (area, [x1, y1, x2, y2]) = window_region(screen, title)?;
+        println!("{}", Msg::WindowFound(title, x1, y1, x2, y2));
+        let on_screen = ScreenSettings {
+            display: area.index,
+            ..screen.clone()
+        };
+        let mut backend = capture_backend(&on_screen, automation)?;
+        return Ok(Screenshot {
+            area,
+            x1,
+            y1,
+            x2,
+            y2,
+            img: capture_region(backend.as_mut(), screen, automation, x1, y1, x2, y2)?,
+            clicked: false,
+        });
+    }
+
+    let mut backend = capture_backend(screen, automation)?;
+
+    // Region saved in the config: no need to ask for it
+    if let Some([x1, y1, x2, y2]) = screen.region {
+        return Ok(Screenshot {
+            area: default_screen(screen),
+            x1,
+            y1,
+            x2,
+            y2,
+            img: capture_region(backend.as_mut(), screen, automation, x1, y1, x2, y2)?,
+            clicked: false,
+        });
+    }
+
+    // Then the board found on the screen, the clicks being the last resort
+    if screen.detect
+        && let Ok(board) = detect_board(&backend.capture()?)
+    {
+        let [x1, y1, x2, y2] = board.region;
+        println!("{}", Msg::BoardFound(x1, y1, x2, y2));
+        return Ok(Screenshot {
+            area: default_screen(screen),
+            x1,
+            y1,
+            x2,
+            y2,
+            img: capture_region(backend.as_mut(), screen, automation, x1, y1, x2, y2)?,
+            clicked: false,
+        });
+    }
+
     let click_points: Arc<Mutex<Vec<(i32, i32)>>> = Arc::new(Mutex::new(vec![]));
     let click_points_clone = Arc::clone(&click_points);
     let current_pos: Arc<Mutex<(f64, f64)>> = Arc::new(Mutex::new((0.0, 0.0)));
     let current_pos_clone = Arc::clone(&current_pos);
 
-    println!("🖱️ Cliquez deux fois pour définir la zone à capturer...");
+    println!("{}", Msg::ClickTwice);
 
-    thread::spawn(move || {
-        let _ = listen(move |event: Event| {
+    let listener = thread::spawn(move || {
+        listen(move |event: Event| {
             match event.event_type {
                 EventType::MouseMove { x, y } => {
                     // Track current mouse position
-                    let mut pos = current_pos_clone.lock().unwrap();
-                    *pos = (x, y);
+                    if let Ok(mut pos) = current_pos_clone.lock() {
+                        *pos = (x, y);
+                    }
                 }
                 EventType::ButtonPress(button) => {
-                    if button == Button::Left {
-                        // Capture current position when left click occurs
-                        let pos = current_pos_clone.lock().unwrap();
-                        let mut points = click_points_clone.lock().unwrap();
+                    // Capture current position when left click occurs
+                    if button == Button::Left
+                        && let (Ok(pos), Ok(mut points)) =
+                            (current_pos_clone.lock(), click_points_clone.lock())
+                    {
                         points.push((pos.0 as i32, pos.1 as i32));
-                        println!("📍 Clic à : ({}, {})", pos.0 as i32, pos.1 as i32);
+                    }
+                }
+                EventType::ButtonRelease(button) => {
+                    // The end of a drag is the other corner, a release where the
+                    // button went down is a plain click
+                    if button == Button::Left
+                        && let (Ok(pos), Ok(mut points)) =
+                            (current_pos_clone.lock(), click_points_clone.lock())
+                        && let Some(&(x, y)) = points.last()
+                    {
+                        let end = (pos.0 as i32, pos.1 as i32);
+                        if (end.0 - x).abs() > DRAG_DISTANCE || (end.1 - y).abs() > DRAG_DISTANCE {
+                            points.push(end);
+                        }
                     }
                 }
                 _ => {}
             }
-        });
+        })
     });
 
-    // Attendre que le thread se termine (c’est-à-dire 2 clics)
+    // Attendre que le thread se termine (c’est-à-dire 2 coins)
+    let mut announced = 0;
+    let mut shown = None;
     loop {
-        {
-            let points = click_points.lock().unwrap();
-            if points.len() == 2 {
-                let (x1, y1) = points[0];
-                let (x2, y2) = points[1];
-                return Screenshot {
-                    x1,
-                    y1,
-                    x2,
-                    y2,
-                    img: capture_region(x1, y1, x2, y2),
-                };
+        let clicks = click_points
+            .lock()
+            .map(|points| points.clone())
+            .map_err(|_| OcrError::Capture("mouse listener crashed".to_string()))?;
+        for &(x, y) in clicks.iter().skip(announced) {
+            println!("\r{}", Msg::ClickAt(x, y));
+        }
+        announced = clicks.len();
+
+        if let [(x1, y1), (x2, y2), ..] = clicks[..] {
+            // Clicks are on the whole desktop, captures of a single screen
+            let area = match (screen_at(screen, x1, y1), screen_at(screen, x2, y2)) {
+                (Some(area), Some(other)) if area.index == other.index => {
+                    println!("{}", Msg::DisplayFound(area.index.unwrap_or_default()));
+                    let on_screen = ScreenSettings {
+                        display: area.index,
+                        ..screen.clone()
+                    };
+                    backend = capture_backend(&on_screen, automation)?;
+                    area
+                }
+                _ => default_screen(screen),
+            };
+            let ((x1, y1), (x2, y2)) = (area.to_pixels(x1, y1), area.to_pixels(x2, y2));
+            let shot = Screenshot {
+                area,
+                x1,
+                y1,
+                x2,
+                y2,
+                img: capture_region(backend.as_mut(), screen, automation, x1, y1, x2, y2)?,
+                clicked: true,
+            };
+            if keep_region(&shot) {
+                return Ok(shot);
             }
+            if let Ok(mut points) = click_points.lock() {
+                points.clear();
+            }
+            (announced, shown) = (0, None);
+            println!("{}", Msg::ClickTwice);
+            continue;
         }
-        thread::sleep(Duration::from_millis(100));
+
+        // Where the pointer is, and the rectangle it draws from the first corner
+        let pos = current_pos
+            .lock()
+            .map(|pos| (pos.0 as i32, pos.1 as i32))
+            .unwrap_or_default();
+        if shown != Some(pos) {
+            shown = Some(pos);
+            let feedback = match clicks.first() {
+                Some(&(x, y)) => Msg::SelectingArea(x, y, pos.0, pos.1).to_string(),
+                None => Msg::PointerAt(pos.0, pos.1).to_string(),
+            };
+            // Trailing spaces wipe what a longer line left
+            print!("\r{}    ", feedback);
+            let _ = io::stdout().flush();
+        }
+        // The listener only returns when it could not hook the mouse
+        if listener.is_finished() {
+            return Err(OcrError::Capture(
+                "could not listen to mouse clicks".to_string(),
+            ));
+        }
+        thread::sleep(Duration::from_millis(automation.click_poll_ms));
+    }
+}
+
+// Asks whether the selected area is the right one, kept without asking out of a
+// terminal
+fn keep_region(shot: &Screenshot) -> bool {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return true;
+    }
+    print!("{}", Msg::KeepArea(shot.img.width(), shot.img.height()));
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if stdin.read_line(&mut answer).is_err() {
+        return true;
+    }
+    !answer.trim().to_lowercase().starts_with('n')
+}
+
+// Captures the region of a first screenshot again and again
+pub struct RegionWatch {
+    backend: Box<dyn CaptureBackend>,
+    automation: AutomationSettings,
+    area: ScreenArea,
+    region: [i32; 4],
+    // Follows the window of `[screen] window` when set
+    screen: ScreenSettings,
+}
+
+impl RegionWatch {
+    pub fn new(
+        first: &Screenshot,
+        screen: &ScreenSettings,
+        automation: &AutomationSettings,
+    ) -> Result<Self, OcrError> {
+        let on_screen = ScreenSettings {
+            display: first.area.index,
+            ..screen.clone()
+        };
+        Ok(RegionWatch {
+            backend: capture_backend(&on_screen, automation)?,
+            automation: automation.clone(),
+            area: first.area,
+            region: [first.x1, first.y1, first.x2, first.y2],
+            screen: screen.clone(),
+        })
+    }
+
+    // Silent, unlike the first capture: this runs every few seconds. The screenshot
+    // holds the region it was taken from, where the window was at that time.
+    pub fn capture(&mut self) -> Result<Screenshot, OcrError> {
+        if let Some(title) = &self.screen.window {
+            (self.area, self.region) = window_region(&self.screen, title)?;
+        }
+        let img = stable_region(self.backend.as_mut(), &self.automation, self.region)?;
+        if self.screen.save_captures {
+            img.save(CAPTURE_PATH)
+                .map_err(|e| OcrError::Capture(e.to_string()))?;
+        }
+        let [x1, y1, x2, y2] = self.region;
+        Ok(Screenshot {
+            area: self.area,
+            x1,
+            y1,
+            x2,
+            y2,
+            img,
+            clicked: false,
+        })
+    }
+}
+
+// Keys a hotkey can end with, named after their rdev variant without its Key or Num
+// prefix: "s", "5", "f9", "space"
+const HOTKEYS: [Key; 52] = [
+    Key::KeyA,
+    Key::KeyB,
+    Key::KeyC,
+    Key::KeyD,
+    Key::KeyE,
+    Key::KeyF,
+    Key::KeyG,
+    Key::KeyH,
+    Key::KeyI,
+    Key::KeyJ,
+    Key::KeyK,
+    Key::KeyL,
+    Key::KeyM,
+    Key::KeyN,
+    Key::KeyO,
+    Key::KeyP,
+    Key::KeyQ,
+    Key::KeyR,
+    Key::KeyS,
+    Key::KeyT,
+    Key::KeyU,
+    Key::KeyV,
+    Key::KeyW,
+    Key::KeyX,
+    Key::KeyY,
+    Key::KeyZ,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::Space,
+    Key::Return,
+    Key::Insert,
+    Key::Pause,
+];
+
+// A key pressed with some modifiers, as "ctrl+shift+s"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    key: Key,
+}
+
+impl FromStr for Hotkey {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (mut ctrl, mut shift, mut alt, mut key) = (false, false, false, None);
+        for part in text.split('+').map(|part| part.trim().to_lowercase()) {
+            match part.as_str() {
+                "ctrl" | "control" => ctrl = true,
+                "shift" => shift = true,
+                "alt" => alt = true,
+                name => {
+                    let named = HOTKEYS.into_iter().find(|candidate| {
+                        let variant = format!("{:?}", candidate).to_lowercase();
+                        variant
+                            .strip_prefix("key")
+                            .or(variant.strip_prefix("num"))
+                            .unwrap_or(&variant)
+                            == name
+                    });
+                    key = Some(named.ok_or_else(|| format!("unknown key {}", name))?);
+                }
+            }
+        }
+        let key = key.ok_or_else(|| format!("no key in {}", text))?;
+        Ok(Hotkey {
+            ctrl,
+            shift,
+            alt,
+            key,
+        })
+    }
+}
+
+impl Hotkey {
+    fn is_pressed(&self, key: Key, held: &HashSet<Key>) -> bool {
+        let any = |keys: &[Key]| keys.iter().any(|key| held.contains(key));
+        key == self.key
+            && any(&[Key::ControlLeft, Key::ControlRight]) == self.ctrl
+            && any(&[Key::ShiftLeft, Key::ShiftRight]) == self.shift
+            && any(&[Key::Alt, Key::AltGr]) == self.alt
+    }
+}
+
+// Runs `action` on this thread each time the hotkey is pressed, the listener thread
+// only passing the presses on. Only returns when the keyboard could not be hooked.
+pub fn on_hotkey(hotkey: Hotkey, mut action: impl FnMut()) -> OcrError {
+    let (sender, presses) = mpsc::channel();
+    let listener = thread::spawn(move || {
+        let mut held = HashSet::new();
+        listen(move |event: Event| match event.event_type {
+            EventType::KeyPress(key) => {
+                held.insert(key);
+                if hotkey.is_pressed(key, &held) {
+                    let _ = sender.send(());
+                }
+            }
+            EventType::KeyRelease(key) => {
+                held.remove(&key);
+            }
+            _ => {}
+        })
+    });
+    loop {
+        match presses.recv_timeout(Duration::from_millis(100)) {
+            Ok(()) => action(),
+            Err(RecvTimeoutError::Timeout) if !listener.is_finished() => {}
+            _ => return OcrError::Capture("could not listen to the keyboard".to_string()),
+        }
+    }
+}
+
+// Steps of the pointer between the press and the release of a drag, for the clients
+// that follow the pointer rather than jump to where it is released
+const DRAG_STEPS: u32 = 10;
+
+// Drags with the left button from one point of the desktop to another, as a player
+// would move a card
+pub fn drag(from: (f64, f64), to: (f64, f64)) -> Result<(), OcrError> {
+    let send = |event: EventType| {
+        simulate(&event).map_err(|_| OcrError::Capture("could not move the mouse".to_string()))?;
+        // Some systems drop events sent too close together
+        thread::sleep(Duration::from_millis(20));
+        Ok(())
+    };
+    send(EventType::MouseMove {
+        x: from.0,
+        y: from.1,
+    })?;
+    send(EventType::ButtonPress(Button::Left))?;
+    for step in 1..=DRAG_STEPS {
+        let along = step as f64 / DRAG_STEPS as f64;
+        send(EventType::MouseMove {
+            x: from.0 + (to.0 - from.0) * along,
+            y: from.1 + (to.1 - from.1) * along,
+        })?;
+    }
+    send(EventType::ButtonRelease(Button::Left))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A screen right of a 1920 units wide one, a little lower
+    fn second_screen(scale: f64) -> ScreenArea {
+        ScreenArea {
+            index: Some(1),
+            x: 1920,
+            y: 120,
+            scale,
+        }
+    }
+
+    #[test]
+    fn test_screen_area() {
+        for scale in [1.0, 1.5, 2.0] {
+            let area = second_screen(scale);
+            assert_eq!(area.to_pixels(1920, 120), (0, 0));
+            let hundred = (100.0 * scale) as i32;
+            assert_eq!(area.to_pixels(2020, 220), (hundred, hundred));
+            assert_eq!(area.to_desktop(0, 0), (1920.0, 120.0));
+            assert_eq!(area.to_desktop(hundred, hundred), (2020.0, 220.0));
+
+            // Back where it was, give or take the half pixel rounded off
+            for (x, y) in [(1921, 121), (2021, 333), (3839, 1199)] {
+                let (px, py) = area.to_pixels(x, y);
+                let (dx, dy) = area.to_desktop(px, py);
+                assert!(
+                    (dx - x as f64).abs() <= 0.5 / scale + 1e-9,
+                    "{x} at {scale}"
+                );
+                assert!(
+                    (dy - y as f64).abs() <= 0.5 / scale + 1e-9,
+                    "{y} at {scale}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_screenshot_to_desktop() {
+        // Selected from the bottom right corner to the top left one
+        let shot = Screenshot {
+            area: second_screen(2.0),
+            x1: 300,
+            y1: 200,
+            x2: 100,
+            y2: 50,
+            img: RgbaImage::new(200, 150),
+            clicked: true,
+        };
+        assert_eq!(shot.to_desktop(0, 0), (1970.0, 145.0));
+        assert_eq!(shot.to_desktop(10, 20), (1975.0, 155.0));
+    }
+
+    #[test]
+    fn test_hotkey_from_str() {
+        let hotkey = |ctrl, shift, alt, key| Hotkey {
+            ctrl,
+            shift,
+            alt,
+            key,
+        };
+        assert_eq!(
+            "ctrl+shift+s".parse::<Hotkey>(),
+            Ok(hotkey(true, true, false, Key::KeyS))
+        );
+        assert_eq!(
+            "F9".parse::<Hotkey>(),
+            Ok(hotkey(false, false, false, Key::F9))
+        );
+        assert_eq!(
+            "5".parse::<Hotkey>(),
+            Ok(hotkey(false, false, false, Key::Num5))
+        );
+        assert_eq!(
+            "Alt + Space".parse::<Hotkey>(),
+            Ok(hotkey(false, false, true, Key::Space))
+        );
+
+        assert_eq!(
+            "ctrl+banana".parse::<Hotkey>(),
+            Err("unknown key banana".to_string())
+        );
+        assert_eq!(
+            "ctrl+shift".parse::<Hotkey>(),
+            Err("no key in ctrl+shift".to_string())
+        );
     }
 }
@@ -0,0 +1,254 @@
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use freecell::batch::BatchOptions;
+use freecell::board::parse_board;
+use freecell::deal::{ms_deal, parse_deck};
+use freecell::game::Game;
+use freecell::i18n::Msg;
+use freecell::output::{game_json, solution_json};
+use freecell::postprocess::{merge_supermoves, remove_wasted_moves};
+use freecell::solver::{Solver, Strategy};
+
+use crate::metrics::Metrics;
+
+// Body of POST /solve: one of deal, cards or board, then optional limits
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SolveRequest {
+    deal: Option<u32>,
+    cards: Option<String>,
+    board: Option<String>,
+    strategy: Option<Strategy>,
+    max_nodes: Option<u32>,
+    // Seconds
+    time_limit: Option<f64>,
+}
+
+fn deal_game(number: u32) -> Result<Game, String> {
+    match number {
+        1..=0x7fff_ffff => Ok(Game::new(&ms_deal(number))),
+        _ => Err(format!("Invalid deal number: {}", number)),
+    }
+}
+
+fn request_game(request: &SolveRequest) -> Result<Game, String> {
+    match (request.deal, &request.cards, &request.board) {
+        (Some(number), None, None) => deal_game(number),
+        (None, Some(cards), None) => parse_deck(cards)
+            .map(|deck| Game::new(&deck))
+            .map_err(|e| e.to_string()),
+        (None, None, Some(board)) => parse_board(board).map_err(|e| e.to_string()),
+        _ => Err("Expected exactly one of deal, cards or board".to_string()),
+    }
+}
+
+// The request may lower the server limits, never raise them
+fn solve(body: &str, limits: &BatchOptions, metrics: &Metrics) -> Result<Value, String> {
+    let request: SolveRequest =
+        serde_json::from_str(body).map_err(|e| format!("Invalid request: {}", e))?;
+    let game = request_game(&request)?;
+
+    let mut solver = Solver::new(game.clone());
+    solver.verbose = false;
+    solver.strategy = request.strategy.unwrap_or(limits.strategy);
+    solver.weights = limits.weights;
+    let asked = match request.time_limit {
+        Some(seconds) => match Duration::try_from_secs_f64(seconds) {
+            Ok(asked) if seconds > 0.0 => Some(asked),
+            _ => return Err(format!("Invalid time_limit: {}", seconds)),
+        },
+        None => None,
+    };
+    solver.time_limit = match (asked, limits.time_limit) {
+        (Some(asked), Some(limit)) => Some(asked.min(limit)),
+        (asked, limit) => asked.or(limit),
+    };
+    let max_nodes = request
+        .max_nodes
+        .map_or(limits.max_nodes, |n| n.min(limits.max_nodes));
+
+    let started = Instant::now();
+    let solution = solver.solve(max_nodes);
+    let outcome = match (&solution, solver.limit_reached) {
+        (Some(_), _) => "solved",
+        (None, true) => "limit",
+        (None, false) => "unsolvable",
+    };
+    metrics.observe_search(&solver.stats, started.elapsed(), outcome);
+
+    let steps = solution.map(|solution| {
+        let solution = match limits.optimize {
+            true => remove_wasted_moves(&game, &solution),
+            false => solution,
+        };
+        merge_supermoves(&game, &solution)
+    });
+    let mut json = solution_json(&game, steps.as_deref(), &solver.stats, false);
+    json["limit_reached"] = json!(solver.limit_reached);
+    Ok(json)
+}
+
+// Status code and JSON body of a request, apart from the network
+pub fn route(
+    method: &Method,
+    path: &str,
+    body: &str,
+    limits: &BatchOptions,
+    metrics: &Metrics,
+) -> (u16, Value) {
+    let error = |status, message: String| (status, json!({ "error": message }));
+    match (method, path.strip_prefix("/deal/")) {
+        (Method::Get, Some(number)) => match number
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid deal number: {}", number))
+            .and_then(deal_game)
+        {
+            Ok(game) => (200, game_json(&game)),
+            Err(e) => error(400, e),
+        },
+        (Method::Post, None) if path == "/solve" => match solve(body, limits, metrics) {
+            Ok(json) => (200, json),
+            Err(e) => error(400, e),
+        },
+        _ => error(404, format!("No route for {} {}", method, path)),
+    }
+}
+
+// Label of a route in the metrics, without the deal number
+fn route_label(path: &str) -> &str {
+    match path {
+        "/solve" | "/metrics" => path,
+        _ if path.starts_with("/deal/") => "/deal",
+        _ => "other",
+    }
+}
+
+fn respond(mut request: Request, limits: &BatchOptions, metrics: &Metrics) -> io::Result<()> {
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body)?;
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let (status, body, content_type) = match (request.method(), path.as_str()) {
+        (Method::Get, "/metrics") => (200, metrics.render(), "text/plain; version=0.0.4"),
+        (method, _) => {
+            let (status, json) = route(method, &path, &body, limits, metrics);
+            (status, json.to_string(), "application/json")
+        }
+    };
+    metrics.observe_request(route_label(&path), status);
+
+    let mut response = Response::from_string(body).with_status_code(status);
+    if let Ok(header) = Header::from_bytes("Content-Type", content_type) {
+        response.add_header(header);
+    }
+    request.respond(response)
+}
+
+// Each worker thread takes the next request, so that `jobs` deals are solved at once
+pub fn run_server(address: &str, jobs: usize, limits: BatchOptions) -> io::Result<()> {
+    let server = Server::http(address).map_err(io::Error::other)?;
+    let metrics = Metrics::new().map_err(io::Error::other)?;
+    eprintln!("{}", Msg::ServerListening(address));
+
+    let jobs = match jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                while let Ok(request) = server.recv() {
+                    if let Err(e) = respond(request, &limits, &metrics) {
+                        eprintln!("{}", Msg::ResponseNotSent(&e));
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use freecell::solver::HeuristicWeights;
+
+    fn limits() -> BatchOptions {
+        BatchOptions {
+            strategy: Strategy::AStar,
+            max_nodes: 100_000,
+            time_limit: None,
+            weights: HeuristicWeights::default(),
+            optimize: true,
+            rate: false,
+        }
+    }
+
+    #[test]
+    fn test_routes() {
+        let metrics = Metrics::new().unwrap();
+        let (status, json) = route(&Method::Get, "/deal/1", "", &limits(), &metrics);
+        assert_eq!(status, 200);
+        assert_eq!(json["columns"][0][0], "JD");
+
+        let (status, json) = route(
+            &Method::Post,
+            "/solve",
+            r#"{"deal": 1}"#,
+            &limits(),
+            &metrics,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(json["solved"], true);
+
+        // The request lowers the node limit
+        let body = r#"{"deal": 1, "strategy": "dfs", "max_nodes": 10}"#;
+        let (_, json) = route(&Method::Post, "/solve", body, &limits(), &metrics);
+        assert_eq!(json["solved"], false);
+        assert_eq!(json["limit_reached"], true);
+
+        let (status, _) = route(
+            &Method::Post,
+            "/solve",
+            r#"{"deal": 1, "cards": "AS"}"#,
+            &limits(),
+            &metrics,
+        );
+        assert_eq!(status, 400);
+        for body in [
+            r#"{"deal": 1, "time_limit": 1e30}"#,
+            r#"{"deal": 1, "time_limit": -1}"#,
+            r#"{"deal": 1, "strategy": "weighted:50000000"}"#,
+        ] {
+            assert_eq!(
+                route(&Method::Post, "/solve", body, &limits(), &metrics).0,
+                400
+            );
+        }
+        assert_eq!(
+            route(&Method::Get, "/deal/0", "", &limits(), &metrics).0,
+            400
+        );
+        assert_eq!(
+            route(&Method::Get, "/solve", "", &limits(), &metrics).0,
+            404
+        );
+
+        // Both searches went through the metrics
+        let text = metrics.render();
+        assert!(text.contains("freecell_solves_total{outcome=\"solved\"} 1"));
+        assert!(text.contains("freecell_solves_total{outcome=\"limit\"} 1"));
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::action::{Action, ActionType};
+use crate::card::{Card, Suit};
+use crate::error::SolverError;
+use crate::game::Game;
+use crate::heap::{OpenList, SearchNode};
+use crate::solver::{HeuristicWeights, QueueKind, Strategy};
+use crate::stats::SearchStats;
+
+const MAGIC: &[u8; 4] = b"FCSS";
+// Bumped whenever the layout below changes, older files are then refused
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+// An A* search stopped midway: what it needs to go on where it left off.
+//
+// Little-endian binary layout, after the magic and the version:
+//   strategy, queue kind, heuristic weights, deal, search counters,
+//   best g-score per state hash, then the open list in pop order. Open nodes only
+//   keep their path, 4 bytes per move, the position is replayed from the deal.
+pub struct SearchSnapshot {
+    pub game: Game,
+    pub strategy: Strategy,
+    pub queue_kind: QueueKind,
+    pub weights: HeuristicWeights,
+    pub stats: SearchStats,
+    pub best_g: HashMap<u64, usize>,
+    pub open: Vec<(i32, Vec<Action>)>,
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SolverError> {
+        if self.bytes.len() < n {
+            return Err(SolverError::Snapshot("file is truncated".to_string()));
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SolverError> {
+        let mut out = [0; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, SolverError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SolverError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, SolverError> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, SolverError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], SolverError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn text(&mut self) -> Result<&'a str, SolverError> {
+        std::str::from_utf8(self.bytes()?).map_err(|e| SolverError::Snapshot(e.to_string()))
+    }
+}
+
+// rank * 4 + suit, 0 for an empty freecell
+fn card_byte(card: Option<&Card>) -> u8 {
+    card.map_or(0, |card| card.rank << 2 | card.suit as u8)
+}
+
+fn read_card(reader: &mut Reader) -> Result<Option<Card>, SolverError> {
+    match reader.u8()? {
+        0 => Ok(None),
+        byte if (1..=13).contains(&(byte >> 2)) => Ok(Some(Card {
+            rank: byte >> 2,
+            suit: Suit::ALL[(byte & 3) as usize],
+        })),
+        byte => Err(SolverError::Snapshot(format!("invalid card {}", byte))),
+    }
+}
+
+// Columns with their length, freecells, foundations and the number of freecells
+fn put_game(out: &mut Vec<u8>, game: &Game) {
+    for column in &game.columns {
+        out.push(column.len() as u8);
+        out.extend(column.iter().map(|card| card_byte(Some(card))));
+    }
+    out.extend(game.freecells.iter().map(|card| card_byte(card.as_ref())));
+    out.extend_from_slice(&game.foundations);
+    out.push(game.cells as u8);
+}
+
+fn read_game(reader: &mut Reader) -> Result<Game, SolverError> {
+    let mut game = Game::new(&[]);
+    for column in &mut game.columns {
+        for _ in 0..reader.u8()? {
+            let card = read_card(reader)?
+                .ok_or_else(|| SolverError::Snapshot("empty card in a column".to_string()))?;
+            column.push(card);
+        }
+    }
+    for freecell in &mut game.freecells {
+        *freecell = read_card(reader)?;
+    }
+    game.foundations = reader.array()?;
+    Ok(game.with_cells(reader.u8()? as usize))
+}
+
+fn put_action(out: &mut Vec<u8>, action: &Action) {
+    out.extend_from_slice(&[
+        action.action_type as u8,
+        action.source as u8,
+        action.dest as u8,
+        action.pile_size as u8,
+    ]);
+}
+
+fn read_action(reader: &mut Reader) -> Result<Action, SolverError> {
+    let [kind, source, dest, pile_size] = reader.array()?;
+    let action_type = *ActionType::ALL
+        .get(kind as usize)
+        .ok_or_else(|| SolverError::Snapshot(format!("unknown move type {}", kind)))?;
+    Ok(Action {
+        action_type,
+        source: source as usize,
+        dest: dest as usize,
+        pile_size: pile_size as usize,
+    })
+}
+
+impl SearchSnapshot {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        put_u32(&mut out, SNAPSHOT_VERSION);
+        put_bytes(&mut out, self.strategy.to_string().as_bytes());
+        out.push(match self.queue_kind {
+            QueueKind::Buckets => 0,
+            QueueKind::Heap => 1,
+        });
+        for weight in [
+            self.weights.remaining_card,
+            self.weights.ordered_pair,
+            self.weights.occupied_freecell,
+            self.weights.blocked_card,
+        ] {
+            out.extend_from_slice(&weight.to_le_bytes());
+        }
+        put_game(&mut out, &self.game);
+
+        let stats = &self.stats;
+        put_u64(&mut out, stats.nodes_explored);
+        for count in stats.expansions {
+            put_u64(&mut out, count);
+        }
+        put_u64(&mut out, stats.duplicate_hits);
+        put_u64(&mut out, stats.heuristic_evaluations);
+        put_u64(&mut out, stats.reopened_states);
+
+        put_u64(&mut out, self.best_g.len() as u64);
+        for (&hash, &g) in &self.best_g {
+            put_u64(&mut out, hash);
+            put_u32(&mut out, g as u32);
+        }
+
+        put_u64(&mut out, self.open.len() as u64);
+        for (f_score, path) in &self.open {
+            out.extend_from_slice(&f_score.to_le_bytes());
+            put_u32(&mut out, path.len() as u32);
+            for action in path {
+                put_action(&mut out, action);
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SolverError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(SolverError::Snapshot("not a search checkpoint".to_string()));
+        }
+        let version = reader.u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SolverError::Snapshot(format!(
+                "version {} is not supported, expected {}",
+                version, SNAPSHOT_VERSION
+            )));
+        }
+
+        let strategy = reader.text()?.parse().map_err(SolverError::Snapshot)?;
+        let queue_kind = match reader.u8()? {
+            0 => QueueKind::Buckets,
+            _ => QueueKind::Heap,
+        };
+        let weights = HeuristicWeights {
+            remaining_card: reader.i32()?,
+            ordered_pair: reader.i32()?,
+            occupied_freecell: reader.i32()?,
+            blocked_card: reader.i32()?,
+        };
+        let game = read_game(&mut reader)?;
+
+        let mut stats = SearchStats {
+            nodes_explored: reader.u64()?,
+            ..Default::default()
+        };
+        for count in &mut stats.expansions {
+            *count = reader.u64()?;
+        }
+        stats.duplicate_hits = reader.u64()?;
+        stats.heuristic_evaluations = reader.u64()?;
+        stats.reopened_states = reader.u64()?;
+
+        let mut best_g = HashMap::new();
+        for _ in 0..reader.u64()? {
+            best_g.insert(reader.u64()?, reader.u32()? as usize);
+        }
+
+        let mut open = Vec::new();
+        for _ in 0..reader.u64()? {
+            let f_score = reader.i32()?;
+            let path = (0..reader.u32()?)
+                .map(|_| read_action(&mut reader))
+                .collect::<Result<_, _>>()?;
+            open.push((f_score, path));
+        }
+
+        Ok(SearchSnapshot {
+            game,
+            strategy,
+            queue_kind,
+            weights,
+            stats,
+            best_g,
+            open,
+        })
+    }
+
+    // Written next to the target then renamed, so that a crash while saving keeps the
+    // previous checkpoint
+    pub fn save(&self, path: &Path) -> Result<(), SolverError> {
+        let partial = path.with_extension("partial");
+        fs::write(&partial, self.to_bytes())?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SolverError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    // The open list with every position replayed from the deal
+    pub(crate) fn open_list(&self) -> OpenList {
+        let mut open = OpenList::new(self.queue_kind);
+        for (f_score, path) in &self.open {
+            let mut state = self.game.clone();
+            for action in path {
+                state.apply_unchecked(action);
+            }
+            open.push(
+                *f_score,
+                SearchNode {
+                    state,
+                    path: path.clone(),
+                },
+            );
+        }
+        open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let game = Game::new(&ms_deal(617));
+        let first = crate::solver::Solver::new(game.clone()).get_moves(&game)[0];
+        let snapshot = SearchSnapshot {
+            game: game.clone(),
+            strategy: Strategy::Weighted(3),
+            queue_kind: QueueKind::Buckets,
+            weights: HeuristicWeights::default(),
+            stats: SearchStats {
+                nodes_explored: 42,
+                ..Default::default()
+            },
+            best_g: HashMap::from([(game.hash_key(), 0)]),
+            open: vec![(7, vec![first])],
+        };
+
+        let bytes = snapshot.to_bytes();
+        let restored = SearchSnapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.strategy, Strategy::Weighted(3));
+        assert_eq!(restored.stats.nodes_explored, 42);
+        assert_eq!(restored.best_g, snapshot.best_g);
+        assert_eq!(restored.open, snapshot.open);
+        assert_eq!(restored.game.layout_key(), game.layout_key());
+
+        let mut newer = bytes.clone();
+        newer[4] = 99;
+        assert!(SearchSnapshot::from_bytes(&newer).is_err());
+        assert!(SearchSnapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_resume_search() {
+        let path = std::env::temp_dir().join("freecell-test-resume.fcss");
+        let mut solver = crate::solver::Solver::new(Game::new(&ms_deal(1)));
+        solver.verbose = false;
+        solver.checkpoint = Some((path.clone(), 1_000_000));
+        assert!(solver.solve(100).is_none());
+        assert!(solver.limit_reached);
+
+        let snapshot = SearchSnapshot::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(snapshot.stats.nodes_explored, 100);
+        let mut resumed = crate::solver::Solver::from_snapshot(snapshot);
+        resumed.verbose = false;
+        assert!(resumed.solve(500_000).is_some());
+        assert!(resumed.stats.nodes_explored > 100);
+    }
+}
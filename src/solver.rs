@@ -1,23 +1,266 @@
 use crate::action::{Action, ActionType};
-use crate::card::{Card, Suit};
-use crate::game::Game;
-use crate::heap::HeapNode;
-use std::collections::{BinaryHeap, HashSet};
-use std::fmt::Debug;
-use std::hash::{DefaultHasher, Hash, Hasher};
-
-pub struct Solver {
+use crate::card::Card;
+use crate::game::{Game, UndoToken};
+use crate::heap::{OpenList, SearchNode};
+use crate::i18n::Msg;
+use crate::observer::{NullObserver, SearchObserver};
+use crate::snapshot::SearchSnapshot;
+use crate::stats::{Phase, SearchStats};
+use crate::verify::verify_solution;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+// std's clock panics in the browser
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+pub use crate::heap::QueueKind;
+
+// Rough memory use of a search, checked against `memory_limit`: a queued position
+// with its cards and a path of about 50 moves, and an entry of a table of seen states
+const OPEN_NODE_BYTES: usize =
+    size_of::<SearchNode>() + 52 * size_of::<Card>() + 50 * size_of::<Action>();
+const SEEN_STATE_BYTES: usize = 2 * size_of::<(u64, usize)>();
+// Highest f-score the bucket queue is used for, as it keeps a bucket for every score
+const MAX_BUCKET_SCORE: i64 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(try_from = "String"))]
+pub enum Strategy {
+    #[default]
+    AStar,
+    Dfs,
+    // A* with the heuristic multiplied by the weight: faster, longer solutions
+    Weighted(u32),
+}
+
+// Heaviest weight of `Strategy::Weighted`, the weighted f-scores stay far from i32::MAX
+const MAX_WEIGHT: u32 = 1000;
+
+impl Strategy {
+    pub const ALL: [Strategy; 2] = [Strategy::AStar, Strategy::Dfs];
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        if let Some(weight) = lower.strip_prefix("weighted:") {
+            return match weight.parse() {
+                Ok(weight) if (1..=MAX_WEIGHT).contains(&weight) => Ok(Strategy::Weighted(weight)),
+                _ => Err(format!(
+                    "Invalid weight: {}, expected 1 to {}",
+                    weight, MAX_WEIGHT
+                )),
+            };
+        }
+        match lower.as_str() {
+            "astar" | "a*" => Ok(Strategy::AStar),
+            "dfs" => Ok(Strategy::Dfs),
+            _ => Err(format!("Unknown strategy: {}", s)),
+        }
+    }
+}
+
+// Same spelling as `Display`, "astar" or "weighted:5"
+#[cfg(feature = "serde")]
+impl Serialize for Strategy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl TryFrom<String> for Strategy {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+// Same spelling as accepted by `FromStr`
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::AStar => f.pad("astar"),
+            Strategy::Dfs => f.pad("dfs"),
+            Strategy::Weighted(weight) => f.pad(&format!("weighted:{}", weight)),
+        }
+    }
+}
+
+// Terms of the heuristic, settable from the [heuristic] section of the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct HeuristicWeights {
+    pub remaining_card: i32,
+    pub ordered_pair: i32,
+    pub occupied_freecell: i32,
+    pub blocked_card: i32,
+}
+
+impl HeuristicWeights {
+    // Lowest and highest heuristic of a position with `cells` freecells. Each card left
+    // scores `remaining_card`, plus the pair bonus or the blocked penalty of the card
+    // under it at most.
+    pub fn bounds(&self, cells: usize) -> (i64, i64) {
+        let card = self.remaining_card as i64;
+        let pair = -(self.ordered_pair as i64);
+        let blocked = self.blocked_card as i64;
+        let freecells = self.occupied_freecell as i64 * cells as i64;
+        let low = (52 * (card + pair.min(blocked).min(0))).min(0) + freecells.min(0);
+        let high = (52 * (card + pair.max(blocked).max(0))).max(0) + freecells.max(0);
+        (low, high)
+    }
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        HeuristicWeights {
+            remaining_card: 10,
+            ordered_pair: 3,
+            occupied_freecell: 5,
+            blocked_card: 5,
+        }
+    }
+}
+
+// Shared with another thread while a search runs: it can stop the search and read
+// how many nodes were explored so far, updated every 1000 nodes
+#[derive(Debug, Clone, Default)]
+pub struct SearchControl {
+    stop: Arc<AtomicBool>,
+    nodes: Arc<AtomicU64>,
+}
+
+impl SearchControl {
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+}
+
+pub struct Solver<O = NullObserver> {
     pub initial_game: Game,
-    pub visited_states: std::collections::HashSet<u64>,
-    pub nodes_explored: u64,
+    pub queue_kind: QueueKind,
+    pub strategy: Strategy,
+    pub weights: HeuristicWeights,
+    pub stats: SearchStats,
+    // Wall-clock budget of a search, checked along with the node limit
+    pub time_limit: Option<Duration>,
+    // Estimated bytes of the open list and the seen states, checked like the time limit
+    pub memory_limit: Option<usize>,
+    // Set when the last search stopped on a limit rather than exhausting the tree
+    pub limit_reached: bool,
+    pub control: Option<SearchControl>,
+    // File an A* search saves itself to, every N nodes and when it hits a limit
+    pub checkpoint: Option<(PathBuf, u64)>,
+    // Search to go on with instead of starting from the deal
+    resume: Option<SearchSnapshot>,
+    started: Option<Instant>,
+    // Progress and result messages on stdout
+    pub verbose: bool,
+    // Times each phase into `stats.phase_times`, which slows the search down a little
+    pub profile: bool,
+    pub observer: O,
 }
 
 impl Solver {
     pub fn new(game: Game) -> Self {
         Solver {
             initial_game: game,
-            visited_states: std::collections::HashSet::new(),
-            nodes_explored: 0,
+            queue_kind: QueueKind::default(),
+            strategy: Strategy::default(),
+            weights: HeuristicWeights::default(),
+            stats: SearchStats::default(),
+            time_limit: None,
+            memory_limit: None,
+            limit_reached: false,
+            control: None,
+            checkpoint: None,
+            resume: None,
+            started: None,
+            verbose: true,
+            profile: false,
+            observer: NullObserver,
+        }
+    }
+
+    // Goes on with a saved search: `solve` then starts from its open list. The node
+    // limit still counts the nodes explored before the checkpoint.
+    pub fn from_snapshot(snapshot: SearchSnapshot) -> Self {
+        let mut solver = Solver::new(snapshot.game.clone());
+        solver.strategy = snapshot.strategy;
+        solver.weights = snapshot.weights;
+        solver.queue_kind = snapshot.queue_kind;
+        solver.resume = Some(snapshot);
+        solver
+    }
+}
+
+impl<O: SearchObserver> Solver<O> {
+    // Same solver, reporting to `observer` as it searches
+    pub fn with_observer<P: SearchObserver>(self, observer: P) -> Solver<P> {
+        Solver {
+            initial_game: self.initial_game,
+            queue_kind: self.queue_kind,
+            strategy: self.strategy,
+            weights: self.weights,
+            stats: self.stats,
+            time_limit: self.time_limit,
+            memory_limit: self.memory_limit,
+            limit_reached: self.limit_reached,
+            control: self.control,
+            checkpoint: self.checkpoint,
+            resume: self.resume,
+            started: self.started,
+            verbose: self.verbose,
+            profile: self.profile,
+            observer,
+        }
+    }
+
+    fn save_checkpoint(&self, open: &OpenList, best_g: &HashMap<u64, usize>) {
+        let Some((path, _)) = &self.checkpoint else {
+            return;
+        };
+        let snapshot = SearchSnapshot {
+            game: self.initial_game.clone(),
+            strategy: self.strategy,
+            queue_kind: open.kind(),
+            weights: self.weights,
+            stats: self.stats.clone(),
+            best_g: best_g.clone(),
+            open: open
+                .in_pop_order()
+                .into_iter()
+                .map(|(f_score, node)| (f_score, node.path.clone()))
+                .collect(),
+        };
+        match snapshot.save(path) {
+            Ok(()) if self.verbose => println!("{}", Msg::CheckpointSaved(&path.display())),
+            Ok(()) => {}
+            Err(e) => eprintln!("{}", Msg::CheckpointFailed(&e)),
         }
     }
 
@@ -26,25 +269,25 @@ impl Solver {
 
         // Cartes pas encore en fondation (poids principal)
         let cards_remaining = 52 - game.foundations.iter().map(|&f| f as i32).sum::<i32>();
-        score += cards_remaining * 10;
+        score += cards_remaining * self.weights.remaining_card;
 
         // Bonus de sequences bien ordonnées dans les colonnes
         for col in &game.columns {
             for window in col.windows(2) {
                 if game.can_stack_on(&window[0], &window[1]) {
-                    score -= 3;
+                    score -= self.weights.ordered_pair;
                 }
             }
         }
 
         // Pénalité pour cellules libres occupées
-        score += (4 - game.count_free_cells() as i32) * 5;
+        score += (game.cells - game.count_free_cells()) as i32 * self.weights.occupied_freecell;
 
         // Pénalité pour les cartes bloquees
         for col in &game.columns {
             for window in col.windows(2) {
-                if &window[0].rank < &window[1].rank {
-                    score += 5;
+                if window[0].rank < window[1].rank {
+                    score += self.weights.blocked_card;
                 }
             }
         }
@@ -74,15 +317,15 @@ impl Solver {
 
         // Freecell to foundations
         for (fc_index, freecell) in game.freecells.iter().enumerate() {
-            if let Some(card) = freecell {
-                if game.can_move_to_foundation(card) {
-                    all_moves.push(Action {
-                        action_type: ActionType::FreecellToFoundation,
-                        source: fc_index,
-                        dest: card.suit as usize,
-                        pile_size: 1,
-                    });
-                }
+            if let Some(card) = freecell
+                && game.can_move_to_foundation(card)
+            {
+                all_moves.push(Action {
+                    action_type: ActionType::FreecellToFoundation,
+                    source: fc_index,
+                    dest: card.suit as usize,
+                    pile_size: 1,
+                });
             }
         }
 
@@ -101,18 +344,20 @@ impl Solver {
                 }
             }
 
-            // Move between columns
+            // Move between columns, limited by the free cells and empty columns available
+            let max_pile = game.max_movable_sequence(false) as usize;
+            let max_pile_to_empty = game.max_movable_sequence(true) as usize;
+
             for (j, target_col) in game.columns.iter().enumerate() {
                 if i == j {
                     continue;
                 }
 
-                if seq_len == target_col.len() && target_col.is_empty() {
-                    continue; // Skip moving full sequence to empty column
-                }
-
-                for pile_size in 1..seq_len {
+                for pile_size in 1..=seq_len {
                     if target_col.is_empty() {
+                        if pile_size > max_pile_to_empty || pile_size == source_col.len() {
+                            continue; // Skip moving full column to empty column
+                        }
                         // Can move any sequence to empty column
                         all_moves.push(Action {
                             action_type: ActionType::ColToCol,
@@ -121,6 +366,9 @@ impl Solver {
                             pile_size,
                         });
                     } else {
+                        if pile_size > max_pile {
+                            break;
+                        }
                         let target_top_card = target_col.last().unwrap();
                         let moving_card = &source_col[source_col.len() - pile_size];
                         if game.can_stack_on(target_top_card, moving_card) {
@@ -136,7 +384,7 @@ impl Solver {
             }
 
             // Move to freecells
-            for freecell_index in 0..4 {
+            for freecell_index in 0..game.cells {
                 if game.freecells[freecell_index].is_none() {
                     all_moves.push(Action {
                         action_type: ActionType::ColToFreecell,
@@ -147,27 +395,23 @@ impl Solver {
                     break; // Only need one freecell move
                 }
             }
+        }
 
-            // Move from freecells to columns
+        // Move from freecells to columns
+        for (i, target_col) in game.columns.iter().enumerate() {
             for (fc_index, freecell) in game.freecells.iter().enumerate() {
                 if let Some(card) = freecell {
-                    if source_col.is_empty() {
+                    let fits = match target_col.last() {
+                        Some(target_top_card) => game.can_stack_on(target_top_card, card),
+                        None => true,
+                    };
+                    if fits {
                         all_moves.push(Action {
                             action_type: ActionType::FreecellToCol,
                             source: fc_index,
                             dest: i,
                             pile_size: 1,
                         });
-                    } else {
-                        let target_top_card = source_col.last().unwrap();
-                        if game.can_stack_on(target_top_card, card) {
-                            all_moves.push(Action {
-                                action_type: ActionType::FreecellToCol,
-                                source: fc_index,
-                                dest: i,
-                                pile_size: 1,
-                            });
-                        }
                     }
                 }
             }
@@ -178,103 +422,411 @@ impl Solver {
 
     pub fn apply_move(&self, game: &Game, action: &Action) -> Game {
         let mut copy = game.clone();
+        copy.apply_unchecked(action);
+        copy
+    }
 
-        match action.action_type {
-            ActionType::ColToFoundation => {
-                let card = copy.columns[action.source].pop().unwrap();
-                copy.foundations[card.suit as usize] += 1;
-            }
-            ActionType::FreecellToFoundation => {
-                let card = copy.freecells[action.source].take().unwrap();
-                copy.foundations[card.suit as usize] += 1;
-            }
-            ActionType::ColToFreecell => {
-                let card = copy.columns[action.source].pop().unwrap();
-                copy.freecells[action.dest] = Some(card);
-            }
-            ActionType::FreecellToCol => {
-                let card = copy.freecells[action.source].take().unwrap();
-                copy.columns[action.dest].push(card);
-            }
-            ActionType::ColToCol => {
-                let moving_cards: Vec<Card> = copy.columns[action.source]
-                    .drain(copy.columns[action.source].len() - action.pile_size..)
-                    .collect();
-                copy.columns[action.dest].extend(moving_cards);
+    pub fn solve(&mut self, max_nodes: u32) -> Option<Vec<Action>> {
+        self.stats = SearchStats::default();
+        self.limit_reached = false;
+        self.started = Some(Instant::now());
+        let solution = match self.strategy {
+            Strategy::AStar => self.solve_astar(max_nodes, 1),
+            Strategy::Dfs => self.solve_dfs(max_nodes),
+            Strategy::Weighted(weight) => self.solve_astar(max_nodes, weight as i32),
+        };
+
+        if cfg!(debug_assertions)
+            && let Some(path) = &solution
+        {
+            match verify_solution(&self.initial_game, path) {
+                Ok(state) => assert!(state.is_won(), "{:?} solution does not win", self.strategy),
+                Err(e) => panic!("{:?} produced an illegal solution: {}", self.strategy, e),
             }
         }
 
-        copy
+        solution
     }
 
-    pub fn solve(&self, max_nodes: u32) -> Option<Vec<Action>> {
-        let start_h = self.heuristic(&self.initial_game);
+    // Start of a timed phase, None unless profiling
+    fn phase_start(&self) -> Option<Instant> {
+        self.profile.then(Instant::now)
+    }
 
-        let mut counter = 0;
+    fn phase_end(&mut self, phase: Phase, start: Option<Instant>) {
+        if let Some(start) = start {
+            self.stats.record_phase(phase, start.elapsed());
+        }
+    }
 
-        let mut heap = BinaryHeap::new();
+    // The clock and the control are only read every 1000 nodes
+    fn out_of_budget(&self, max_nodes: u32, memory: usize) -> bool {
+        let nodes = self.stats.nodes_explored;
+        if nodes >= max_nodes as u64 {
+            return true;
+        }
+        if !nodes.is_multiple_of(1000) {
+            return false;
+        }
+        if let Some(control) = &self.control {
+            control.nodes.store(nodes, Ordering::Relaxed);
+            if control.is_stopped() {
+                return true;
+            }
+        }
+        if self.memory_limit.is_some_and(|limit| memory > limit) {
+            return true;
+        }
+        match (self.time_limit, self.started) {
+            (Some(limit), Some(started)) => started.elapsed() >= limit,
+            _ => false,
+        }
+    }
 
-        heap.push(HeapNode {
-            f_score: start_h,
-            counter,
-            state: self.initial_game.clone(),
-            path: Vec::new(),
-        });
+    // The bucket queue needs f-scores that are never negative and that stay small enough
+    // for one bucket per score, which other weights than the default ones may not give
+    fn queue_for(&self, weight: i32) -> QueueKind {
+        let (low, high) = self.weights.bounds(self.initial_game.cells);
+        let fits = low >= 0 && high * weight as i64 <= MAX_BUCKET_SCORE;
+        match self.queue_kind {
+            QueueKind::Buckets if fits => QueueKind::Buckets,
+            _ => QueueKind::Heap,
+        }
+    }
 
-        let mut visited = HashSet::new();
-        visited.insert(self.initial_game.hash_key());
-        let mut nodes_explored = 0;
+    // f = g + weight * h, a weight of 1 being plain A*
+    fn solve_astar(&mut self, max_nodes: u32, weight: i32) -> Option<Vec<Action>> {
+        // Best g-score seen for each state. The first path found is kept: a shorter one
+        // is only counted as a reopened state so that the counters don't change the search.
+        let (mut open, mut best_g) = match self.resume.take() {
+            Some(snapshot) => {
+                self.stats = snapshot.stats.clone();
+                (snapshot.open_list(), snapshot.best_g)
+            }
+            None => {
+                let start_h = self.heuristic(&self.initial_game);
+                self.stats.heuristic_evaluations += 1;
+
+                let mut open = OpenList::new(self.queue_for(weight));
+                open.push(
+                    weight * start_h,
+                    SearchNode {
+                        state: self.initial_game.clone(),
+                        path: Vec::new(),
+                    },
+                );
+                (open, HashMap::from([(self.initial_game.hash_key(), 0)]))
+            }
+        };
 
-        while let Some(node) = heap.pop() {
-            if nodes_explored >= max_nodes {
+        while !open.is_empty() {
+            let memory = open.len() * OPEN_NODE_BYTES + best_g.len() * SEEN_STATE_BYTES;
+            if self.out_of_budget(max_nodes, memory) {
+                self.limit_reached = true;
+                self.save_checkpoint(&open, &best_g);
                 break;
             }
+            if let Some((_, every)) = self.checkpoint
+                && self.stats.nodes_explored > 0
+                && self.stats.nodes_explored.is_multiple_of(every.max(1))
+            {
+                self.save_checkpoint(&open, &best_g);
+            }
+            let start = self.phase_start();
+            let popped = open.pop();
+            self.phase_end(Phase::Queue, start);
+            let Some(node) = popped else {
+                break;
+            };
 
             let g_score = node.path.len() as i32;
-            nodes_explored += 1;
+            self.stats.nodes_explored += 1;
 
-            if nodes_explored % 1000 == 0 {
+            if self.verbose && self.stats.nodes_explored.is_multiple_of(1000) {
                 println!(
-                    "Explored: {}, Queue: {}, Path: {}, H: {:.1}",
-                    nodes_explored,
-                    heap.len(),
-                    node.path.len(),
-                    node.f_score - g_score
+                    "{}",
+                    Msg::AStarProgress(
+                        self.stats.nodes_explored,
+                        open.len(),
+                        node.path.len(),
+                        self.heuristic(&node.state)
+                    )
                 );
             }
 
             if node.state.is_won() {
-                println!("\n✓ Solution trouvée en {} coups!", node.path.len());
-                println!("Nœuds explorés: {}", nodes_explored);
+                if self.verbose {
+                    println!("{}", Msg::SearchSolved(node.path.len()));
+                    println!("{}", Msg::NodesExplored(self.stats.nodes_explored));
+                }
+                self.observer.on_solution(&node.path);
                 return Some(node.path);
             }
 
             // Générer les mouvements
-            for mov in self.get_moves(&node.state) {
+            let start = self.phase_start();
+            let moves = self.get_moves(&node.state);
+            self.phase_end(Phase::MoveGeneration, start);
+            for mov in moves {
+                let start = self.phase_start();
                 let new_state = self.apply_move(&node.state, &mov);
+                self.phase_end(Phase::MoveGeneration, start);
+                let new_g = node.path.len() + 1;
+
+                let start = self.phase_start();
                 let state_hash = new_state.hash_key();
+                let duplicate = match best_g.get_mut(&state_hash) {
+                    Some(g) => {
+                        if new_g < *g {
+                            self.stats.reopened_states += 1;
+                            *g = new_g;
+                        }
+                        true
+                    }
+                    None => {
+                        best_g.insert(state_hash, new_g);
+                        false
+                    }
+                };
+                self.phase_end(Phase::Hashing, start);
+                if duplicate {
+                    self.stats.duplicate_hits += 1;
+                    self.observer.on_prune(&new_state, &mov);
+                    continue;
+                }
+                self.stats.record_expansion(mov.action_type);
+                self.observer.on_expand(&new_state, &mov, new_g);
+
+                let start = self.phase_start();
+                let new_h = self.heuristic(&new_state);
+                self.phase_end(Phase::Heuristic, start);
+                self.stats.heuristic_evaluations += 1;
+                let new_f = g_score + 1 + weight * new_h;
+
+                let mut new_path = node.path.clone();
+                new_path.push(mov);
+
+                let start = self.phase_start();
+                open.push(
+                    new_f,
+                    SearchNode {
+                        state: new_state,
+                        path: new_path,
+                    },
+                );
+                self.phase_end(Phase::Queue, start);
+            }
+        }
 
-                if !visited.contains(&state_hash) {
-                    visited.insert(state_hash);
-                    let new_g = g_score + 1;
-                    let new_h = self.heuristic(&new_state);
-                    let new_f = new_g + new_h;
+        if self.verbose {
+            println!("{}", Msg::SearchFailed(self.stats.nodes_explored));
+        }
+        None
+    }
 
-                    counter += 1;
-                    let mut new_path = node.path.clone();
-                    new_path.push(mov);
+    // Moves sorted so that the most promising one (lowest heuristic) is at the end of the Vec
+    fn ordered_moves(&mut self, game: &mut Game) -> Vec<Action> {
+        let start = self.phase_start();
+        let moves = self.get_moves(game);
+        self.phase_end(Phase::MoveGeneration, start);
+
+        let start = self.phase_start();
+        let mut scored: Vec<(i32, Action)> = moves
+            .into_iter()
+            .map(|mov| {
+                let token = game.apply_unchecked(&mov);
+                let h = self.heuristic(game);
+                game.undo(token);
+                (h, mov)
+            })
+            .collect();
+        self.stats.heuristic_evaluations += scored.len() as u64;
+        scored.sort_by_key(|(h, _)| -h);
+        self.phase_end(Phase::Heuristic, start);
+
+        scored.into_iter().map(|(_, mov)| mov).collect()
+    }
 
-                    heap.push(HeapNode {
-                        f_score: new_f,
-                        counter,
-                        state: new_state,
-                        path: new_path,
-                    });
+    // Moves in generation order, or sorted by heuristic for the regular DFS
+    fn next_moves(&mut self, game: &mut Game, ordered: bool) -> Vec<Action> {
+        if ordered {
+            return self.ordered_moves(game);
+        }
+        let start = self.phase_start();
+        let moves = self.get_moves(game);
+        self.phase_end(Phase::MoveGeneration, start);
+        moves
+    }
+
+    // Visits every position reachable from the deal, without node limit nor heuristic,
+    // only stopping at a win or on the time limit. When it returns None without
+    // `limit_reached`, the deal is proven unsolvable and `stats.nodes_explored + 1`
+    // is the number of reachable positions (deal included).
+    pub fn solve_exhaustive(&mut self) -> Option<Vec<Action>> {
+        self.stats = SearchStats::default();
+        self.limit_reached = false;
+        self.started = Some(Instant::now());
+        self.dfs(u32::MAX, false)
+    }
+
+    fn solve_dfs(&mut self, max_nodes: u32) -> Option<Vec<Action>> {
+        self.dfs(max_nodes, true)
+    }
+
+    // Depth-first search on a single working Game: moves are applied in place and
+    // undone on backtrack, the only allocations are the per-depth move lists.
+    fn dfs(&mut self, max_nodes: u32, ordered: bool) -> Option<Vec<Action>> {
+        let mut game = self.initial_game.clone();
+
+        let mut visited = HashSet::new();
+        visited.insert(game.hash_key());
+
+        let mut undo_stack: Vec<UndoToken> = Vec::new();
+        let mut stack: Vec<Vec<Action>> = vec![self.next_moves(&mut game, ordered)];
+
+        while let Some(moves) = stack.last_mut() {
+            let Some(mov) = moves.pop() else {
+                // Plus de coups à cette profondeur : on remonte
+                stack.pop();
+                if let Some(token) = undo_stack.pop() {
+                    game.undo(token);
+                }
+                continue;
+            };
+
+            let token = game.apply_unchecked(&mov);
+            let start = self.phase_start();
+            let new_state = visited.insert(game.hash_key());
+            self.phase_end(Phase::Hashing, start);
+            if !new_state {
+                self.stats.duplicate_hits += 1;
+                self.observer.on_prune(&game, &mov);
+                game.undo(token);
+                continue;
+            }
+            undo_stack.push(token);
+            self.stats.record_expansion(mov.action_type);
+            self.observer.on_expand(&game, &mov, undo_stack.len());
+
+            self.stats.nodes_explored += 1;
+            if self.verbose && self.stats.nodes_explored.is_multiple_of(1000) {
+                println!(
+                    "{}",
+                    Msg::DfsProgress(
+                        self.stats.nodes_explored,
+                        undo_stack.len(),
+                        self.heuristic(&game)
+                    )
+                );
+            }
+
+            if game.is_won() {
+                let path: Vec<Action> = undo_stack.iter().map(|t| *t.action()).collect();
+                if self.verbose {
+                    println!("{}", Msg::SearchSolved(path.len()));
+                    println!("{}", Msg::NodesExplored(self.stats.nodes_explored));
                 }
+                self.observer.on_solution(&path);
+                return Some(path);
             }
+
+            if self.out_of_budget(max_nodes, visited.len() * SEEN_STATE_BYTES) {
+                self.limit_reached = true;
+                break;
+            }
+
+            stack.push(self.next_moves(&mut game, ordered));
         }
 
-        println!("\n✗ Pas de solution trouvée après {} nœuds", nodes_explored);
+        if self.verbose {
+            println!("{}", Msg::SearchFailed(self.stats.nodes_explored));
+        }
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::deal::ms_deal;
+
+    #[test]
+    fn test_parse_strategy() {
+        assert_eq!("A*".parse(), Ok(Strategy::AStar));
+        assert_eq!("weighted:5".parse(), Ok(Strategy::Weighted(5)));
+        assert!("weighted:0".parse::<Strategy>().is_err());
+        assert_eq!("weighted:1000".parse(), Ok(Strategy::Weighted(1000)));
+        assert!("weighted:1001".parse::<Strategy>().is_err());
+        assert!("weighted:50000000".parse::<Strategy>().is_err());
+        assert!("bfs".parse::<Strategy>().is_err());
+        assert_eq!(Strategy::Weighted(5).to_string(), "weighted:5");
+    }
+
+    #[test]
+    fn test_exhaustive_search() {
+        // The only unsolvable deal among the first 32000
+        let mut solver = Solver::new(Game::new(&ms_deal(11982)));
+        solver.verbose = false;
+        assert!(solver.solve_exhaustive().is_none());
+        assert!(!solver.limit_reached);
+        assert_eq!(solver.stats.nodes_explored + 1, 83239);
+
+        let mut solver = Solver::new(Game::new(&ms_deal(1)));
+        solver.verbose = false;
+        assert!(solver.solve_exhaustive().is_some());
+    }
+
+    #[test]
+    fn test_queue_for_weights() {
+        let mut solver = Solver::new(Game::new(&ms_deal(1)));
+        solver.verbose = false;
+        assert_eq!(HeuristicWeights::default().bounds(4), (0, 800));
+        assert_eq!(solver.queue_for(1), QueueKind::Buckets);
+        assert_eq!(solver.queue_for(1000), QueueKind::Heap);
+
+        // Pairs worth more than a card left: negative f-scores
+        solver.weights.ordered_pair = 11;
+        assert!(solver.weights.bounds(4).0 < 0);
+        assert_eq!(solver.queue_for(1), QueueKind::Heap);
+        solver.solve(1_000);
+        assert!(solver.limit_reached);
+
+        solver.weights = HeuristicWeights {
+            remaining_card: 100_000,
+            ..HeuristicWeights::default()
+        };
+        assert_eq!(solver.queue_for(1), QueueKind::Heap);
+    }
+
+    #[test]
+    fn test_search_control_stops() {
+        let control = SearchControl::default();
+        let mut solver = Solver::new(Game::new(&ms_deal(1)));
+        solver.verbose = false;
+        solver.control = Some(control.clone());
+        control.stop();
+        assert!(solver.solve(1_000_000).is_none());
+        assert!(solver.limit_reached);
+    }
+
+    #[test]
+    fn test_profile_times_phases() {
+        let mut solver = Solver::new(Game::new(&ms_deal(617)));
+        solver.verbose = false;
+        solver.solve(20_000);
+        let plain = solver.stats.clone();
+        assert!(!plain.is_profiled());
+
+        solver.profile = true;
+        solver.solve(20_000);
+        assert_eq!(solver.stats.nodes_explored, plain.nodes_explored);
+        for phase in Phase::ALL {
+            assert!(
+                !solver.stats.phase_times[phase as usize].is_zero(),
+                "{:?}",
+                phase
+            );
+        }
+    }
+}
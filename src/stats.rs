@@ -0,0 +1,129 @@
+use std::fmt;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde_json::{Value, json};
+
+use crate::action::ActionType;
+
+const ACTION_TYPES: [ActionType; 5] = ActionType::ALL;
+
+// Parts of a search timed by `Solver::profile`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    MoveGeneration,
+    Heuristic,
+    Hashing,
+    Queue,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [
+        Phase::MoveGeneration,
+        Phase::Heuristic,
+        Phase::Hashing,
+        Phase::Queue,
+    ];
+}
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SearchStats {
+    pub nodes_explored: u64,
+    // New states reached, indexed by `ActionType as usize`
+    pub expansions: [u64; 5],
+    pub duplicate_hits: u64,
+    pub heuristic_evaluations: u64,
+    // Duplicates reached through a shorter path than the one already queued
+    pub reopened_states: u64,
+    // Time spent in each phase, indexed by `Phase as usize`. Zero unless profiled
+    pub phase_times: [Duration; 4],
+}
+
+impl SearchStats {
+    pub fn record_expansion(&mut self, action_type: ActionType) {
+        self.expansions[action_type as usize] += 1;
+    }
+
+    pub fn total_expansions(&self) -> u64 {
+        self.expansions.iter().sum()
+    }
+
+    pub fn expansions_by_type(&self) -> impl Iterator<Item = (ActionType, u64)> + '_ {
+        ACTION_TYPES
+            .iter()
+            .map(|&t| (t, self.expansions[t as usize]))
+    }
+
+    pub fn record_phase(&mut self, phase: Phase, elapsed: Duration) {
+        self.phase_times[phase as usize] += elapsed;
+    }
+
+    pub fn is_profiled(&self) -> bool {
+        self.phase_times.iter().any(|t| !t.is_zero())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Value {
+        let expansions: serde_json::Map<String, Value> = self
+            .expansions_by_type()
+            .map(|(t, count)| (format!("{:?}", t), json!(count)))
+            .collect();
+
+        let mut json = json!({
+            "nodes_explored": self.nodes_explored,
+            "expansions": expansions,
+            "duplicate_hits": self.duplicate_hits,
+            "heuristic_evaluations": self.heuristic_evaluations,
+            "reopened_states": self.reopened_states,
+        });
+        if self.is_profiled() {
+            let phases: serde_json::Map<String, Value> = Phase::ALL
+                .iter()
+                .map(|&p| {
+                    let ms = self.phase_times[p as usize].as_secs_f64() * 1000.0;
+                    (format!("{:?}", p), json!(ms))
+                })
+                .collect();
+            json["phase_ms"] = Value::Object(phases);
+        }
+        json
+    }
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Nodes explored:        {:>10}", self.nodes_explored)?;
+        writeln!(f, "Expansions:            {:>10}", self.total_expansions())?;
+        for action_type in ACTION_TYPES {
+            writeln!(
+                f,
+                "  {:<20} {:>10}",
+                format!("{:?}", action_type),
+                self.expansions[action_type as usize]
+            )?;
+        }
+        writeln!(f, "Duplicate hits:        {:>10}", self.duplicate_hits)?;
+        writeln!(
+            f,
+            "Heuristic evaluations: {:>10}",
+            self.heuristic_evaluations
+        )?;
+        write!(f, "Reopened states:       {:>10}", self.reopened_states)?;
+        if self.is_profiled() {
+            let total: Duration = self.phase_times.iter().sum();
+            write!(f, "\nPhase times:")?;
+            for phase in Phase::ALL {
+                let time = self.phase_times[phase as usize];
+                write!(
+                    f,
+                    "\n  {:<20} {:>10.1?} {:>5.1}%",
+                    format!("{:?}", phase),
+                    time,
+                    100.0 * time.as_secs_f64() / total.as_secs_f64()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
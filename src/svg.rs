@@ -0,0 +1,178 @@
+use std::fmt::Write;
+
+use crate::action::{Action, Location};
+use crate::card::{Card, Suit};
+use crate::explain::explain;
+use crate::game::Game;
+use crate::notation;
+use crate::render::{
+    BOARD_WIDTH, CARD_HEIGHT, CARD_WIDTH, COLUMNS_Y, MARGIN, OVERLAP, board_height, rank_text,
+    slot_x, suit_char,
+};
+
+// Room for the caption under each board of a solution diagram
+const CAPTION_HEIGHT: u32 = 24;
+
+const STYLE: &str = "<style>\
+.felt{fill:#1b5e20}\
+.slot{fill:none;stroke:#438c48}\
+.card{fill:#fafaf5;stroke:#3c3c3c}\
+.red{fill:#c8141e}\
+.black{fill:#141414}\
+.label{font:bold 13px sans-serif}\
+.caption{font:13px sans-serif;fill:#222}\
+.arrow{stroke:#ffc107;stroke-width:3;fill:none;marker-end:url(#head)}\
+</style>\
+<defs><marker id=\"head\" markerWidth=\"8\" markerHeight=\"8\" refX=\"6\" refY=\"4\" orient=\"auto\">\
+<path d=\"M0,0 L8,4 L0,8 z\" fill=\"#ffc107\"/></marker></defs>";
+
+fn card_svg(out: &mut String, card: &Card, x: u32, y: u32) {
+    let class = if card.is_black() { "black" } else { "red" };
+    let _ = write!(
+        out,
+        "<rect class=\"card\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\"/>\
+<text class=\"label {}\" x=\"{}\" y=\"{}\">{}{}</text>",
+        x,
+        y,
+        CARD_WIDTH,
+        CARD_HEIGHT,
+        class,
+        x + 3,
+        y + 15,
+        rank_text(card),
+        suit_char(card.suit)
+    );
+}
+
+fn slot_svg(out: &mut String, x: u32, y: u32) {
+    let _ = write!(
+        out,
+        "<rect class=\"slot\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\"/>",
+        x, y, CARD_WIDTH, CARD_HEIGHT
+    );
+}
+
+fn board_svg(out: &mut String, game: &Game, offset_y: u32) {
+    let _ = write!(
+        out,
+        "<rect class=\"felt\" x=\"0\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+        offset_y,
+        BOARD_WIDTH,
+        board_height(game)
+    );
+
+    for (i, cell) in game.freecells.iter().enumerate() {
+        let x = slot_x(i as u32);
+        match cell {
+            Some(card) => card_svg(out, card, x, offset_y + MARGIN),
+            None => slot_svg(out, x, offset_y + MARGIN),
+        }
+    }
+    for (i, &count) in game.foundations.iter().enumerate() {
+        let x = slot_x(4 + i as u32);
+        if count > 0 {
+            let card = Card {
+                rank: count,
+                suit: Suit::ALL[i],
+            };
+            card_svg(out, &card, x, offset_y + MARGIN);
+        } else {
+            slot_svg(out, x, offset_y + MARGIN);
+        }
+    }
+
+    for (i, column) in game.columns.iter().enumerate() {
+        let x = slot_x(i as u32);
+        if column.is_empty() {
+            slot_svg(out, x, offset_y + COLUMNS_Y);
+        }
+        for (row, card) in column.iter().enumerate() {
+            card_svg(out, card, x, offset_y + COLUMNS_Y + row as u32 * OVERLAP);
+        }
+    }
+}
+
+// Where arrows start or end for `location`, `row` is only used for columns
+fn anchor(location: Location, row: usize, offset_y: u32) -> (u32, u32) {
+    match location {
+        Location::Column(i) => (
+            slot_x(i as u32) + CARD_WIDTH / 2,
+            offset_y + COLUMNS_Y + row as u32 * OVERLAP + OVERLAP / 2,
+        ),
+        Location::Freecell(i) => (
+            slot_x(i as u32) + CARD_WIDTH / 2,
+            offset_y + MARGIN + CARD_HEIGHT / 2,
+        ),
+        Location::Foundation(i) => (
+            slot_x(4 + i as u32) + CARD_WIDTH / 2,
+            offset_y + MARGIN + CARD_HEIGHT / 2,
+        ),
+    }
+}
+
+fn arrow_svg(out: &mut String, game: &Game, action: &Action, offset_y: u32) {
+    let source_row = match action.source_location() {
+        Location::Column(i) => game.columns[i].len().saturating_sub(action.pile_size),
+        _ => 0,
+    };
+    let dest_row = match action.dest_location() {
+        Location::Column(i) => game.columns[i].len(),
+        _ => 0,
+    };
+    let (x1, y1) = anchor(action.source_location(), source_row, offset_y);
+    let (x2, y2) = anchor(action.dest_location(), dest_row, offset_y);
+    let _ = write!(
+        out,
+        "<line class=\"arrow\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+        x1, y1, x2, y2
+    );
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn document(width: u32, height: u32, body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">{}{}</svg>\n",
+        width, height, width, height, STYLE, body
+    )
+}
+
+pub fn game_to_svg(game: &Game) -> String {
+    let mut body = String::new();
+    board_svg(&mut body, game, 0);
+    document(BOARD_WIDTH, board_height(game), &body)
+}
+
+// One board per move, stacked vertically, with an arrow from source to destination
+// and the move in standard notation with its explanation as caption
+pub fn solution_to_svg(game: &Game, actions: &[Action]) -> String {
+    let mut body = String::new();
+    let mut state = game.clone();
+    let mut y = 0;
+
+    for (i, action) in actions.iter().enumerate() {
+        board_svg(&mut body, &state, y);
+        arrow_svg(&mut body, &state, action, y);
+        let height = board_height(&state);
+        let _ = write!(
+            body,
+            "<text class=\"caption\" x=\"{}\" y=\"{}\">{}. {} — {}</text>",
+            MARGIN,
+            y + height + CAPTION_HEIGHT - 8,
+            i + 1,
+            notation::to_standard(action),
+            escape(&explain(&state, action))
+        );
+        y += height + CAPTION_HEIGHT;
+        state.apply_unchecked(action);
+    }
+
+    board_svg(&mut body, &state, y);
+    y += board_height(&state);
+
+    document(BOARD_WIDTH, y, &body)
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+
+use crate::action::Action;
+use crate::game::{Game, IllegalMove};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+    pub index: usize,
+    pub action: Action,
+    pub reason: IllegalMove,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move #{} ({:?}) is illegal: {}",
+            self.index + 1,
+            self.action,
+            self.reason
+        )
+    }
+}
+
+// Replay `actions` from `game` through the validated Game::apply. The final position is
+// returned even if it is not won: callers decide whether a partial solution is acceptable.
+pub fn verify_solution(game: &Game, actions: &[Action]) -> Result<Game, VerifyError> {
+    let mut state = game.clone();
+
+    for (index, action) in actions.iter().enumerate() {
+        state.apply(action).map_err(|reason| VerifyError {
+            index,
+            action: *action,
+            reason,
+        })?;
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::action::Location;
+    use crate::card::Card;
+
+    #[test]
+    fn test_verify_solution_reports_first_illegal_move() {
+        let mut game = Game::new(&[]);
+        game.columns[0] = vec![Card::try_from("2S").unwrap(), Card::try_from("1S").unwrap()];
+        game.columns[1] = vec![Card::try_from("5D").unwrap()];
+
+        let moves = [
+            Action::between(Location::Column(0), Location::Foundation(2), 1).unwrap(),
+            Action::between(Location::Column(1), Location::Foundation(0), 1).unwrap(),
+        ];
+
+        let error = verify_solution(&game, &moves).unwrap_err();
+        assert_eq!(error.index, 1);
+        assert_eq!(
+            error.reason,
+            IllegalMove::NotPlayableOnFoundation(Card::try_from("5D").unwrap())
+        );
+
+        let state = verify_solution(&game, &moves[..1]).unwrap();
+        assert_eq!(state.foundations[2], 1);
+    }
+}
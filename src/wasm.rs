@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use wasm_bindgen::prelude::*;
+
+use crate::action::Action;
+use crate::board::parse_board;
+use crate::deal::{ms_deal, parse_deck, random_deal};
+use crate::game::Game;
+use crate::output::solution_json;
+use crate::postprocess::{merge_supermoves, remove_wasted_moves};
+use crate::solver::{Solver, Strategy};
+
+// A position handed to JavaScript. Moves go back and forth as the JSON of `Action`,
+// positions as the JSON of `Game`
+#[wasm_bindgen]
+pub struct FreecellGame {
+    game: Game,
+}
+
+#[wasm_bindgen]
+impl FreecellGame {
+    // Microsoft FreeCell deal number, 1 to 2147483647
+    #[wasm_bindgen(js_name = fromDeal)]
+    pub fn from_deal(number: u32) -> Result<FreecellGame, JsError> {
+        if !(1..=0x7fff_ffff).contains(&number) {
+            return Err(JsError::new(&format!("Invalid deal number: {}", number)));
+        }
+        Ok(FreecellGame {
+            game: Game::new(&ms_deal(number)),
+        })
+    }
+
+    #[wasm_bindgen(js_name = fromSeed)]
+    pub fn from_seed(seed: u64) -> FreecellGame {
+        FreecellGame {
+            game: Game::new(&random_deal(seed)),
+        }
+    }
+
+    // The 52 cards in dealing order, "AS 7D KC ..."
+    #[wasm_bindgen(js_name = fromCards)]
+    pub fn from_cards(cards: &str) -> Result<FreecellGame, JsError> {
+        Ok(FreecellGame {
+            game: Game::new(&parse_deck(cards)?),
+        })
+    }
+
+    // A PySolFC or fc-solve board
+    #[wasm_bindgen(js_name = fromBoard)]
+    pub fn from_board(text: &str) -> Result<FreecellGame, JsError> {
+        Ok(FreecellGame {
+            game: parse_board(text)?,
+        })
+    }
+
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<FreecellGame, JsError> {
+        Ok(FreecellGame {
+            game: serde_json::from_str(json)?,
+        })
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.game).unwrap_or_default()
+    }
+
+    #[wasm_bindgen(js_name = isWon)]
+    pub fn is_won(&self) -> bool {
+        self.game.is_won()
+    }
+
+    // Legal moves of the position, as generated by the solver
+    pub fn moves(&self) -> String {
+        let moves = Solver::new(self.game.clone()).get_moves(&self.game);
+        serde_json::to_string(&moves).unwrap_or_default()
+    }
+
+    // Plays one of the actions listed by `moves`
+    pub fn play(&mut self, action: &str) -> Result<(), JsError> {
+        let action: Action = serde_json::from_str(action)?;
+        self.game.apply(&action)?;
+        Ok(())
+    }
+
+    // Same document as `--output-format json`. The browser has a single thread, the
+    // node and time limits are what keeps a hard deal from freezing the page.
+    pub fn solve(
+        &self,
+        strategy: &str,
+        max_nodes: u32,
+        time_limit_ms: Option<f64>,
+    ) -> Result<String, JsError> {
+        let mut solver = Solver::new(self.game.clone());
+        solver.verbose = false;
+        if !strategy.is_empty() {
+            solver.strategy = strategy.parse::<Strategy>().map_err(|e| JsError::new(&e))?;
+        }
+        solver.time_limit = time_limit_ms.map(|ms| Duration::from_secs_f64(ms / 1000.0));
+
+        let steps = solver.solve(max_nodes).map(|solution| {
+            let solution = remove_wasted_moves(&self.game, &solution);
+            merge_supermoves(&self.game, &solution)
+        });
+        let json = solution_json(&self.game, steps.as_deref(), &solver.stats, false);
+        Ok(json.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_solve_from_js_calls() {
+        let mut game = FreecellGame::from_deal(1).ok().unwrap();
+        let moves: Vec<Action> = serde_json::from_str(&game.moves()).unwrap();
+        assert!(!moves.is_empty());
+
+        game.play(&serde_json::to_string(&moves[0]).unwrap())
+            .ok()
+            .unwrap();
+        let restored = FreecellGame::from_json(&game.to_json()).ok().unwrap();
+        assert_eq!(restored.game.layout_key(), game.game.layout_key());
+
+        let solution: serde_json::Value =
+            serde_json::from_str(&game.solve("astar", 100_000, None).ok().unwrap()).unwrap();
+        assert_eq!(solution["solved"], true);
+    }
+}